@@ -9,9 +9,9 @@
 use aesni;
 
 use aessafe;
-use blockmodes::{PaddingProcessor, EcbEncryptor, EcbDecryptor, CbcEncryptor, CbcDecryptor, CtrMode,
-    CtrModeX8};
-use symmetriccipher::{Encryptor, Decryptor, SynchronousStreamCipher};
+use blockmodes::{PaddingProcessor, EcbEncryptor, EcbEncryptorX8, EcbDecryptorX8, CbcEncryptor,
+    CbcDecryptorX8, CtrModeX8, CfbEncryptor, CfbDecryptor, Cfb8Encryptor, Cfb8Decryptor, OfbMode};
+use symmetriccipher::{Encryptor, Decryptor, SynchronousStreamCipher, CloneableStreamCipher};
 use util;
 
 /// AES key size
@@ -32,350 +32,1155 @@ pub fn ecb_encryptor<X: PaddingProcessor + Send>(
         match key_size {
             KeySize128 => {
                 let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let enc = box EcbEncryptorX8::new(aes_enc, aes_enc, padding);
+                enc as Box<Encryptor>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let enc = box EcbEncryptorX8::new(aes_enc, aes_enc, padding);
+                enc as Box<Encryptor>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let enc = box EcbEncryptorX8::new(aes_enc, aes_enc, padding);
+                enc as Box<Encryptor>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let enc = box EcbEncryptor::new(aes_enc, padding);
+                enc as Box<Encryptor>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let enc = box EcbEncryptor::new(aes_enc, padding);
+                enc as Box<Encryptor>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
                 let enc = box EcbEncryptor::new(aes_enc, padding);
                 enc as Box<Encryptor>
             }
+        }
+    }
+}
+
+/// Get the best implementation of an EcbEncryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn ecb_encryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        padding: X) -> Box<Encryptor> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let enc = box EcbEncryptor::new(aes_enc, padding);
+            enc as Box<Encryptor>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let enc = box EcbEncryptor::new(aes_enc, padding);
+            enc as Box<Encryptor>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let enc = box EcbEncryptor::new(aes_enc, padding);
+            enc as Box<Encryptor>
+        }
+    }
+}
+
+/// Get the best implementation of an EcbDecryptor
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn ecb_decryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        padding: X) -> Box<Decryptor> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aesni::AesNi128Decryptor::new(key);
+                let dec = box EcbDecryptorX8::new(aes_dec, aes_dec, padding);
+                dec as Box<Decryptor>
+            }
+            KeySize192 => {
+                let aes_dec = aesni::AesNi192Decryptor::new(key);
+                let dec = box EcbDecryptorX8::new(aes_dec, aes_dec, padding);
+                dec as Box<Decryptor>
+            }
+            KeySize256 => {
+                let aes_dec = aesni::AesNi256Decryptor::new(key);
+                let dec = box EcbDecryptorX8::new(aes_dec, aes_dec, padding);
+                dec as Box<Decryptor>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aessafe::AesSafe128Decryptor::new(key);
+                let aes_dec_x8 = aessafe::AesSafe128DecryptorX8::new(key);
+                let dec = box EcbDecryptorX8::new(aes_dec, aes_dec_x8, padding);
+                dec as Box<Decryptor>
+            }
+            KeySize192 => {
+                let aes_dec = aessafe::AesSafe192Decryptor::new(key);
+                let aes_dec_x8 = aessafe::AesSafe192DecryptorX8::new(key);
+                let dec = box EcbDecryptorX8::new(aes_dec, aes_dec_x8, padding);
+                dec as Box<Decryptor>
+            }
+            KeySize256 => {
+                let aes_dec = aessafe::AesSafe256Decryptor::new(key);
+                let aes_dec_x8 = aessafe::AesSafe256DecryptorX8::new(key);
+                let dec = box EcbDecryptorX8::new(aes_dec, aes_dec_x8, padding);
+                dec as Box<Decryptor>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of an EcbDecryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn ecb_decryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        padding: X) -> Box<Decryptor> {
+    match key_size {
+        KeySize128 => {
+            let aes_dec = aessafe::AesSafe128Decryptor::new(key);
+            let aes_dec_x8 = aessafe::AesSafe128DecryptorX8::new(key);
+            let dec = box EcbDecryptorX8::new(aes_dec, aes_dec_x8, padding);
+            dec as Box<Decryptor>
+        }
+        KeySize192 => {
+            let aes_dec = aessafe::AesSafe192Decryptor::new(key);
+            let aes_dec_x8 = aessafe::AesSafe192DecryptorX8::new(key);
+            let dec = box EcbDecryptorX8::new(aes_dec, aes_dec_x8, padding);
+            dec as Box<Decryptor>
+        }
+        KeySize256 => {
+            let aes_dec = aessafe::AesSafe256Decryptor::new(key);
+            let aes_dec_x8 = aessafe::AesSafe256DecryptorX8::new(key);
+            let dec = box EcbDecryptorX8::new(aes_dec, aes_dec_x8, padding);
+            dec as Box<Decryptor>
+        }
+    }
+}
+
+/// Get the best implementation of a CbcEncryptor
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cbc_encryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        padding: X) -> Box<Encryptor> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+                enc as Box<Encryptor>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+                enc as Box<Encryptor>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+                enc as Box<Encryptor>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+                enc as Box<Encryptor>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+                enc as Box<Encryptor>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+                enc as Box<Encryptor>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CbcEncryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cbc_encryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        padding: X) -> Box<Encryptor> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+            enc as Box<Encryptor>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+            enc as Box<Encryptor>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
+            enc as Box<Encryptor>
+        }
+    }
+}
+
+/// Get the best implementation of a CbcDecryptor
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cbc_decryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        padding: X) -> Box<Decryptor> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aesni::AesNi128Decryptor::new(key);
+                let dec = box CbcDecryptorX8::new(aes_dec, aes_dec, padding, Vec::from_slice(iv));
+                dec as Box<Decryptor>
+            }
+            KeySize192 => {
+                let aes_dec = aesni::AesNi192Decryptor::new(key);
+                let dec = box CbcDecryptorX8::new(aes_dec, aes_dec, padding, Vec::from_slice(iv));
+                dec as Box<Decryptor>
+            }
+            KeySize256 => {
+                let aes_dec = aesni::AesNi256Decryptor::new(key);
+                let dec = box CbcDecryptorX8::new(aes_dec, aes_dec, padding, Vec::from_slice(iv));
+                dec as Box<Decryptor>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aessafe::AesSafe128Decryptor::new(key);
+                let aes_dec_x8 = aessafe::AesSafe128DecryptorX8::new(key);
+                let dec = box CbcDecryptorX8::new(
+                    aes_dec,
+                    aes_dec_x8,
+                    padding,
+                    Vec::from_slice(iv));
+                dec as Box<Decryptor>
+            }
+            KeySize192 => {
+                let aes_dec = aessafe::AesSafe192Decryptor::new(key);
+                let aes_dec_x8 = aessafe::AesSafe192DecryptorX8::new(key);
+                let dec = box CbcDecryptorX8::new(
+                    aes_dec,
+                    aes_dec_x8,
+                    padding,
+                    Vec::from_slice(iv));
+                dec as Box<Decryptor>
+            }
+            KeySize256 => {
+                let aes_dec = aessafe::AesSafe256Decryptor::new(key);
+                let aes_dec_x8 = aessafe::AesSafe256DecryptorX8::new(key);
+                let dec = box CbcDecryptorX8::new(
+                    aes_dec,
+                    aes_dec_x8,
+                    padding,
+                    Vec::from_slice(iv));
+                dec as Box<Decryptor>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CbcDecryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cbc_decryptor<X: PaddingProcessor + Send>(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8],
+        padding: X) -> Box<Decryptor> {
+    match key_size {
+        KeySize128 => {
+            let aes_dec = aessafe::AesSafe128Decryptor::new(key);
+            let aes_dec_x8 = aessafe::AesSafe128DecryptorX8::new(key);
+            let dec = box CbcDecryptorX8::new(aes_dec, aes_dec_x8, padding, Vec::from_slice(iv));
+            dec as Box<Decryptor>
+        }
+        KeySize192 => {
+            let aes_dec = aessafe::AesSafe192Decryptor::new(key);
+            let aes_dec_x8 = aessafe::AesSafe192DecryptorX8::new(key);
+            let dec = box CbcDecryptorX8::new(aes_dec, aes_dec_x8, padding, Vec::from_slice(iv));
+            dec as Box<Decryptor>
+        }
+        KeySize256 => {
+            let aes_dec = aessafe::AesSafe256Decryptor::new(key);
+            let aes_dec_x8 = aessafe::AesSafe256DecryptorX8::new(key);
+            let dec = box CbcDecryptorX8::new(aes_dec, aes_dec_x8, padding, Vec::from_slice(iv));
+            dec as Box<Decryptor>
+        }
+    }
+}
+
+/// Get the best implementation of a Ctr
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn ctr(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aesni::AesNi128Encryptor::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_dec = aesni::AesNi192Encryptor::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_dec = aesni::AesNi256Encryptor::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<SynchronousStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<SynchronousStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a Ctr
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn ctr(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
+            let dec = box CtrModeX8::new(aes_dec, iv);
+            dec as Box<SynchronousStreamCipher>
+        }
+        KeySize192 => {
+            let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
+            let dec = box CtrModeX8::new(aes_dec, iv);
+            dec as Box<SynchronousStreamCipher>
+        }
+        KeySize256 => {
+            let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
+            let dec = box CtrModeX8::new(aes_dec, iv);
+            dec as Box<SynchronousStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a Ctr that can be cloned
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn ctr_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aesni::AesNi128Encryptor::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize192 => {
+                let aes_dec = aesni::AesNi192Encryptor::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize256 => {
+                let aes_dec = aesni::AesNi256Encryptor::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<CloneableStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize192 => {
+                let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize256 => {
+                let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
+                let dec = box CtrModeX8::new(aes_dec, iv);
+                dec as Box<CloneableStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a Ctr that can be cloned
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn ctr_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
+            let dec = box CtrModeX8::new(aes_dec, iv);
+            dec as Box<CloneableStreamCipher>
+        }
+        KeySize192 => {
+            let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
+            let dec = box CtrModeX8::new(aes_dec, iv);
+            dec as Box<CloneableStreamCipher>
+        }
+        KeySize256 => {
+            let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
+            let dec = box CtrModeX8::new(aes_dec, iv);
+            dec as Box<CloneableStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a CfbEncryptor
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cfb_encryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CfbEncryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cfb_encryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<SynchronousStreamCipher>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<SynchronousStreamCipher>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<SynchronousStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a CfbEncryptor that can be cloned
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cfb_encryptor_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CfbEncryptor that can be cloned
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cfb_encryptor_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<CloneableStreamCipher>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<CloneableStreamCipher>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let enc = box CfbEncryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<CloneableStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a CfbDecryptor
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cfb_decryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CfbDecryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cfb_decryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<SynchronousStreamCipher>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<SynchronousStreamCipher>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<SynchronousStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a CfbDecryptor that can be cloned
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cfb_decryptor_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a CfbDecryptor that can be cloned
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cfb_decryptor_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<CloneableStreamCipher>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<CloneableStreamCipher>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let dec = box CfbDecryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<CloneableStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a Cfb8Encryptor
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cfb8_encryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+        }
+    } else {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize192 => {
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+            KeySize256 => {
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<SynchronousStreamCipher>
+            }
+        }
+    }
+}
+
+/// Get the best implementation of a Cfb8Encryptor
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+pub fn cfb8_encryptor(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+    match key_size {
+        KeySize128 => {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<SynchronousStreamCipher>
+        }
+        KeySize192 => {
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<SynchronousStreamCipher>
+        }
+        KeySize256 => {
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<SynchronousStreamCipher>
+        }
+    }
+}
+
+/// Get the best implementation of a Cfb8Encryptor that can be cloned
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn cfb8_encryptor_cloneable(
+        key_size: KeySize,
+        key: &[u8],
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
+    if util::supports_aesni() {
+        match key_size {
+            KeySize128 => {
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
+            }
             KeySize192 => {
                 let aes_enc = aesni::AesNi192Encryptor::new(key);
-                let enc = box EcbEncryptor::new(aes_enc, padding);
-                enc as Box<Encryptor>
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
             }
             KeySize256 => {
                 let aes_enc = aesni::AesNi256Encryptor::new(key);
-                let enc = box EcbEncryptor::new(aes_enc, padding);
-                enc as Box<Encryptor>
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
             }
         }
     } else {
         match key_size {
             KeySize128 => {
                 let aes_enc = aessafe::AesSafe128Encryptor::new(key);
-                let enc = box EcbEncryptor::new(aes_enc, padding);
-                enc as Box<Encryptor>
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
             }
             KeySize192 => {
                 let aes_enc = aessafe::AesSafe192Encryptor::new(key);
-                let enc = box EcbEncryptor::new(aes_enc, padding);
-                enc as Box<Encryptor>
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
             }
             KeySize256 => {
                 let aes_enc = aessafe::AesSafe256Encryptor::new(key);
-                let enc = box EcbEncryptor::new(aes_enc, padding);
-                enc as Box<Encryptor>
+                let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+                enc as Box<CloneableStreamCipher>
             }
         }
     }
 }
 
-/// Get the best implementation of an EcbEncryptor
+/// Get the best implementation of a Cfb8Encryptor that can be cloned
 #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
-pub fn ecb_encryptor<X: PaddingProcessor + Send>(
+pub fn cfb8_encryptor_cloneable(
         key_size: KeySize,
         key: &[u8],
-        padding: X) -> Box<Encryptor> {
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
     match key_size {
         KeySize128 => {
             let aes_enc = aessafe::AesSafe128Encryptor::new(key);
-            let enc = box EcbEncryptor::new(aes_enc, padding);
-            enc as Box<Encryptor>
+            let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<CloneableStreamCipher>
         }
         KeySize192 => {
             let aes_enc = aessafe::AesSafe192Encryptor::new(key);
-            let enc = box EcbEncryptor::new(aes_enc, padding);
-            enc as Box<Encryptor>
+            let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<CloneableStreamCipher>
         }
         KeySize256 => {
             let aes_enc = aessafe::AesSafe256Encryptor::new(key);
-            let enc = box EcbEncryptor::new(aes_enc, padding);
-            enc as Box<Encryptor>
+            let enc = box Cfb8Encryptor::new(aes_enc, Vec::from_slice(iv));
+            enc as Box<CloneableStreamCipher>
         }
     }
 }
 
-/// Get the best implementation of an EcbDecryptor
+/// Get the best implementation of a Cfb8Decryptor
 #[cfg(target_arch = "x86")]
 #[cfg(target_arch = "x86_64")]
-pub fn ecb_decryptor<X: PaddingProcessor + Send>(
+pub fn cfb8_decryptor(
         key_size: KeySize,
         key: &[u8],
-        padding: X) -> Box<Decryptor> {
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
     if util::supports_aesni() {
         match key_size {
             KeySize128 => {
-                let aes_dec = aesni::AesNi128Decryptor::new(key);
-                let dec = box EcbDecryptor::new(aes_dec, padding);
-                dec as Box<Decryptor>
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
             }
             KeySize192 => {
-                let aes_dec = aesni::AesNi192Decryptor::new(key);
-                let dec = box EcbDecryptor::new(aes_dec, padding);
-                dec as Box<Decryptor>
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
             }
             KeySize256 => {
-                let aes_dec = aesni::AesNi256Decryptor::new(key);
-                let dec = box EcbDecryptor::new(aes_dec, padding);
-                dec as Box<Decryptor>
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
             }
         }
     } else {
         match key_size {
             KeySize128 => {
-                let aes_dec = aessafe::AesSafe128Decryptor::new(key);
-                let dec = box EcbDecryptor::new(aes_dec, padding);
-                dec as Box<Decryptor>
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
             }
             KeySize192 => {
-                let aes_dec = aessafe::AesSafe192Decryptor::new(key);
-                let dec = box EcbDecryptor::new(aes_dec, padding);
-                dec as Box<Decryptor>
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
             }
             KeySize256 => {
-                let aes_dec = aessafe::AesSafe256Decryptor::new(key);
-                let dec = box EcbDecryptor::new(aes_dec, padding);
-                dec as Box<Decryptor>
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<SynchronousStreamCipher>
             }
         }
     }
 }
 
-/// Get the best implementation of an EcbDecryptor
+/// Get the best implementation of a Cfb8Decryptor
 #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
-pub fn ecb_decryptor<X: PaddingProcessor + Send>(
+pub fn cfb8_decryptor(
         key_size: KeySize,
         key: &[u8],
-        padding: X) -> Box<Decryptor> {
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
     match key_size {
         KeySize128 => {
-            let aes_dec = aessafe::AesSafe128Decryptor::new(key);
-            let dec = box EcbDecryptor::new(aes_dec, padding);
-            dec as Box<Decryptor>
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<SynchronousStreamCipher>
         }
         KeySize192 => {
-            let aes_dec = aessafe::AesSafe192Decryptor::new(key);
-            let dec = box EcbDecryptor::new(aes_dec, padding);
-            dec as Box<Decryptor>
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<SynchronousStreamCipher>
         }
         KeySize256 => {
-            let aes_dec = aessafe::AesSafe256Decryptor::new(key);
-            let dec = box EcbDecryptor::new(aes_dec, padding);
-            dec as Box<Decryptor>
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<SynchronousStreamCipher>
         }
     }
 }
 
-/// Get the best implementation of a CbcEncryptor
+/// Get the best implementation of a Cfb8Decryptor that can be cloned
 #[cfg(target_arch = "x86")]
 #[cfg(target_arch = "x86_64")]
-pub fn cbc_encryptor<X: PaddingProcessor + Send>(
+pub fn cfb8_decryptor_cloneable(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8],
-        padding: X) -> Box<Encryptor> {
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
     if util::supports_aesni() {
         match key_size {
             KeySize128 => {
                 let aes_enc = aesni::AesNi128Encryptor::new(key);
-                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-                enc as Box<Encryptor>
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
             }
             KeySize192 => {
                 let aes_enc = aesni::AesNi192Encryptor::new(key);
-                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-                enc as Box<Encryptor>
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
             }
             KeySize256 => {
                 let aes_enc = aesni::AesNi256Encryptor::new(key);
-                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-                enc as Box<Encryptor>
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
             }
         }
     } else {
         match key_size {
             KeySize128 => {
                 let aes_enc = aessafe::AesSafe128Encryptor::new(key);
-                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-                enc as Box<Encryptor>
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
             }
             KeySize192 => {
                 let aes_enc = aessafe::AesSafe192Encryptor::new(key);
-                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-                enc as Box<Encryptor>
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
             }
             KeySize256 => {
                 let aes_enc = aessafe::AesSafe256Encryptor::new(key);
-                let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-                enc as Box<Encryptor>
+                let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+                dec as Box<CloneableStreamCipher>
             }
         }
     }
 }
 
-/// Get the best implementation of a CbcEncryptor
+/// Get the best implementation of a Cfb8Decryptor that can be cloned
 #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
-pub fn cbc_encryptor<X: PaddingProcessor + Send>(
+pub fn cfb8_decryptor_cloneable(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8],
-        padding: X) -> Box<Encryptor> {
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
     match key_size {
         KeySize128 => {
             let aes_enc = aessafe::AesSafe128Encryptor::new(key);
-            let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-            enc as Box<Encryptor>
+            let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<CloneableStreamCipher>
         }
         KeySize192 => {
             let aes_enc = aessafe::AesSafe192Encryptor::new(key);
-            let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-            enc as Box<Encryptor>
+            let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<CloneableStreamCipher>
         }
         KeySize256 => {
             let aes_enc = aessafe::AesSafe256Encryptor::new(key);
-            let enc = box CbcEncryptor::new(aes_enc, padding, Vec::from_slice(iv));
-            enc as Box<Encryptor>
+            let dec = box Cfb8Decryptor::new(aes_enc, Vec::from_slice(iv));
+            dec as Box<CloneableStreamCipher>
         }
     }
 }
 
-/// Get the best implementation of a CbcDecryptor
+/// Get the best implementation of an Ofb
 #[cfg(target_arch = "x86")]
 #[cfg(target_arch = "x86_64")]
-pub fn cbc_decryptor<X: PaddingProcessor + Send>(
+pub fn ofb(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8],
-        padding: X) -> Box<Decryptor> {
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
     if util::supports_aesni() {
         match key_size {
             KeySize128 => {
-                let aes_dec = aesni::AesNi128Decryptor::new(key);
-                let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-                dec as Box<Decryptor>
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<SynchronousStreamCipher>
             }
             KeySize192 => {
-                let aes_dec = aesni::AesNi192Decryptor::new(key);
-                let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-                dec as Box<Decryptor>
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<SynchronousStreamCipher>
             }
             KeySize256 => {
-                let aes_dec = aesni::AesNi256Decryptor::new(key);
-                let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-                dec as Box<Decryptor>
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<SynchronousStreamCipher>
             }
         }
     } else {
         match key_size {
             KeySize128 => {
-                let aes_dec = aessafe::AesSafe128Decryptor::new(key);
-                let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-                dec as Box<Decryptor>
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<SynchronousStreamCipher>
             }
             KeySize192 => {
-                let aes_dec = aessafe::AesSafe192Decryptor::new(key);
-                let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-                dec as Box<Decryptor>
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<SynchronousStreamCipher>
             }
             KeySize256 => {
-                let aes_dec = aessafe::AesSafe256Decryptor::new(key);
-                let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-                dec as Box<Decryptor>
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<SynchronousStreamCipher>
             }
         }
     }
 }
 
-/// Get the best implementation of a CbcDecryptor
+/// Get the best implementation of an Ofb
 #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
-pub fn cbc_decryptor<X: PaddingProcessor + Send>(
+pub fn ofb(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8],
-        padding: X) -> Box<Decryptor> {
+        iv: &[u8]) -> Box<SynchronousStreamCipher> {
     match key_size {
         KeySize128 => {
-            let aes_dec = aessafe::AesSafe128Decryptor::new(key);
-            let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-            dec as Box<Decryptor>
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+            ofb as Box<SynchronousStreamCipher>
         }
         KeySize192 => {
-            let aes_dec = aessafe::AesSafe192Decryptor::new(key);
-            let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-            dec as Box<Decryptor>
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+            ofb as Box<SynchronousStreamCipher>
         }
         KeySize256 => {
-            let aes_dec = aessafe::AesSafe256Decryptor::new(key);
-            let dec = box CbcDecryptor::new(aes_dec, padding, Vec::from_slice(iv));
-            dec as Box<Decryptor>
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+            ofb as Box<SynchronousStreamCipher>
         }
     }
 }
 
-/// Get the best implementation of a Ctr
+/// Get the best implementation of an Ofb that can be cloned
 #[cfg(target_arch = "x86")]
 #[cfg(target_arch = "x86_64")]
-pub fn ctr(
+pub fn ofb_cloneable(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
     if util::supports_aesni() {
         match key_size {
             KeySize128 => {
-                let aes_dec = aesni::AesNi128Encryptor::new(key);
-                let dec = box CtrMode::new(aes_dec, Vec::from_slice(iv));
-                dec as Box<SynchronousStreamCipher>
+                let aes_enc = aesni::AesNi128Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<CloneableStreamCipher>
             }
             KeySize192 => {
-                let aes_dec = aesni::AesNi192Encryptor::new(key);
-                let dec = box CtrMode::new(aes_dec, Vec::from_slice(iv));
-                dec as Box<SynchronousStreamCipher>
+                let aes_enc = aesni::AesNi192Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<CloneableStreamCipher>
             }
             KeySize256 => {
-                let aes_dec = aesni::AesNi256Encryptor::new(key);
-                let dec = box CtrMode::new(aes_dec, Vec::from_slice(iv));
-                dec as Box<SynchronousStreamCipher>
+                let aes_enc = aesni::AesNi256Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<CloneableStreamCipher>
             }
         }
     } else {
         match key_size {
             KeySize128 => {
-                let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
-                let dec = box CtrModeX8::new(aes_dec, iv);
-                dec as Box<SynchronousStreamCipher>
+                let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<CloneableStreamCipher>
             }
             KeySize192 => {
-                let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
-                let dec = box CtrModeX8::new(aes_dec, iv);
-                dec as Box<SynchronousStreamCipher>
+                let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<CloneableStreamCipher>
             }
             KeySize256 => {
-                let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
-                let dec = box CtrModeX8::new(aes_dec, iv);
-                dec as Box<SynchronousStreamCipher>
+                let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+                let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+                ofb as Box<CloneableStreamCipher>
             }
         }
     }
 }
 
-/// Get the best implementation of a Ctr
+/// Get the best implementation of an Ofb that can be cloned
 #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
-pub fn ctr(
+pub fn ofb_cloneable(
         key_size: KeySize,
         key: &[u8],
-        iv: &[u8]) -> Box<SynchronousStreamCipher> {
+        iv: &[u8]) -> Box<CloneableStreamCipher> {
     match key_size {
         KeySize128 => {
-            let aes_dec = aessafe::AesSafe128EncryptorX8::new(key);
-            let dec = box CtrModeX8::new(aes_dec, iv);
-            dec as Box<SynchronousStreamCipher>
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+            ofb as Box<CloneableStreamCipher>
         }
         KeySize192 => {
-            let aes_dec = aessafe::AesSafe192EncryptorX8::new(key);
-            let dec = box CtrModeX8::new(aes_dec, iv);
-            dec as Box<SynchronousStreamCipher>
+            let aes_enc = aessafe::AesSafe192Encryptor::new(key);
+            let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+            ofb as Box<CloneableStreamCipher>
         }
         KeySize256 => {
-            let aes_dec = aessafe::AesSafe256EncryptorX8::new(key);
-            let dec = box CtrModeX8::new(aes_dec, iv);
-            dec as Box<SynchronousStreamCipher>
+            let aes_enc = aessafe::AesSafe256Encryptor::new(key);
+            let ofb = box OfbMode::new(aes_enc, Vec::from_slice(iv));
+            ofb as Box<CloneableStreamCipher>
         }
     }
 }
@@ -386,8 +1191,12 @@ mod test {
     #[cfg(target_arch = "x86_64")]
     use aesni;
 
+    use aes::{ctr_cloneable, cfb_encryptor_cloneable, cfb_decryptor_cloneable,
+        cfb8_encryptor_cloneable, cfb8_decryptor_cloneable, ofb_cloneable, KeySize128};
     use aessafe;
-    use symmetriccipher::{BlockEncryptor, BlockDecryptor, BlockEncryptorX8, BlockDecryptorX8};
+    use blockmodes::{CtrModeX8, CfbEncryptor, CfbDecryptor, OfbMode};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor, BlockEncryptorX8, BlockDecryptorX8,
+        CloneableStreamCipher};
     use util;
 
     // Test vectors from:
@@ -562,6 +1371,65 @@ mod test {
         }
     }
 
+    fn run_test_x8<E: BlockEncryptorX8, D: BlockDecryptorX8>(enc: &E, dec: &D, test: &Test) {
+        // Each test has 4 blocks of data, so it is repeated twice to fill up all 8 blocks
+        let mut plain = Vec::new();
+        let mut cipher = Vec::new();
+        for _ in range(0u, 2) {
+            for data in test.data.iter() {
+                plain.push_all(data.plain.as_slice());
+                cipher.push_all(data.cipher.as_slice());
+            }
+        }
+        let mut tmp = [0u8, ..128];
+        enc.encrypt_block_x8(plain.as_slice(), tmp);
+        assert!(tmp == cipher.as_slice());
+        dec.decrypt_block_x8(cipher.as_slice(), tmp);
+        assert!(tmp == plain.as_slice());
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_aesni_128_x8() {
+        if util::supports_aesni() {
+            let tests = tests128();
+            for t in tests.iter() {
+                let enc = aesni::AesNi128Encryptor::new(t.key.as_slice());
+                let dec = aesni::AesNi128Decryptor::new(t.key.as_slice());
+                run_test_x8(&enc, &dec, t);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_aesni_192_x8() {
+        if util::supports_aesni() {
+            let tests = tests192();
+            for t in tests.iter() {
+                let enc = aesni::AesNi192Encryptor::new(t.key.as_slice());
+                let dec = aesni::AesNi192Decryptor::new(t.key.as_slice());
+                run_test_x8(&enc, &dec, t);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_aesni_256_x8() {
+        if util::supports_aesni() {
+            let tests = tests256();
+            for t in tests.iter() {
+                let enc = aesni::AesNi256Encryptor::new(t.key.as_slice());
+                let dec = aesni::AesNi256Decryptor::new(t.key.as_slice());
+                run_test_x8(&enc, &dec, t);
+            }
+        }
+    }
+
     #[test]
     fn test_aessafe_128() {
         let tests = tests128();
@@ -742,6 +1610,77 @@ mod test {
         dec.decrypt_block_x8(cipher, tmp);
         assert!(tmp == plain);
     }
+
+    fn process_fresh(c: &mut Box<CloneableStreamCipher>, prefix: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::from_elem(prefix.len() + data.len(), 0u8);
+        c.process(prefix, out.mut_slice_to(prefix.len()));
+        c.process(data, out.mut_slice_from(prefix.len()));
+        Vec::from_slice(out.slice_from(prefix.len()))
+    }
+
+    /// Process a prefix that ends partway through a block, clone the cipher and then feed
+    /// different data to the original and to the copy. Each of them must produce the same output
+    /// as a fresh cipher that processed the prefix followed by its own data.
+    fn run_clone_test(new_cipher: || -> Box<CloneableStreamCipher>) {
+        let prefix = Vec::from_fn(37, |i| i as u8);
+        let data1 = Vec::from_fn(100, |i| (i * 3) as u8);
+        let data2 = Vec::from_fn(150, |i| (i * 7 + 1) as u8);
+
+        let mut c1 = new_cipher();
+        let mut tmp = Vec::from_elem(prefix.len(), 0u8);
+        c1.process(prefix.as_slice(), tmp.as_mut_slice());
+        let mut c2 = c1.clone();
+
+        let mut out1 = Vec::from_elem(data1.len(), 0u8);
+        c1.process(data1.as_slice(), out1.as_mut_slice());
+        let mut out2 = Vec::from_elem(data2.len(), 0u8);
+        c2.process(data2.as_slice(), out2.as_mut_slice());
+
+        assert!(out1 == process_fresh(&mut new_cipher(), prefix.as_slice(), data1.as_slice()));
+        assert!(out2 == process_fresh(&mut new_cipher(), prefix.as_slice(), data2.as_slice()));
+    }
+
+    #[test]
+    fn test_ctr_clone() {
+        run_clone_test(|| ctr_cloneable(KeySize128, [1u8, ..16], [2u8, ..16]));
+    }
+
+    #[test]
+    fn test_cfb_clone() {
+        run_clone_test(|| cfb_encryptor_cloneable(KeySize128, [1u8, ..16], [2u8, ..16]));
+        run_clone_test(|| cfb_decryptor_cloneable(KeySize128, [1u8, ..16], [2u8, ..16]));
+        run_clone_test(|| cfb8_encryptor_cloneable(KeySize128, [1u8, ..16], [2u8, ..16]));
+        run_clone_test(|| cfb8_decryptor_cloneable(KeySize128, [1u8, ..16], [2u8, ..16]));
+    }
+
+    #[test]
+    fn test_ofb_clone() {
+        run_clone_test(|| ofb_cloneable(KeySize128, [1u8, ..16], [2u8, ..16]));
+    }
+
+    // The functions above pick AES-NI when it is available, so build the modes on top of the
+    // portable implementation as well.
+    #[test]
+    fn test_aessafe_clone() {
+        let key = [1u8, ..16];
+        let iv = [2u8, ..16];
+        run_clone_test(|| {
+            let aes_enc = aessafe::AesSafe128EncryptorX8::new(key);
+            box CtrModeX8::new(aes_enc, iv) as Box<CloneableStreamCipher>
+        });
+        run_clone_test(|| {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            box CfbEncryptor::new(aes_enc, Vec::from_slice(iv)) as Box<CloneableStreamCipher>
+        });
+        run_clone_test(|| {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            box CfbDecryptor::new(aes_enc, Vec::from_slice(iv)) as Box<CloneableStreamCipher>
+        });
+        run_clone_test(|| {
+            let aes_enc = aessafe::AesSafe128Encryptor::new(key);
+            box OfbMode::new(aes_enc, Vec::from_slice(iv)) as Box<CloneableStreamCipher>
+        });
+    }
 }
 
 #[cfg(test)]
@@ -776,6 +1715,26 @@ mod bench {
         }
     }
 
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[bench]
+    pub fn aesni_x8_bench(bh: &mut Bencher) {
+        if util::supports_aesni() {
+            let key: [u8, ..16] = [1u8, ..16];
+            let plain: [u8, ..128] = [2u8, ..128];
+
+            let a = aesni::AesNi128Encryptor::new(key);
+
+            let mut tmp = [0u8, ..128];
+
+            bh.iter( || {
+                a.encrypt_block_x8(plain, tmp);
+            });
+
+            bh.bytes = (plain.len()) as u64;
+        }
+    }
+
     #[bench]
     pub fn aes_safe_bench(bh: &mut Bencher) {
         let key: [u8, ..16] = [1u8, ..16];
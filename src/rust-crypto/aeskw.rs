@@ -0,0 +1,434 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * This module implements the AES Key Wrap algorithm from RFC 3394 and the AES Key Wrap with Padding
+ * algorithm from RFC 5649. Both are used to protect keys under a key encryption key (KEK). The
+ * functions here accept any 128 bit block cipher, which in practice means one of the AES
+ * implementations from the aesni or aessafe modules.
+ */
+
+use std::slice;
+
+use cryptoutil::{read_u32_be, read_u64_be, write_u32_be, write_u64_be};
+use symmetriccipher::{BlockEncryptor, BlockDecryptor, SymmetricCipherError, InvalidLength,
+    InvalidPadding};
+use util::fixed_time_eq;
+
+/// The default initial value from RFC 3394
+static DEFAULT_IV: [u8, ..8] = [0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6];
+
+/// The constant first half of the alternative initial value from RFC 5649. The second half is the
+/// length of the key in bytes.
+static ALTERNATIVE_IV_PREFIX: [u8, ..4] = [0xa6, 0x59, 0x59, 0xa6];
+
+// The wrapping process W from RFC 3394. data holds the initial value A followed by the n 64 bit
+// registers R[1] to R[n] and is transformed in place.
+fn wrap_core<E: BlockEncryptor>(kek: &E, data: &mut [u8]) {
+    let n = data.len() / 8 - 1;
+    let mut block = [0u8, ..16];
+    let mut out = [0u8, ..16];
+    slice::bytes::copy_memory(block.mut_slice_to(8), data.slice_to(8));
+    for j in range(0u, 6) {
+        for i in range(1u, n + 1) {
+            slice::bytes::copy_memory(block.mut_slice_from(8), data.slice(8 * i, 8 * i + 8));
+            kek.encrypt_block(block, out);
+            let a = read_u64_be(out.slice_to(8)) ^ ((n * j + i) as u64);
+            write_u64_be(block.mut_slice_to(8), a);
+            slice::bytes::copy_memory(data.mut_slice(8 * i, 8 * i + 8), out.slice_from(8));
+        }
+    }
+    slice::bytes::copy_memory(data.mut_slice_to(8), block.slice_to(8));
+}
+
+// The unwrapping process W^-1 from RFC 3394. This is the exact inverse of wrap_core(); checking
+// the recovered initial value is left to the caller.
+fn unwrap_core<D: BlockDecryptor>(kek: &D, data: &mut [u8]) {
+    let n = data.len() / 8 - 1;
+    let mut block = [0u8, ..16];
+    let mut out = [0u8, ..16];
+    slice::bytes::copy_memory(block.mut_slice_to(8), data.slice_to(8));
+    for j in range(0u, 6).rev() {
+        for i in range(1u, n + 1).rev() {
+            let a = read_u64_be(block.slice_to(8)) ^ ((n * j + i) as u64);
+            write_u64_be(block.mut_slice_to(8), a);
+            slice::bytes::copy_memory(block.mut_slice_from(8), data.slice(8 * i, 8 * i + 8));
+            kek.decrypt_block(block, out);
+            slice::bytes::copy_memory(block.mut_slice_to(8), out.slice_to(8));
+            slice::bytes::copy_memory(data.mut_slice(8 * i, 8 * i + 8), out.slice_from(8));
+        }
+    }
+    slice::bytes::copy_memory(data.mut_slice_to(8), block.slice_to(8));
+}
+
+/// Wrap a key using the AES Key Wrap algorithm from RFC 3394. The key must be a multiple of 8 bytes
+/// long and at least 16 bytes; otherwise, InvalidLength is returned. The wrapped key is 8 bytes
+/// longer than the key.
+pub fn wrap_key<E: BlockEncryptor>(kek: &E, key: &[u8]) -> Result<Vec<u8>, SymmetricCipherError> {
+    assert!(kek.block_size() == 16);
+    if key.len() < 16 || key.len() % 8 != 0 {
+        return Err(InvalidLength);
+    }
+    let mut data = Vec::from_elem(key.len() + 8, 0u8);
+    slice::bytes::copy_memory(data.mut_slice_to(8), DEFAULT_IV);
+    slice::bytes::copy_memory(data.mut_slice_from(8), key);
+    wrap_core(kek, data.as_mut_slice());
+    Ok(data)
+}
+
+/// Unwrap a key that was wrapped with the AES Key Wrap algorithm from RFC 3394. InvalidLength is
+/// returned if the wrapped key can't possibly be valid and InvalidPadding is returned if the
+/// integrity check fails, which happens if the wrong KEK is used or the data was modified.
+pub fn unwrap_key<D: BlockDecryptor>(
+        kek: &D,
+        wrapped: &[u8]) -> Result<Vec<u8>, SymmetricCipherError> {
+    assert!(kek.block_size() == 16);
+    if wrapped.len() < 24 || wrapped.len() % 8 != 0 {
+        return Err(InvalidLength);
+    }
+    let mut data = Vec::from_slice(wrapped);
+    unwrap_core(kek, data.as_mut_slice());
+    if !fixed_time_eq(data.slice_to(8), DEFAULT_IV) {
+        return Err(InvalidPadding);
+    }
+    Ok(Vec::from_slice(data.slice_from(8)))
+}
+
+/// Wrap a key using the AES Key Wrap with Padding algorithm from RFC 5649. The key may be any
+/// length from 1 byte up to 2^32 - 1 bytes. The wrapped key is the length of the key rounded up
+/// to a multiple of 8 bytes plus another 8 bytes.
+pub fn wrap_key_with_padding<E: BlockEncryptor>(
+        kek: &E,
+        key: &[u8]) -> Result<Vec<u8>, SymmetricCipherError> {
+    assert!(kek.block_size() == 16);
+    if key.len() == 0 || key.len() as u64 > 0xffffffff {
+        return Err(InvalidLength);
+    }
+    let padded_len = (key.len() + 7) / 8 * 8;
+    let mut data = Vec::from_elem(padded_len + 8, 0u8);
+    slice::bytes::copy_memory(data.mut_slice_to(4), ALTERNATIVE_IV_PREFIX);
+    write_u32_be(data.mut_slice(4, 8), key.len() as u32);
+    slice::bytes::copy_memory(data.mut_slice_from(8), key);
+    if padded_len == 8 {
+        // A single block is simply encrypted
+        let mut out = [0u8, ..16];
+        kek.encrypt_block(data.as_slice(), out);
+        slice::bytes::copy_memory(data.as_mut_slice(), out);
+    } else {
+        wrap_core(kek, data.as_mut_slice());
+    }
+    Ok(data)
+}
+
+/// Unwrap a key that was wrapped with the AES Key Wrap with Padding algorithm from RFC 5649.
+/// InvalidLength is returned if the wrapped key can't possibly be valid and InvalidPadding is
+/// returned if the alternative initial value or the padding fail to check out.
+pub fn unwrap_key_with_padding<D: BlockDecryptor>(
+        kek: &D,
+        wrapped: &[u8]) -> Result<Vec<u8>, SymmetricCipherError> {
+    assert!(kek.block_size() == 16);
+    if wrapped.len() < 16 || wrapped.len() % 8 != 0 {
+        return Err(InvalidLength);
+    }
+    let mut data = Vec::from_slice(wrapped);
+    if wrapped.len() == 16 {
+        kek.decrypt_block(wrapped, data.as_mut_slice());
+    } else {
+        unwrap_core(kek, data.as_mut_slice());
+    }
+
+    // The message length indicator must place the end of the key in the last 8 byte block and all
+    // of the padding bytes after it must be 0.
+    let padded_len = wrapped.len() - 8;
+    let key_len = read_u32_be(data.slice(4, 8)) as uint;
+    if !fixed_time_eq(data.slice_to(4), ALTERNATIVE_IV_PREFIX) ||
+            key_len + 8 <= padded_len || key_len > padded_len {
+        return Err(InvalidPadding);
+    }
+    let mut pad = 0u8;
+    for &x in data.slice_from(8 + key_len).iter() {
+        pad |= x;
+    }
+    if pad != 0 {
+        return Err(InvalidPadding);
+    }
+    Ok(Vec::from_slice(data.slice(8, 8 + key_len)))
+}
+
+#[cfg(test)]
+mod test {
+    use aeskw::{wrap_key, unwrap_key, wrap_key_with_padding, unwrap_key_with_padding};
+    use aessafe::{AesSafe128Encryptor, AesSafe128Decryptor, AesSafe192Encryptor,
+        AesSafe192Decryptor, AesSafe256Encryptor, AesSafe256Decryptor};
+    use symmetriccipher::{BlockEncryptor, BlockDecryptor, InvalidLength, InvalidPadding};
+
+    struct Test {
+        kek: Vec<u8>,
+        key: Vec<u8>,
+        wrapped: Vec<u8>
+    }
+
+    fn kw_tests() -> Vec<Test> {
+        vec![
+            // RFC 3394 Section 4.1
+            Test {
+                kek: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f ],
+                key: vec![
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff ],
+                wrapped: vec![
+                    0x1f, 0xa6, 0x8b, 0x0a, 0x81, 0x12, 0xb4, 0x47,
+                    0xae, 0xf3, 0x4b, 0xd8, 0xfb, 0x5a, 0x7b, 0x82,
+                    0x9d, 0x3e, 0x86, 0x23, 0x71, 0xd2, 0xcf, 0xe5 ]
+            },
+            // RFC 3394 Section 4.2
+            Test {
+                kek: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+                    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17 ],
+                key: vec![
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff ],
+                wrapped: vec![
+                    0x96, 0x77, 0x8b, 0x25, 0xae, 0x6c, 0xa4, 0x35,
+                    0xf9, 0x2b, 0x5b, 0x97, 0xc0, 0x50, 0xae, 0xd2,
+                    0x46, 0x8a, 0xb8, 0xa1, 0x7a, 0xd8, 0x4e, 0x5d ]
+            },
+            // RFC 3394 Section 4.3
+            Test {
+                kek: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+                    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+                    0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f ],
+                key: vec![
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff ],
+                wrapped: vec![
+                    0x64, 0xe8, 0xc3, 0xf9, 0xce, 0x0f, 0x5b, 0xa2,
+                    0x63, 0xe9, 0x77, 0x79, 0x05, 0x81, 0x8a, 0x2a,
+                    0x93, 0xc8, 0x19, 0x1e, 0x7d, 0x6e, 0x8a, 0xe7 ]
+            },
+            // RFC 3394 Section 4.4
+            Test {
+                kek: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+                    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17 ],
+                key: vec![
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07 ],
+                wrapped: vec![
+                    0x03, 0x1d, 0x33, 0x26, 0x4e, 0x15, 0xd3, 0x32,
+                    0x68, 0xf2, 0x4e, 0xc2, 0x60, 0x74, 0x3e, 0xdc,
+                    0xe1, 0xc6, 0xc7, 0xdd, 0xee, 0x72, 0x5a, 0x93,
+                    0x6b, 0xa8, 0x14, 0x91, 0x5c, 0x67, 0x62, 0xd2 ]
+            },
+            // RFC 3394 Section 4.5
+            Test {
+                kek: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+                    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+                    0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f ],
+                key: vec![
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07 ],
+                wrapped: vec![
+                    0xa8, 0xf9, 0xbc, 0x16, 0x12, 0xc6, 0x8b, 0x3f,
+                    0xf6, 0xe6, 0xf4, 0xfb, 0xe3, 0x0e, 0x71, 0xe4,
+                    0x76, 0x9c, 0x8b, 0x80, 0xa3, 0x2c, 0xb8, 0x95,
+                    0x8c, 0xd5, 0xd1, 0x7d, 0x6b, 0x25, 0x4d, 0xa1 ]
+            },
+            // RFC 3394 Section 4.6
+            Test {
+                kek: vec![
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+                    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17,
+                    0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f ],
+                key: vec![
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+                    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07,
+                    0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f ],
+                wrapped: vec![
+                    0x28, 0xc9, 0xf4, 0x04, 0xc4, 0xb8, 0x10, 0xf4,
+                    0xcb, 0xcc, 0xb3, 0x5c, 0xfb, 0x87, 0xf8, 0x26,
+                    0x3f, 0x57, 0x86, 0xe2, 0xd8, 0x0e, 0xd3, 0x26,
+                    0xcb, 0xc7, 0xf0, 0xe7, 0x1a, 0x99, 0xf4, 0x3b,
+                    0xfb, 0x98, 0x8b, 0x9b, 0x7a, 0x02, 0xdd, 0x21 ]
+            }
+        ]
+    }
+
+    fn kwp_tests() -> Vec<Test> {
+        vec![
+            // RFC 5649 Section 6
+            Test {
+                kek: vec![
+                    0x58, 0x40, 0xdf, 0x6e, 0x29, 0xb0, 0x2a, 0xf1,
+                    0xab, 0x49, 0x3b, 0x70, 0x5b, 0xf1, 0x6e, 0xa1,
+                    0xae, 0x83, 0x38, 0xf4, 0xdc, 0xc1, 0x76, 0xa8 ],
+                key: vec![
+                    0xc3, 0x7b, 0x7e, 0x64, 0x92, 0x58, 0x43, 0x40,
+                    0xbe, 0xd1, 0x22, 0x07, 0x80, 0x89, 0x41, 0x15,
+                    0x50, 0x68, 0xf7, 0x38 ],
+                wrapped: vec![
+                    0x13, 0x8b, 0xde, 0xaa, 0x9b, 0x8f, 0xa7, 0xfc,
+                    0x61, 0xf9, 0x77, 0x42, 0xe7, 0x22, 0x48, 0xee,
+                    0x5a, 0xe6, 0xae, 0x53, 0x60, 0xd1, 0xae, 0x6a,
+                    0x5f, 0x54, 0xf3, 0x73, 0xfa, 0x54, 0x3b, 0x6a ]
+            },
+            Test {
+                kek: vec![
+                    0x58, 0x40, 0xdf, 0x6e, 0x29, 0xb0, 0x2a, 0xf1,
+                    0xab, 0x49, 0x3b, 0x70, 0x5b, 0xf1, 0x6e, 0xa1,
+                    0xae, 0x83, 0x38, 0xf4, 0xdc, 0xc1, 0x76, 0xa8 ],
+                key: vec![
+                    0x46, 0x6f, 0x72, 0x50, 0x61, 0x73, 0x69 ],
+                wrapped: vec![
+                    0xaf, 0xbe, 0xb0, 0xf0, 0x7d, 0xfb, 0xf5, 0x41,
+                    0x92, 0x00, 0xf2, 0xcc, 0xb5, 0x0b, 0xb2, 0x4f ]
+            },
+            // NIST CAVP KWP_AE_128.txt, KWP_AE_192.txt and KWP_AE_256.txt
+            Test {
+                kek: vec![
+                    0x78, 0x65, 0xe2, 0x0f, 0x3c, 0x21, 0x65, 0x9a,
+                    0xb4, 0x69, 0x0b, 0x62, 0x9c, 0xdf, 0x3c, 0xc4 ],
+                key: vec![
+                    0xbd, 0x68, 0x43, 0xd4, 0x20, 0x37, 0x8d, 0xc8,
+                    0x96 ],
+                wrapped: vec![
+                    0x41, 0xec, 0xa9, 0x56, 0xd4, 0xaa, 0x04, 0x7e,
+                    0xb5, 0xcf, 0x4e, 0xfe, 0x65, 0x96, 0x61, 0xe7,
+                    0x4d, 0xb6, 0xf8, 0xc5, 0x64, 0xe2, 0x35, 0x00 ]
+            },
+            Test {
+                kek: vec![
+                    0xf5, 0xf8, 0x96, 0xa3, 0xbd, 0x2f, 0x4a, 0x98,
+                    0x23, 0xef, 0x16, 0x2b, 0x00, 0xb8, 0x05, 0xd7,
+                    0xde, 0x1e, 0xa4, 0x66, 0x26, 0x96, 0xa2, 0x58 ],
+                key: vec![
+                    0x6c, 0xcd, 0xd5, 0x85, 0x18, 0x40, 0x97, 0xeb,
+                    0xd5, 0xc3, 0xaf, 0x3e, 0x47, 0xd0, 0x2c, 0x19,
+                    0x14, 0x7b, 0x4d, 0x99, 0x5f, 0x96, 0x43, 0x66,
+                    0x91, 0x56, 0x75, 0x8c, 0x13, 0x16, 0x8f ],
+                wrapped: vec![
+                    0x4e, 0x9b, 0xc2, 0xbc, 0xbc, 0x6c, 0x1e, 0x13,
+                    0xd3, 0x35, 0xbc, 0xc0, 0xf7, 0x73, 0x6a, 0x88,
+                    0xfa, 0x87, 0x53, 0x66, 0x15, 0xbb, 0x8e, 0x63,
+                    0x8b, 0xcc, 0x81, 0x66, 0x84, 0x68, 0x17, 0x90,
+                    0x67, 0xcf, 0xa9, 0x8a, 0x9d, 0x0e, 0x33, 0x26 ]
+            },
+            Test {
+                kek: vec![
+                    0x95, 0xda, 0x27, 0x00, 0xca, 0x6f, 0xd9, 0xa5,
+                    0x25, 0x54, 0xee, 0x2a, 0x8d, 0xf1, 0x38, 0x6f,
+                    0x5b, 0x94, 0xa1, 0xa6, 0x0e, 0xd8, 0xa4, 0xae,
+                    0xf6, 0x0a, 0x8d, 0x61, 0xab, 0x5f, 0x22, 0x5a ],
+                key: vec![
+                    0xd1 ],
+                wrapped: vec![
+                    0x06, 0xba, 0x7a, 0xe6, 0xf3, 0x24, 0x8c, 0xfd,
+                    0xcf, 0x26, 0x75, 0x07, 0xfa, 0x00, 0x1b, 0xc4 ]
+            }
+        ]
+    }
+
+    fn run_test<E: BlockEncryptor, D: BlockDecryptor>(enc: &E, dec: &D, test: &Test, padded: bool) {
+        let wrapped = if padded {
+            wrap_key_with_padding(enc, test.key.as_slice())
+        } else {
+            wrap_key(enc, test.key.as_slice())
+        };
+        assert!(wrapped.ok().unwrap() == test.wrapped);
+
+        let key = if padded {
+            unwrap_key_with_padding(dec, test.wrapped.as_slice())
+        } else {
+            unwrap_key(dec, test.wrapped.as_slice())
+        };
+        assert!(key.ok().unwrap() == test.key);
+
+        // Flipping any bit of the wrapped key must cause the integrity check to fail
+        let mut bad = test.wrapped.clone();
+        for i in range(0, bad.len()) {
+            bad.as_mut_slice()[i] ^= 0x80;
+            let result = if padded {
+                unwrap_key_with_padding(dec, bad.as_slice())
+            } else {
+                unwrap_key(dec, bad.as_slice())
+            };
+            match result {
+                Err(InvalidPadding) => {}
+                _ => fail!("Modified wrapped key was accepted")
+            }
+            bad.as_mut_slice()[i] ^= 0x80;
+        }
+    }
+
+    fn run_tests(tests: Vec<Test>, padded: bool) {
+        for test in tests.iter() {
+            let kek = test.kek.as_slice();
+            match kek.len() {
+                16 => run_test(
+                    &AesSafe128Encryptor::new(kek), &AesSafe128Decryptor::new(kek), test, padded),
+                24 => run_test(
+                    &AesSafe192Encryptor::new(kek), &AesSafe192Decryptor::new(kek), test, padded),
+                32 => run_test(
+                    &AesSafe256Encryptor::new(kek), &AesSafe256Decryptor::new(kek), test, padded),
+                _ => fail!("Invalid KEK length")
+            }
+        }
+    }
+
+    #[test]
+    fn test_aes_kw() {
+        run_tests(kw_tests(), false);
+    }
+
+    #[test]
+    fn test_aes_kwp() {
+        run_tests(kwp_tests(), true);
+    }
+
+    #[test]
+    fn test_invalid_lengths() {
+        let kek = [0u8, ..16];
+        let enc = AesSafe128Encryptor::new(kek);
+        let dec = AesSafe128Decryptor::new(kek);
+        let data = [0u8, ..40];
+
+        for &len in [0u, 8, 15, 17].iter() {
+            match wrap_key(&enc, data.slice_to(len)) {
+                Err(InvalidLength) => {}
+                _ => fail!("Wrapped a key with an invalid length")
+            }
+        }
+        for &len in [0u, 8, 16, 23, 25].iter() {
+            match unwrap_key(&dec, data.slice_to(len)) {
+                Err(InvalidLength) => {}
+                _ => fail!("Unwrapped a key with an invalid length")
+            }
+        }
+        match wrap_key_with_padding(&enc, &[]) {
+            Err(InvalidLength) => {}
+            _ => fail!("Wrapped an empty key")
+        }
+        for &len in [0u, 8, 15, 17].iter() {
+            match unwrap_key_with_padding(&dec, data.slice_to(len)) {
+                Err(InvalidLength) => {}
+                _ => fail!("Unwrapped a key with an invalid length")
+            }
+        }
+    }
+}
@@ -4,16 +4,24 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use symmetriccipher::{BlockEncryptor, BlockDecryptor};
+use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
 
 pub struct AesNi128Encryptor {
     round_keys: [u8, ..16 * (10 + 1)]
 }
 
+impl Clone for AesNi128Encryptor {
+    fn clone(&self) -> AesNi128Encryptor { *self }
+}
+
 pub struct AesNi128Decryptor {
     round_keys: [u8, ..16 * (10 + 1)]
 }
 
+impl Clone for AesNi128Decryptor {
+    fn clone(&self) -> AesNi128Decryptor { *self }
+}
+
 impl AesNi128Encryptor {
     pub fn new(key: &[u8]) -> AesNi128Encryptor {
         let mut e = AesNi128Encryptor {
@@ -48,14 +56,36 @@ impl BlockDecryptor for AesNi128Decryptor {
     }
 }
 
+impl BlockEncryptorX8 for AesNi128Encryptor {
+    fn block_size(&self) -> uint { 16 }
+    fn encrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        encrypt_block_x8_aseni(10, input, self.round_keys, output);
+    }
+}
+
+impl BlockDecryptorX8 for AesNi128Decryptor {
+    fn block_size(&self) -> uint { 16 }
+    fn decrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        decrypt_block_x8_aseni(10, input, self.round_keys, output);
+    }
+}
+
 pub struct AesNi192Encryptor {
     round_keys: [u8, ..16 * (12 + 1)]
 }
 
+impl Clone for AesNi192Encryptor {
+    fn clone(&self) -> AesNi192Encryptor { *self }
+}
+
 pub struct AesNi192Decryptor {
     round_keys: [u8, ..16 * (12 + 1)]
 }
 
+impl Clone for AesNi192Decryptor {
+    fn clone(&self) -> AesNi192Decryptor { *self }
+}
+
 impl AesNi192Encryptor {
     pub fn new(key: &[u8]) -> AesNi192Encryptor {
         let mut e = AesNi192Encryptor {
@@ -90,14 +120,36 @@ impl BlockDecryptor for AesNi192Decryptor {
     }
 }
 
+impl BlockEncryptorX8 for AesNi192Encryptor {
+    fn block_size(&self) -> uint { 16 }
+    fn encrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        encrypt_block_x8_aseni(12, input, self.round_keys, output);
+    }
+}
+
+impl BlockDecryptorX8 for AesNi192Decryptor {
+    fn block_size(&self) -> uint { 16 }
+    fn decrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        decrypt_block_x8_aseni(12, input, self.round_keys, output);
+    }
+}
+
 pub struct AesNi256Encryptor {
     round_keys: [u8, ..16 * (14 + 1)]
 }
 
+impl Clone for AesNi256Encryptor {
+    fn clone(&self) -> AesNi256Encryptor { *self }
+}
+
 pub struct AesNi256Decryptor {
     round_keys: [u8, ..16 * (14 + 1)]
 }
 
+impl Clone for AesNi256Decryptor {
+    fn clone(&self) -> AesNi256Decryptor { *self }
+}
+
 impl AesNi256Encryptor {
     pub fn new(key: &[u8]) -> AesNi256Encryptor {
         let mut e = AesNi256Encryptor {
@@ -132,6 +184,20 @@ impl BlockDecryptor for AesNi256Decryptor {
     }
 }
 
+impl BlockEncryptorX8 for AesNi256Encryptor {
+    fn block_size(&self) -> uint { 16 }
+    fn encrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        encrypt_block_x8_aseni(14, input, self.round_keys, output);
+    }
+}
+
+impl BlockDecryptorX8 for AesNi256Decryptor {
+    fn block_size(&self) -> uint { 16 }
+    fn decrypt_block_x8(&self, input: &[u8], output: &mut [u8]) {
+        decrypt_block_x8_aseni(14, input, self.round_keys, output);
+    }
+}
+
 enum KeyType {
     Encryption,
     Decryption
@@ -522,3 +588,309 @@ fn decrypt_block_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &m
         );
     }
 }
+
+#[cfg(target_arch = "x86_64")]
+#[inline(never)]
+#[allow(dead_assignment)]
+fn encrypt_block_x8_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    assert!(input.len() >= 128 && output.len() >= 128);
+    unsafe {
+        let mut rounds = rounds;
+        let mut round_keysp: *const u8 = round_keys.unsafe_ref(0);
+        let outp: *mut u8 = output.unsafe_mut_ref(0);
+        let inp: *const u8 = input.unsafe_ref(0);
+
+        asm!(
+        "
+            /* Copy the data to encrypt to xmm1 - xmm8 */
+            movdqu ($2), %xmm1
+            movdqu 16($2), %xmm2
+            movdqu 32($2), %xmm3
+            movdqu 48($2), %xmm4
+            movdqu 64($2), %xmm5
+            movdqu 80($2), %xmm6
+            movdqu 96($2), %xmm7
+            movdqu 112($2), %xmm8
+
+            /* Perform round 0 - the whitening step */
+            movdqu ($1), %xmm0
+            add $$0x10, $1
+            pxor %xmm0, %xmm1
+            pxor %xmm0, %xmm2
+            pxor %xmm0, %xmm3
+            pxor %xmm0, %xmm4
+            pxor %xmm0, %xmm5
+            pxor %xmm0, %xmm6
+            pxor %xmm0, %xmm7
+            pxor %xmm0, %xmm8
+
+            /* Perform all remaining rounds (except the final one). Each round key is
+               applied to all of the blocks before moving on to the next one so that
+               the instructions for the different blocks can be pipelined. */
+            enc_round_x8:
+            movdqu ($1), %xmm0
+            add $$0x10, $1
+            aesenc %xmm0, %xmm1
+            aesenc %xmm0, %xmm2
+            aesenc %xmm0, %xmm3
+            aesenc %xmm0, %xmm4
+            aesenc %xmm0, %xmm5
+            aesenc %xmm0, %xmm6
+            aesenc %xmm0, %xmm7
+            aesenc %xmm0, %xmm8
+            sub $$0x01, $0
+            cmp $$0x01, $0
+            jne enc_round_x8
+
+            /* Perform the last round */
+            movdqu ($1), %xmm0
+            aesenclast %xmm0, %xmm1
+            aesenclast %xmm0, %xmm2
+            aesenclast %xmm0, %xmm3
+            aesenclast %xmm0, %xmm4
+            aesenclast %xmm0, %xmm5
+            aesenclast %xmm0, %xmm6
+            aesenclast %xmm0, %xmm7
+            aesenclast %xmm0, %xmm8
+
+            /* Finally, move the results from xmm1 - xmm8 to outp */
+            movdqu %xmm1, ($3)
+            movdqu %xmm2, 16($3)
+            movdqu %xmm3, 32($3)
+            movdqu %xmm4, 48($3)
+            movdqu %xmm5, 64($3)
+            movdqu %xmm6, 80($3)
+            movdqu %xmm7, 96($3)
+            movdqu %xmm8, 112($3)
+        "
+        : "=r" (rounds), "=r" (round_keysp) // outputs
+        : "r" (inp), "r" (outp), "0" (rounds), "1" (round_keysp) // inputs
+        : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8",
+          "memory", "cc" // clobbers
+        : "volatile" // options
+        );
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(never)]
+#[allow(dead_assignment)]
+fn decrypt_block_x8_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    assert!(input.len() >= 128 && output.len() >= 128);
+    unsafe {
+        let mut rounds = rounds;
+        let mut round_keysp: *const u8 = round_keys.unsafe_ref(round_keys.len() - 16);
+        let outp: *mut u8 = output.unsafe_mut_ref(0);
+        let inp: *const u8 = input.unsafe_ref(0);
+
+        asm!(
+        "
+            /* Copy the data to decrypt to xmm1 - xmm8 */
+            movdqu ($2), %xmm1
+            movdqu 16($2), %xmm2
+            movdqu 32($2), %xmm3
+            movdqu 48($2), %xmm4
+            movdqu 64($2), %xmm5
+            movdqu 80($2), %xmm6
+            movdqu 96($2), %xmm7
+            movdqu 112($2), %xmm8
+
+            /* Perform round 0 - the whitening step */
+            movdqu ($1), %xmm0
+            sub $$0x10, $1
+            pxor %xmm0, %xmm1
+            pxor %xmm0, %xmm2
+            pxor %xmm0, %xmm3
+            pxor %xmm0, %xmm4
+            pxor %xmm0, %xmm5
+            pxor %xmm0, %xmm6
+            pxor %xmm0, %xmm7
+            pxor %xmm0, %xmm8
+
+            /* Perform all remaining rounds (except the final one). Each round key is
+               applied to all of the blocks before moving on to the next one so that
+               the instructions for the different blocks can be pipelined. */
+            dec_round_x8:
+            movdqu ($1), %xmm0
+            sub $$0x10, $1
+            aesdec %xmm0, %xmm1
+            aesdec %xmm0, %xmm2
+            aesdec %xmm0, %xmm3
+            aesdec %xmm0, %xmm4
+            aesdec %xmm0, %xmm5
+            aesdec %xmm0, %xmm6
+            aesdec %xmm0, %xmm7
+            aesdec %xmm0, %xmm8
+            sub $$0x01, $0
+            cmp $$0x01, $0
+            jne dec_round_x8
+
+            /* Perform the last round */
+            movdqu ($1), %xmm0
+            aesdeclast %xmm0, %xmm1
+            aesdeclast %xmm0, %xmm2
+            aesdeclast %xmm0, %xmm3
+            aesdeclast %xmm0, %xmm4
+            aesdeclast %xmm0, %xmm5
+            aesdeclast %xmm0, %xmm6
+            aesdeclast %xmm0, %xmm7
+            aesdeclast %xmm0, %xmm8
+
+            /* Finally, move the results from xmm1 - xmm8 to outp */
+            movdqu %xmm1, ($3)
+            movdqu %xmm2, 16($3)
+            movdqu %xmm3, 32($3)
+            movdqu %xmm4, 48($3)
+            movdqu %xmm5, 64($3)
+            movdqu %xmm6, 80($3)
+            movdqu %xmm7, 96($3)
+            movdqu %xmm8, 112($3)
+        "
+        : "=r" (rounds), "=r" (round_keysp) // outputs
+        : "r" (inp), "r" (outp), "0" (rounds), "1" (round_keysp) // inputs
+        : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8",
+          "memory", "cc" // clobbers
+        : "volatile" // options
+        );
+    }
+}
+
+#[cfg(target_arch = "x86")]
+#[inline(never)]
+#[allow(dead_assignment)]
+fn encrypt_block_x4_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    assert!(input.len() >= 64 && output.len() >= 64);
+    unsafe {
+        let mut rounds = rounds;
+        let mut round_keysp: *const u8 = round_keys.unsafe_ref(0);
+        let outp: *mut u8 = output.unsafe_mut_ref(0);
+        let inp: *const u8 = input.unsafe_ref(0);
+
+        asm!(
+        "
+            /* Copy the data to encrypt to xmm1 - xmm4 */
+            movdqu ($2), %xmm1
+            movdqu 16($2), %xmm2
+            movdqu 32($2), %xmm3
+            movdqu 48($2), %xmm4
+
+            /* Perform round 0 - the whitening step */
+            movdqu ($1), %xmm0
+            add $$0x10, $1
+            pxor %xmm0, %xmm1
+            pxor %xmm0, %xmm2
+            pxor %xmm0, %xmm3
+            pxor %xmm0, %xmm4
+
+            /* Perform all remaining rounds (except the final one). Each round key is
+               applied to all of the blocks before moving on to the next one so that
+               the instructions for the different blocks can be pipelined. */
+            enc_round_x4:
+            movdqu ($1), %xmm0
+            add $$0x10, $1
+            aesenc %xmm0, %xmm1
+            aesenc %xmm0, %xmm2
+            aesenc %xmm0, %xmm3
+            aesenc %xmm0, %xmm4
+            sub $$0x01, $0
+            cmp $$0x01, $0
+            jne enc_round_x4
+
+            /* Perform the last round */
+            movdqu ($1), %xmm0
+            aesenclast %xmm0, %xmm1
+            aesenclast %xmm0, %xmm2
+            aesenclast %xmm0, %xmm3
+            aesenclast %xmm0, %xmm4
+
+            /* Finally, move the results from xmm1 - xmm4 to outp */
+            movdqu %xmm1, ($3)
+            movdqu %xmm2, 16($3)
+            movdqu %xmm3, 32($3)
+            movdqu %xmm4, 48($3)
+        "
+        : "=r" (rounds), "=r" (round_keysp) // outputs
+        : "r" (inp), "r" (outp), "0" (rounds), "1" (round_keysp) // inputs
+        : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "memory", "cc" // clobbers
+        : "volatile" // options
+        );
+    }
+}
+
+#[cfg(target_arch = "x86")]
+#[inline(never)]
+#[allow(dead_assignment)]
+fn decrypt_block_x4_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    assert!(input.len() >= 64 && output.len() >= 64);
+    unsafe {
+        let mut rounds = rounds;
+        let mut round_keysp: *const u8 = round_keys.unsafe_ref(round_keys.len() - 16);
+        let outp: *mut u8 = output.unsafe_mut_ref(0);
+        let inp: *const u8 = input.unsafe_ref(0);
+
+        asm!(
+        "
+            /* Copy the data to decrypt to xmm1 - xmm4 */
+            movdqu ($2), %xmm1
+            movdqu 16($2), %xmm2
+            movdqu 32($2), %xmm3
+            movdqu 48($2), %xmm4
+
+            /* Perform round 0 - the whitening step */
+            movdqu ($1), %xmm0
+            sub $$0x10, $1
+            pxor %xmm0, %xmm1
+            pxor %xmm0, %xmm2
+            pxor %xmm0, %xmm3
+            pxor %xmm0, %xmm4
+
+            /* Perform all remaining rounds (except the final one). Each round key is
+               applied to all of the blocks before moving on to the next one so that
+               the instructions for the different blocks can be pipelined. */
+            dec_round_x4:
+            movdqu ($1), %xmm0
+            sub $$0x10, $1
+            aesdec %xmm0, %xmm1
+            aesdec %xmm0, %xmm2
+            aesdec %xmm0, %xmm3
+            aesdec %xmm0, %xmm4
+            sub $$0x01, $0
+            cmp $$0x01, $0
+            jne dec_round_x4
+
+            /* Perform the last round */
+            movdqu ($1), %xmm0
+            aesdeclast %xmm0, %xmm1
+            aesdeclast %xmm0, %xmm2
+            aesdeclast %xmm0, %xmm3
+            aesdeclast %xmm0, %xmm4
+
+            /* Finally, move the results from xmm1 - xmm4 to outp */
+            movdqu %xmm1, ($3)
+            movdqu %xmm2, 16($3)
+            movdqu %xmm3, 32($3)
+            movdqu %xmm4, 48($3)
+        "
+        : "=r" (rounds), "=r" (round_keysp) // outputs
+        : "r" (inp), "r" (outp), "0" (rounds), "1" (round_keysp) // inputs
+        : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "memory", "cc" // clobbers
+        : "volatile" // options
+        );
+    }
+}
+
+// 32-bit x86 only has 8 xmm registers, which isn't enough to hold 8 blocks plus a round key, so the
+// blocks are processed 4 at a time.
+#[cfg(target_arch = "x86")]
+fn encrypt_block_x8_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    encrypt_block_x4_aseni(rounds, input.slice_to(64), round_keys, output.mut_slice_to(64));
+    encrypt_block_x4_aseni(rounds, input.slice_from(64), round_keys, output.mut_slice_from(64));
+}
+
+// 32-bit x86 only has 8 xmm registers, which isn't enough to hold 8 blocks plus a round key, so the
+// blocks are processed 4 at a time.
+#[cfg(target_arch = "x86")]
+fn decrypt_block_x8_aseni(rounds: uint, input: &[u8], round_keys: &[u8], output: &mut [u8]) {
+    decrypt_block_x4_aseni(rounds, input.slice_to(64), round_keys, output.mut_slice_to(64));
+    decrypt_block_x4_aseni(rounds, input.slice_from(64), round_keys, output.mut_slice_from(64));
+}
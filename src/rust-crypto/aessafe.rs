@@ -129,13 +129,7 @@ use std::iter::range_step;
 use cryptoutil::{read_u32v_le, write_u32_le};
 use symmetriccipher::{BlockEncryptor, BlockEncryptorX8, BlockDecryptor, BlockDecryptorX8};
 
-// Using std::unstable::simd::u32x4 results in issues creating static arrays of u32x4 values.
-// Defining the type here avoids that problem. Additionally, we need to implement various trait from
-// libstd which wouldn't be possible if we used that type directly.
-#[simd]
-#[deriving(Clone, Eq, PartialEq)]
-#[allow(non_camel_case_types)]
-pub struct u32x4(u32, u32, u32, u32);
+pub use simd::u32x4;
 
 // There are a variety of places where we need to use u32x4 types with either all bits set or not
 // bits set. These macros make that more succinct.
@@ -150,6 +144,11 @@ macro_rules! define_aes_struct(
         pub struct $name {
             sk: [Bs8State<u32>, ..$rounds + 1]
         }
+
+        // Clone can't be derived since fixed size arrays don't implement it.
+        impl Clone for $name {
+            fn clone(&self) -> $name { *self }
+        }
     )
 )
 
@@ -237,6 +236,11 @@ macro_rules! define_aes_struct_x8(
         pub struct $name {
             sk: [Bs8State<u32x4>, ..$rounds + 1]
         }
+
+        // Clone can't be derived since fixed size arrays don't implement it.
+        impl Clone for $name {
+            fn clone(&self) -> $name { *self }
+        }
     )
 )
 
@@ -1177,44 +1181,6 @@ impl AesBitValueOps for u32 {
     }
 }
 
-impl u32x4 {
-    fn lsh(&self, s: uint) -> u32x4 {
-        let u32x4(a0, a1, a2, a3) = *self;
-        return u32x4(
-            a0 << s,
-            (a1 << s) | (a0 >> (32 - s)),
-            (a2 << s) | (a1 >> (32 - s)),
-            (a3 << s) | (a2 >> (32 - s)));
-    }
-
-    fn rsh(&self, s: uint) -> u32x4 {
-        let u32x4(a0, a1, a2, a3) = *self;
-        return u32x4(
-            (a0 >> s) | (a1 << (32 - s)),
-            (a1 >> s) | (a2 << (32 - s)),
-            (a2 >> s) | (a3 << (32 - s)),
-            a3 >> s);
-    }
-}
-
-impl BitXor<u32x4, u32x4> for u32x4 {
-    fn bitxor(&self, rhs: &u32x4) -> u32x4 {
-        return *self ^ *rhs;
-    }
-}
-
-impl BitAnd<u32x4, u32x4> for u32x4 {
-    fn bitand(&self, rhs: &u32x4) -> u32x4 {
-        return *self & *rhs;
-    }
-}
-
-impl Default for u32x4 {
-    fn default() -> u32x4 {
-        return u32x4(0, 0, 0, 0);
-    }
-}
-
 // Arrays to convert to and from a polynomial basis and a normal basis. The affine transformation
 // step is included in these matrices as well, so that doesn't have to be done seperately.
 static a2x_u32x4: [[u32x4, ..8], ..8] = [
@@ -96,6 +96,7 @@ impl <'a> ReadBuffer for RefReadBuffer<'a> {
     }
 }
 
+#[deriving(Clone)]
 pub struct OwnedReadBuffer {
     buff: Vec<u8>,
     len: uint,
@@ -7,6 +7,7 @@
 use buffer::{BufferResult, RefReadBuffer, RefWriteBuffer};
 use symmetriccipher::{Encryptor, Decryptor, SynchronousStreamCipher, SymmetricCipherError};
 use cryptoutil::{read_u32_le, symm_enc_or_dec, write_u32_le};
+use simd::u32x4;
 
 pub struct ChaCha20 {
     state  : [u32, ..16],
@@ -14,15 +15,96 @@ pub struct ChaCha20 {
     offset : uint,
 }
 
+impl Clone for ChaCha20 {
+    fn clone(&self) -> ChaCha20 { *self }
+}
+
+// The quarter round works on u32s as well as u32x4s.
 macro_rules! quater_round(
     ($a:expr, $b:expr, $c:expr, $d:expr) => ({
-        $a += $b; $d ^= $a; $d = $d.rotate_left(16);
-        $c += $d; $b ^= $c; $b = $b.rotate_left(12);
-        $a += $b; $d ^= $a; $d = $d.rotate_left( 8);
-        $c += $d; $b ^= $c; $b = $b.rotate_left( 7);
+        $a = $a + $b; $d = $d ^ $a; $d = $d.rotate_left(16);
+        $c = $c + $d; $b = $b ^ $c; $b = $b.rotate_left(12);
+        $a = $a + $b; $d = $d ^ $a; $d = $d.rotate_left( 8);
+        $c = $c + $d; $b = $b ^ $c; $b = $b.rotate_left( 7);
     });
 )
 
+// Generate a single block of keystream. The state is held as four rows of u32x4s, so each column
+// is in a separate lane and all four column quarter rounds run at once. Before the diagonal rounds,
+// the lanes of the last three rows are rotated so that the diagonals line up as columns, and
+// afterwards they are rotated back.
+fn chacha20_block(state: &[u32, ..16], output: &mut [u8]) {
+    let a0 = u32x4(state[ 0], state[ 1], state[ 2], state[ 3]);
+    let b0 = u32x4(state[ 4], state[ 5], state[ 6], state[ 7]);
+    let c0 = u32x4(state[ 8], state[ 9], state[10], state[11]);
+    let d0 = u32x4(state[12], state[13], state[14], state[15]);
+
+    let mut a = a0;
+    let mut b = b0;
+    let mut c = c0;
+    let mut d = d0;
+
+    for _ in range(0u, 10) {
+        quater_round!(a, b, c, d);
+        b = b.rotate_lanes_1();
+        c = c.rotate_lanes_2();
+        d = d.rotate_lanes_3();
+        quater_round!(a, b, c, d);
+        b = b.rotate_lanes_3();
+        c = c.rotate_lanes_2();
+        d = d.rotate_lanes_1();
+    }
+
+    let rows = [a + a0, b + b0, c + c0, d + d0];
+    for (i, row) in rows.iter().enumerate() {
+        let u32x4(w0, w1, w2, w3) = *row;
+        write_u32_le(output.mut_slice(i * 16     , i * 16 +  4), w0);
+        write_u32_le(output.mut_slice(i * 16 +  4, i * 16 +  8), w1);
+        write_u32_le(output.mut_slice(i * 16 +  8, i * 16 + 12), w2);
+        write_u32_le(output.mut_slice(i * 16 + 12, i * 16 + 16), w3);
+    }
+}
+
+// Generate 4 consecutive blocks of keystream at once. Each word of the state is held in a u32x4
+// with lane i belonging to the i-th block, so the rounds are exactly the same as for a single
+// block of u32s.
+fn chacha20_block_x4(state: &[u32, ..16], output: &mut [u8]) {
+    let mut s = [u32x4(0, 0, 0, 0), ..16];
+    for i in range(0u, 16) {
+        s[i] = u32x4(state[i], state[i], state[i], state[i]);
+    }
+
+    let ctr = (state[13] as u64 << 32) | state[12] as u64;
+    let ctrs = [ctr, ctr + 1, ctr + 2, ctr + 3];
+    s[12] = u32x4(ctrs[0] as u32, ctrs[1] as u32, ctrs[2] as u32, ctrs[3] as u32);
+    s[13] = u32x4(
+        (ctrs[0] >> 32) as u32,
+        (ctrs[1] >> 32) as u32,
+        (ctrs[2] >> 32) as u32,
+        (ctrs[3] >> 32) as u32);
+
+    let mut x = s;
+
+    for _ in range(0u, 10) {
+        quater_round!(x[0], x[4], x[ 8], x[12]);
+        quater_round!(x[1], x[5], x[ 9], x[13]);
+        quater_round!(x[2], x[6], x[10], x[14]);
+        quater_round!(x[3], x[7], x[11], x[15]);
+        quater_round!(x[0], x[5], x[10], x[15]);
+        quater_round!(x[1], x[6], x[11], x[12]);
+        quater_round!(x[2], x[7], x[ 8], x[13]);
+        quater_round!(x[3], x[4], x[ 9], x[14]);
+    }
+
+    for i in range(0u, 16) {
+        let u32x4(w0, w1, w2, w3) = x[i] + s[i];
+        write_u32_le(output.mut_slice(      i * 4,       i * 4 + 4), w0);
+        write_u32_le(output.mut_slice( 64 + i * 4,  64 + i * 4 + 4), w1);
+        write_u32_le(output.mut_slice(128 + i * 4, 128 + i * 4 + 4), w2);
+        write_u32_le(output.mut_slice(192 + i * 4, 192 + i * 4 + 4), w3);
+    }
+}
+
 impl ChaCha20 {
     pub fn new(key: &[u8], nonce: &[u8]) -> ChaCha20 {
         assert!(key.len() == 16 || key.len() == 32);
@@ -66,30 +148,18 @@ impl ChaCha20 {
         state
     }
 
-    // put the the next 64 keystream bytes into self.output
-    fn update(&mut self) {
-        let mut x = self.state;
-
-        for _ in range(0u, 10) {
-            quater_round!(x[0], x[4], x[ 8], x[12]);
-            quater_round!(x[1], x[5], x[ 9], x[13]);
-            quater_round!(x[2], x[6], x[10], x[14]);
-            quater_round!(x[3], x[7], x[11], x[15]);
-            quater_round!(x[0], x[5], x[10], x[15]);
-            quater_round!(x[1], x[6], x[11], x[12]);
-            quater_round!(x[2], x[7], x[ 8], x[13]);
-            quater_round!(x[3], x[4], x[ 9], x[14]);
-        }
-
-        for i in range(0, self.state.len()) {
-            write_u32_le(self.output.mut_slice(i*4, (i+1)*4), self.state[i] + x[i]);
-        }
-
-        self.state[12] += 1;
-        if self.state[12] == 0 {
+    fn add_to_counter(&mut self, n: u32) {
+        let old = self.state[12];
+        self.state[12] += n;
+        if self.state[12] < old {
             self.state[13] += 1;
         }
+    }
 
+    // put the the next 64 keystream bytes into self.output
+    fn update(&mut self) {
+        chacha20_block(&self.state, self.output);
+        self.add_to_counter(1);
         self.offset = 0;
     }
 
@@ -107,8 +177,25 @@ impl ChaCha20 {
 impl SynchronousStreamCipher for ChaCha20 {
     fn process(&mut self, input: &[u8], output: &mut [u8]) {
         assert!(input.len() == output.len());
-        for (x, y) in input.iter().zip(output.mut_iter()) {
-            *y = *x ^ self.next();
+        let len = input.len();
+        let mut i = 0;
+        while i < len {
+            // Once the buffered keystream is used up, generate 4 blocks at a time for as long as
+            // there is enough input left to use all of them.
+            if self.offset == 64 && len - i >= 256 {
+                let mut keystream = [0u8, ..256];
+                chacha20_block_x4(&self.state, keystream);
+                self.add_to_counter(4);
+                for ((x, y), k) in input.slice(i, i + 256).iter()
+                        .zip(output.mut_slice(i, i + 256).mut_iter())
+                        .zip(keystream.iter()) {
+                    *y = *x ^ *k;
+                }
+                i += 256;
+            } else {
+                output[i] = input[i] ^ self.next();
+                i += 1;
+            }
         }
     }
 }
@@ -129,9 +216,102 @@ impl Decryptor for ChaCha20 {
 
 #[cfg(test)]
 mod test {
-    use chacha20::ChaCha20;
+    use std::rand::{Rng, IsaacRng};
+
+    use chacha20::{ChaCha20, chacha20_block, chacha20_block_x4};
+    use cryptoutil::write_u32_le;
     use symmetriccipher::SynchronousStreamCipher;
 
+    // The original scalar implementation of the block function
+    fn chacha20_block_scalar(state: &[u32, ..16], output: &mut [u8]) {
+        let mut x = *state;
+
+        for _ in range(0u, 10) {
+            quater_round!(x[0], x[4], x[ 8], x[12]);
+            quater_round!(x[1], x[5], x[ 9], x[13]);
+            quater_round!(x[2], x[6], x[10], x[14]);
+            quater_round!(x[3], x[7], x[11], x[15]);
+            quater_round!(x[0], x[5], x[10], x[15]);
+            quater_round!(x[1], x[6], x[11], x[12]);
+            quater_round!(x[2], x[7], x[ 8], x[13]);
+            quater_round!(x[3], x[4], x[ 9], x[14]);
+        }
+
+        for i in range(0u, 16) {
+            write_u32_le(output.mut_slice(i*4, (i+1)*4), state[i] + x[i]);
+        }
+    }
+
+    #[test]
+    fn test_simd_block_matches_scalar() {
+        let mut rng = IsaacRng::new_unseeded();
+        for _ in range(0u, 100) {
+            let mut state = [0u32, ..16];
+            for w in state.mut_iter() {
+                *w = rng.gen();
+            }
+            // Make sure that the counter overflows into the next word in some of the blocks
+            state[12] = 0xfffffffe;
+
+            let mut expected = [0u8, ..256];
+            let mut tmp = state;
+            for i in range(0u, 4) {
+                chacha20_block_scalar(&tmp, expected.mut_slice(i * 64, (i + 1) * 64));
+                tmp[12] += 1;
+                if tmp[12] == 0 {
+                    tmp[13] += 1;
+                }
+            }
+
+            let mut out = [0u8, ..64];
+            chacha20_block(&state, out);
+            assert!(out.as_slice() == expected.slice(0, 64));
+
+            let mut out_x4 = [0u8, ..256];
+            chacha20_block_x4(&state, out_x4);
+            assert!(out_x4.as_slice() == expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_chacha20_x4_process() {
+        // Processing a long input at once uses the 4 block path while processing it a byte at a
+        // time only uses the single block path - the results must be identical.
+        let key = [7u8, ..32];
+        let nonce = [9u8, ..8];
+        let input = Vec::from_fn(1000, |i| i as u8);
+
+        let mut c1 = ChaCha20::new(key, nonce);
+        let mut out1 = Vec::from_elem(input.len(), 0u8);
+        c1.process(input.as_slice(), out1.as_mut_slice());
+
+        let mut c2 = ChaCha20::new(key, nonce);
+        let mut out2 = Vec::from_elem(input.len(), 0u8);
+        for i in range(0u, input.len()) {
+            c2.process(input.slice(i, i + 1), out2.mut_slice(i, i + 1));
+        }
+
+        assert!(out1 == out2);
+    }
+
+    #[test]
+    fn test_clone() {
+        // A copy made partway through a block must produce the same keystream as the original.
+        let key = [7u8, ..32];
+        let nonce = [9u8, ..8];
+        let input = Vec::from_elem(600, 0u8);
+
+        let mut c1 = ChaCha20::new(key, nonce);
+        let mut out1 = Vec::from_elem(input.len(), 0u8);
+        c1.process(input.slice_to(100), out1.mut_slice_to(100));
+        let mut c2 = c1.clone();
+        let mut out2 = out1.clone();
+        c1.process(input.slice_from(100), out1.mut_slice_from(100));
+        c2.process(input.slice_from(100), out2.mut_slice_from(100));
+
+        assert!(out1 == out2);
+    }
+
     #[test]
     fn test_chacha20_256_tls_vectors() {
         struct TestVector {
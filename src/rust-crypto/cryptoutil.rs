@@ -9,8 +9,9 @@
 // except according to those terms.
 
 use std;
-use std::num::{One, Zero, Int, CheckedAdd};
+use std::num::{One, Zero, Int, CheckedAdd, ToPrimitive};
 use std::slice::bytes::{MutableByteVector, copy_memory};
+use num::bigint::{BigUint, ToBigUint};
 
 use buffer::{ReadBuffer, WriteBuffer, BufferResult, BufferUnderflow, BufferOverflow};
 use symmetriccipher::{SynchronousStreamCipher, SymmetricCipherError};
@@ -28,6 +29,19 @@ pub fn write_u64_be(dst: &mut[u8], input: u64) {
     dst[7] = (input & 0x00000000000000ff) as u8;
 }
 
+/// Write a u64 into a vector, which must be 8 bytes long. The value is written in little-endian
+/// format.
+pub fn write_u64_le(dst: &mut[u8], input: u64) {
+    dst[7] = ((input & 0xff00000000000000) >> 56) as u8;
+    dst[6] = ((input & 0x00ff000000000000) >> 48) as u8;
+    dst[5] = ((input & 0x0000ff0000000000) >> 40) as u8;
+    dst[4] = ((input & 0x000000ff00000000) >> 32) as u8;
+    dst[3] = ((input & 0x00000000ff000000) >> 24) as u8;
+    dst[2] = ((input & 0x0000000000ff0000) >> 16) as u8;
+    dst[1] = ((input & 0x000000000000ff00) >> 8) as u8;
+    dst[0] = (input & 0x00000000000000ff) as u8;
+}
+
 /// Write a u32 into a vector, which must be 4 bytes long. The value is written in big-endian
 /// format.
 pub fn write_u32_be(dst: &mut[u8], input: u32) {
@@ -86,6 +100,19 @@ pub fn read_u64_be(input: &[u8]) -> u64 {
         (input[7] as u64);
 }
 
+/// Read the value of a vector of bytes as a u64 value in little-endian format.
+pub fn read_u64_le(input: &[u8]) -> u64 {
+    return
+        (input[7] as u64) << 56 |
+        (input[6] as u64) << 48 |
+        (input[5] as u64) << 40 |
+        (input[4] as u64) << 32 |
+        (input[3] as u64) << 24 |
+        (input[2] as u64) << 16 |
+        (input[1] as u64) << 8 |
+        (input[0] as u64);
+}
+
 /// Read the value of a vector of bytes as a u32 value in little-endian format.
 pub fn read_u32_le(input: &[u8]) -> u32 {
     return
@@ -222,6 +249,9 @@ pub trait FixedBuffer {
      /// Get the current buffer.
     fn current_buffer<'s>(&'s mut self) -> &'s [u8];
 
+    /// Get the data currently in the buffer without clearing it.
+    fn buffered<'s>(&'s self) -> &'s [u8];
+
     /// Get the current position of the buffer.
     fn position(&self) -> uint;
 
@@ -304,12 +334,21 @@ macro_rules! impl_fixed_buffer( ($name:ident, $size:expr) => (
             return self.buffer.slice_to(tmp);
         }
 
+        fn buffered<'s>(&'s self) -> &'s [u8] {
+            return self.buffer.slice_to(self.buffer_idx);
+        }
+
         fn position(&self) -> uint { self.buffer_idx }
 
         fn remaining(&self) -> uint { $size - self.buffer_idx }
 
         fn size(&self) -> uint { $size }
     }
+
+    // Clone can't be derived since fixed size arrays don't implement it.
+    impl Clone for $name {
+        fn clone(&self) -> $name { *self }
+    }
 ))
 
 /// A fixed size buffer of 64 bytes useful for cryptographic operations.
@@ -357,13 +396,24 @@ pub trait StandardPadding {
     /// least rem bytes available, the buffer will be zero padded, processed, cleared, and then
     /// filled with zeros again until only rem bytes are remaining.
     fn standard_padding(&mut self, rem: uint, func: |&[u8]|);
+
+    /// Add standard padding to a message whose final byte only contains the given number of bits,
+    /// which must be less than 8. The bits are taken from the most significant end of last and the
+    /// padding bit is placed directly after them, in the same byte. Otherwise this is the same as
+    /// standard_padding().
+    fn standard_padding_bits(&mut self, rem: uint, last: u8, bits: uint, func: |&[u8]|);
 }
 
 impl <T: FixedBuffer> StandardPadding for T {
     fn standard_padding(&mut self, rem: uint, func: |&[u8]|) {
+        self.standard_padding_bits(rem, 0, 0, func);
+    }
+
+    fn standard_padding_bits(&mut self, rem: uint, last: u8, bits: uint, func: |&[u8]|) {
+        assert!(bits < 8);
         let size = self.size();
 
-        self.next(1)[0] = 128;
+        self.next(1)[0] = (last & !(0xffu8 >> bits)) | (0x80u8 >> bits);
 
         if self.remaining() < rem {
             self.zero_until(size);
@@ -375,15 +425,90 @@ impl <T: FixedBuffer> StandardPadding for T {
 }
 
 
+// The following functions are used by the digests to build and parse their exported states. All
+// values are stored in big-endian format.
+
+/// Append a u32 to an exported state.
+pub fn push_u32_be(state: &mut Vec<u8>, input: u32) {
+    let mut tmp = [0u8, ..4];
+    write_u32_be(tmp, input);
+    state.push_all(tmp);
+}
+
+/// Append a u64 to an exported state.
+pub fn push_u64_be(state: &mut Vec<u8>, input: u64) {
+    let mut tmp = [0u8, ..8];
+    write_u64_be(tmp, input);
+    state.push_all(tmp);
+}
+
+/// Append the position and the contents of a FixedBuffer to an exported state. The unused part of
+/// the buffer is written as zeros so that the state always has the same length.
+pub fn push_fixed_buffer<B: FixedBuffer>(state: &mut Vec<u8>, buffer: &B) {
+    let data = buffer.buffered();
+    state.push(data.len() as u8);
+    state.push_all(data);
+    state.grow(buffer.size() - data.len(), &0u8);
+}
+
+/// Restore a FixedBuffer from the part of an exported state that was written by
+/// push_fixed_buffer(). Returns false if the buffer position is not valid.
+pub fn read_fixed_buffer<B: FixedBuffer>(buffer: &mut B, state: &[u8]) -> bool {
+    let position = state[0] as uint;
+    if position >= buffer.size() {
+        return false;
+    }
+    buffer.reset();
+    buffer.input(state.slice(1, 1 + position), |_: &[u8]| { fail!("The buffer can't be full.") });
+    return true;
+}
+
+/// Convert the byte used to store a flag in an exported state back into a bool.
+pub fn read_bool(input: u8) -> Option<bool> {
+    match input {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None
+    }
+}
+
+/// Convert a big-endian byte string into a BigUint.
+pub fn biguint_from_bytes_be(input: &[u8]) -> BigUint {
+    let mut n: BigUint = Zero::zero();
+    for b in input.iter() {
+        n = (n << 8) + b.to_biguint().unwrap();
+    }
+    return n;
+}
+
+/// Convert a BigUint into a big-endian byte string of the given length. fail!() if the value does
+/// not fit.
+pub fn biguint_to_bytes_be(input: &BigUint, len: uint) -> Vec<u8> {
+    let mask = 0xffu.to_biguint().unwrap();
+    let mut n = input.clone();
+    let mut out = Vec::from_elem(len, 0u8);
+    for b in out.mut_iter().rev() {
+        *b = (n & mask).to_u8().unwrap();
+        n = n >> 8;
+    }
+    if !n.is_zero() {
+        fail!("Value is too large to be converted to {} bytes.", len);
+    }
+    return out;
+}
+
 #[cfg(test)]
 pub mod test {
-    use std::num::Bounded;
+    use std::num::{Bounded, Zero};
 
     use std::rand::IsaacRng;
     use std::rand::distributions::{IndependentSample, Range};
 
-    use cryptoutil::{add_bytes_to_bits, add_bytes_to_bits_tuple};
-    use digest::Digest;
+    use num::bigint::ToBigUint;
+
+    use cryptoutil::{add_bytes_to_bits, add_bytes_to_bits_tuple, biguint_from_bytes_be,
+        biguint_to_bytes_be};
+    use digest::{Digest, DigestState, CopyResult};
 
     /// Feed 1,000,000 'a's into the digest with varying input sizes and check that the result is
     /// correct.
@@ -409,6 +534,104 @@ pub mod test {
         assert!(expected == result_str.as_slice());
     }
 
+    /// Export the state of a digest at various points in a message, import it into a second digest
+    /// and check that both produce the same result after processing the rest of the message. Also
+    /// check that states of the wrong length or with an invalid buffer position are rejected.
+    pub fn test_digest_state<D: Digest + DigestState>(digest: &mut D, other: &mut D) {
+        let bs = digest.block_size();
+        let data = Vec::from_fn(bs * 4, |i| i as u8);
+        let mut expected = Vec::from_elem(digest.output_bytes(), 0u8);
+        let mut result = Vec::from_elem(digest.output_bytes(), 0u8);
+
+        for split in range(0, data.len() + 1) {
+            digest.reset();
+            digest.input(data.slice_to(split));
+            let state = digest.export_state();
+
+            other.reset();
+            other.input(data.slice_to(bs + 1));
+            assert!(other.import_state(state.as_slice()).is_ok());
+
+            digest.input(data.slice_from(split));
+            digest.result(expected.as_mut_slice());
+            other.input(data.slice_from(split));
+            other.result(result.as_mut_slice());
+            assert!(expected == result);
+
+            // A finished state can be exported too
+            let state = digest.export_state();
+            other.reset();
+            assert!(other.import_state(state.as_slice()).is_ok());
+            other.result(result.as_mut_slice());
+            assert!(expected == result);
+        }
+
+        digest.reset();
+        let state = digest.export_state();
+        assert!(other.import_state(state.slice_to(state.len() - 1)).is_err());
+
+        // The buffer position is stored just before the buffer contents at the end of the state
+        let mut bad_position = state.clone();
+        *bad_position.get_mut(state.len() - bs - 1) = bs as u8;
+        assert!(other.import_state(bad_position.as_slice()).is_err());
+    }
+
+    /// Clone a digest at various points in a message and check that the copy and the original each
+    /// produce the correct result after processing different continuations of the message. Also
+    /// check that copy_result() matches the result of finishing the digest.
+    pub fn test_digest_clone<D: Digest + Clone>(digest: &mut D) {
+        let bs = digest.block_size();
+        let data = Vec::from_fn(bs * 3, |i| i as u8);
+        let other = Vec::from_elem(bs, 0xffu8);
+        let mut expected = Vec::from_elem(digest.output_bytes(), 0u8);
+        let mut result = Vec::from_elem(digest.output_bytes(), 0u8);
+        let mut reference = digest.clone();
+
+        for split in range(0, data.len() + 1) {
+            digest.reset();
+            digest.input(data.slice_to(split));
+            let mut copy = digest.clone();
+
+            reference.reset();
+            reference.input(data.slice_to(split));
+            reference.result(expected.as_mut_slice());
+            digest.copy_result(result.as_mut_slice());
+            assert!(expected == result);
+
+            // The copy continues with a different message than the original
+            reference.reset();
+            reference.input(data.slice_to(split));
+            reference.input(other.as_slice());
+            reference.result(expected.as_mut_slice());
+            copy.input(other.as_slice());
+            copy.result(result.as_mut_slice());
+            assert!(expected == result);
+
+            reference.reset();
+            reference.input(data.as_slice());
+            reference.result(expected.as_mut_slice());
+            digest.input(data.slice_from(split));
+            digest.result(result.as_mut_slice());
+            assert!(expected == result);
+        }
+    }
+
+    #[test]
+    fn test_biguint_bytes_be() {
+        let n = 0x0102030405u64.to_biguint().unwrap();
+        assert!(biguint_from_bytes_be([1, 2, 3, 4, 5]) == n);
+        assert!(biguint_from_bytes_be([0, 0, 1, 2, 3, 4, 5]) == n);
+        assert!(biguint_to_bytes_be(&n, 7) == vec![0, 0, 1, 2, 3, 4, 5]);
+        assert!(biguint_to_bytes_be(&n, 5) == vec![1, 2, 3, 4, 5]);
+        assert!(biguint_from_bytes_be([0u8, ..0]).is_zero());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_biguint_to_bytes_be_too_large() {
+        biguint_to_bytes_be(&0x0102030405u64.to_biguint().unwrap(), 4);
+    }
+
     // A normal addition - no overflow occurs
     #[test]
     fn test_add_bytes_to_bits_ok() {
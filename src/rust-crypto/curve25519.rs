@@ -0,0 +1,370 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * The X25519 key agreement function as specified in RFC 7748, along with the arithmetic modulo
+ * 2^255 - 19 that it's built on.
+ *
+ * Field elements are represented as 16 signed 64 bit limbs of 16 bits each, as in TweetNaCl. None
+ * of the operations branch on or index memory with secret data: conditional swaps are done with
+ * masks and the Montgomery ladder performs the same steps for every bit of the scalar.
+ */
+
+/// An element of the field of integers modulo 2^255 - 19. Limb i holds bits 16i to 16i + 15, but
+/// limbs may temporarily be larger than 16 bits or negative between carries.
+pub struct Fe(pub [i64, ..16]);
+
+impl Clone for Fe { fn clone(&self) -> Fe { *self } }
+
+// (A - 2) / 4, where A = 486662 is the Montgomery curve constant
+static A24: Fe = Fe([0xdb41, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+fn cswap(a: &mut [i64, ..16], b: &mut [i64, ..16], bit: i64) {
+    let mask = !(bit - 1);
+    for i in range(0u, 16) {
+        let t = mask & (a[i] ^ b[i]);
+        a[i] ^= t;
+        b[i] ^= t;
+    }
+}
+
+impl Fe {
+    /// The field element 0
+    pub fn zero() -> Fe { Fe([0, ..16]) }
+
+    /// The field element 1
+    pub fn one() -> Fe {
+        let mut x = [0i64, ..16];
+        x[0] = 1;
+        Fe(x)
+    }
+
+    /// Decode a 32 byte little endian string, ignoring the top bit. Values of 2^255 - 19 and
+    /// greater are accepted and reduced.
+    pub fn from_bytes(input: &[u8]) -> Fe {
+        assert!(input.len() == 32);
+        let mut x = [0i64, ..16];
+        for i in range(0u, 16) {
+            x[i] = input[2 * i] as i64 + ((input[2 * i + 1] as i64) << 8);
+        }
+        x[15] &= 0x7fff;
+        Fe(x)
+    }
+
+    /// Encode the fully reduced value as a 32 byte little endian string
+    pub fn to_bytes(&self) -> [u8, ..32] {
+        let mut r = *self;
+        r.carry();
+        r.carry();
+        r.carry();
+        let Fe(mut t) = r;
+
+        // Subtract p twice, keeping the result whenever it doesn't go negative
+        for _ in range(0u, 2) {
+            let mut m = [0i64, ..16];
+            m[0] = t[0] - 0xffed;
+            for i in range(1u, 15) {
+                m[i] = t[i] - 0xffff - ((m[i - 1] >> 16) & 1);
+                m[i - 1] &= 0xffff;
+            }
+            m[15] = t[15] - 0x7fff - ((m[14] >> 16) & 1);
+            let borrow = (m[15] >> 16) & 1;
+            m[14] &= 0xffff;
+            cswap(&mut t, &mut m, 1 - borrow);
+        }
+
+        let mut out = [0u8, ..32];
+        for i in range(0u, 16) {
+            out[2 * i] = t[i] as u8;
+            out[2 * i + 1] = (t[i] >> 8) as u8;
+        }
+        out
+    }
+
+    // Propagate carries, so that each limb is in [0, 2^16), except that the carry out of the top
+    // limb is multiplied by 38 and added to the bottom one, since 2^256 = 38 mod p.
+    fn carry(&mut self) {
+        let Fe(ref mut x) = *self;
+        for i in range(0u, 16) {
+            x[i] += 1 << 16;
+            let c = x[i] >> 16;
+            if i < 15 {
+                x[i + 1] += c - 1;
+            } else {
+                x[0] += 38 * (c - 1);
+            }
+            x[i] -= c << 16;
+        }
+    }
+
+    /// Swap a and b if bit is 1 and leave them alone if it is 0, in constant time
+    pub fn swap(a: &mut Fe, b: &mut Fe, bit: i64) {
+        let Fe(ref mut av) = *a;
+        let Fe(ref mut bv) = *b;
+        cswap(av, bv, bit);
+    }
+
+    /// Compute self + b
+    pub fn add(&self, b: &Fe) -> Fe {
+        let (&Fe(ref av), &Fe(ref bv)) = (self, b);
+        let mut x = [0i64, ..16];
+        for i in range(0u, 16) {
+            x[i] = av[i] + bv[i];
+        }
+        Fe(x)
+    }
+
+    /// Compute self - b
+    pub fn sub(&self, b: &Fe) -> Fe {
+        let (&Fe(ref av), &Fe(ref bv)) = (self, b);
+        let mut x = [0i64, ..16];
+        for i in range(0u, 16) {
+            x[i] = av[i] - bv[i];
+        }
+        Fe(x)
+    }
+
+    /// Compute -self
+    pub fn neg(&self) -> Fe {
+        Fe::zero().sub(self)
+    }
+
+    /// Compute self * b
+    pub fn mul(&self, b: &Fe) -> Fe {
+        let (&Fe(ref av), &Fe(ref bv)) = (self, b);
+        let mut t = [0i64, ..31];
+        for i in range(0u, 16) {
+            for j in range(0u, 16) {
+                t[i + j] += av[i] * bv[j];
+            }
+        }
+        for i in range(0u, 15) {
+            t[i] += 38 * t[i + 16];
+        }
+        let mut x = [0i64, ..16];
+        for i in range(0u, 16) {
+            x[i] = t[i];
+        }
+        let mut r = Fe(x);
+        r.carry();
+        r.carry();
+        r
+    }
+
+    /// Compute self^2
+    pub fn square(&self) -> Fe {
+        self.mul(self)
+    }
+
+    /// Compute self^-1 as self^(p - 2). The inverse of 0 is 0.
+    pub fn invert(&self) -> Fe {
+        let mut c = *self;
+        for a in range(0i, 254).rev() {
+            c = c.square();
+            if a != 2 && a != 4 {
+                c = c.mul(self);
+            }
+        }
+        c
+    }
+
+    /// Compute self^((p - 5) / 8), which is used for computing square roots
+    pub fn pow_p58(&self) -> Fe {
+        let mut c = *self;
+        for a in range(0i, 251).rev() {
+            c = c.square();
+            if a != 1 {
+                c = c.mul(self);
+            }
+        }
+        c
+    }
+
+    /// Whether the fully reduced value is odd, which is used as its sign
+    pub fn is_negative(&self) -> bool {
+        (self.to_bytes()[0] & 1) == 1
+    }
+
+    /// Whether the value is not 0 modulo p
+    pub fn is_nonzero(&self) -> bool {
+        self.to_bytes().iter().fold(0u8, |acc, x| acc | *x) != 0
+    }
+}
+
+/**
+ * Compute the X25519 function of a 32 byte scalar and a 32 byte u-coordinate. The scalar is
+ * clamped as RFC 7748 requires and the top bit of the u-coordinate is ignored.
+ *
+ * This doesn't check the result. Key agreement should use x25519_agree(), which rejects the all
+ * zero output that results from a low order point.
+ */
+pub fn x25519(scalar: &[u8], u: &[u8]) -> [u8, ..32] {
+    assert!(scalar.len() == 32);
+    let mut k = [0u8, ..32];
+    for (x, y) in k.mut_iter().zip(scalar.iter()) {
+        *x = *y;
+    }
+    k[0] &= 248;
+    k[31] = (k[31] & 127) | 64;
+
+    let x1 = Fe::from_bytes(u);
+    let mut x2 = Fe::one();
+    let mut z2 = Fe::zero();
+    let mut x3 = x1;
+    let mut z3 = Fe::one();
+
+    // The Montgomery ladder from RFC 7748 section 5
+    for i in range(0u, 255).rev() {
+        let bit = ((k[i >> 3] >> (i & 7)) & 1) as i64;
+        Fe::swap(&mut x2, &mut x3, bit);
+        Fe::swap(&mut z2, &mut z3, bit);
+
+        let a = x2.add(&z2);
+        let aa = a.square();
+        let b = x2.sub(&z2);
+        let bb = b.square();
+        let e = aa.sub(&bb);
+        let c = x3.add(&z3);
+        let d = x3.sub(&z3);
+        let da = d.mul(&a);
+        let cb = c.mul(&b);
+        x3 = da.add(&cb).square();
+        z3 = x1.mul(&da.sub(&cb).square());
+        x2 = aa.mul(&bb);
+        z2 = e.mul(&aa.add(&A24.mul(&e)));
+
+        Fe::swap(&mut x2, &mut x3, bit);
+        Fe::swap(&mut z2, &mut z3, bit);
+    }
+
+    x2.mul(&z2.invert()).to_bytes()
+}
+
+/// Compute the public key for a 32 byte private key, which is X25519 with the base point 9
+pub fn x25519_base(scalar: &[u8]) -> [u8, ..32] {
+    let mut base = [0u8, ..32];
+    base[0] = 9;
+    x25519(scalar, base)
+}
+
+/**
+ * Compute the shared secret from our private key and the peer's public key. Returns None if the
+ * result is all zeros, which happens when the public key is a point of small order, so that the
+ * secret doesn't depend on the private key.
+ */
+pub fn x25519_agree(private_key: &[u8], public_key: &[u8]) -> Option<[u8, ..32]> {
+    let shared = x25519(private_key, public_key);
+    let acc = shared.iter().fold(0u8, |acc, x| acc | *x);
+    if acc == 0 {
+        None
+    } else {
+        Some(shared)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serialize::hex::{FromHex, ToHex};
+
+    use curve25519::{x25519, x25519_base, x25519_agree, Fe};
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        s.from_hex().unwrap()
+    }
+
+    #[test]
+    fn test_rfc7748_vectors() {
+        // (scalar, u-coordinate, output) from RFC 7748 section 5.2
+        let tests = [
+            ("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4",
+             "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c",
+             "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552"),
+            ("4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d",
+             "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493",
+             "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957")
+        ];
+        for &(k, u, out) in tests.iter() {
+            let k = from_hex(k);
+            let u = from_hex(u);
+            assert_eq!(x25519(k.as_slice(), u.as_slice()).to_hex().as_slice(), out);
+        }
+    }
+
+    #[test]
+    fn test_rfc7748_iterated() {
+        // k = u = 9, then repeatedly k, u = X25519(k, u), k
+        let mut k = [0u8, ..32];
+        k[0] = 9;
+        let mut u = k;
+        for i in range(0u, 1000) {
+            let r = x25519(k, u);
+            u = k;
+            k = r;
+            if i == 0 {
+                assert_eq!(k.to_hex().as_slice(),
+                    "422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079");
+            }
+        }
+        assert_eq!(k.to_hex().as_slice(),
+            "684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51");
+    }
+
+    #[test]
+    fn test_rfc7748_agreement() {
+        // From RFC 7748 section 6.1
+        let alice = from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = from_hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let alice_public = x25519_base(alice.as_slice());
+        let bob_public = x25519_base(bob.as_slice());
+        assert_eq!(alice_public.to_hex().as_slice(),
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        assert_eq!(bob_public.to_hex().as_slice(),
+            "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+
+        let shared = "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742";
+        assert_eq!(x25519_agree(alice.as_slice(), bob_public).unwrap().to_hex().as_slice(), shared);
+        assert_eq!(x25519_agree(bob.as_slice(), alice_public).unwrap().to_hex().as_slice(), shared);
+    }
+
+    #[test]
+    fn test_low_order_points() {
+        let k = from_hex("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let points = [
+            // 0 and 1
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            // The points of order 8
+            "e0eb7a7c3b41b8ae1656e3faf19fc46ada098deb9c32b1fd866205165f49b800",
+            "5f9c95bca3508c24b1d0b1559c83ef5b04445cc4581c8e86d8224eddd09f1157",
+            // p - 1, p and p + 1
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f"
+        ];
+        for p in points.iter() {
+            assert!(x25519_agree(k.as_slice(), from_hex(*p).as_slice()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_fe_bytes() {
+        // Non-canonical encodings are reduced and the top bit is ignored
+        let p_plus_2 = from_hex("efffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        let mut two = [0u8, ..32];
+        two[0] = 2;
+        assert!(Fe::from_bytes(p_plus_2.as_slice()).to_bytes() == two);
+        let mut high = two;
+        high[31] = 0x80;
+        assert!(Fe::from_bytes(high).to_bytes() == two);
+
+        let x = Fe::from_bytes(from_hex(
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef").as_slice());
+        assert!(x.mul(&x.invert()).to_bytes() == Fe::one().to_bytes());
+        assert!(x.add(&x.neg()).to_bytes() == Fe::zero().to_bytes());
+        assert!(!Fe::zero().is_nonzero());
+        assert!(x.is_nonzero());
+    }
+}
@@ -0,0 +1,270 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Finite field Diffie-Hellman key agreement.
+ *
+ * Any domain parameters can be used, but the MODP groups from RFC 3526 and the FFDHE groups from
+ * RFC 7919 are built in. All of these use a safe prime p = 2q + 1 with the generator 2, which
+ * generates the subgroup of order q.
+ *
+ * The public value received from the peer is checked to be in the range [2, p - 2] and to be in the
+ * subgroup of order q, as described in NIST SP 800-56A section 5.6.2.3.1, before it is used.
+ */
+
+use std::num::One;
+use std::rand::Rng;
+
+use num::bigint::{BigUint, ToBigUint, RandBigInt};
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be};
+use domain_params::DomainParams;
+use montgomery;
+use montgomery::Montgomery;
+
+/// The built in groups
+#[deriving(Clone, PartialEq, Show)]
+pub enum DhGroup {
+    /// The 1536 bit MODP group from RFC 3526
+    Modp1536,
+    /// The 2048 bit MODP group from RFC 3526
+    Modp2048,
+    /// The 3072 bit MODP group from RFC 3526
+    Modp3072,
+    /// The 4096 bit MODP group from RFC 3526
+    Modp4096,
+    /// The 6144 bit MODP group from RFC 3526
+    Modp6144,
+    /// The 8192 bit MODP group from RFC 3526
+    Modp8192,
+    /// The 2048 bit FFDHE group from RFC 7919
+    Ffdhe2048,
+    /// The 3072 bit FFDHE group from RFC 7919
+    Ffdhe3072,
+    /// The 4096 bit FFDHE group from RFC 7919
+    Ffdhe4096,
+    /// The 6144 bit FFDHE group from RFC 7919
+    Ffdhe6144,
+    /// The 8192 bit FFDHE group from RFC 7919
+    Ffdhe8192
+}
+
+/// The errors that can occur during key agreement
+#[deriving(PartialEq, Show)]
+pub enum DhError {
+    /// The private value is not in the range [1, q - 1]
+    InvalidPrivateValue,
+    /// The peer's public value is out of range or not in the subgroup of order q
+    InvalidPublicValue
+}
+
+// The primes of the groups, which are all of the form
+// p = 2^b - 2^(b - 64) - 1 + 2^64 * (floor(2^(b - 130) * c) + x)
+// where c is pi for the MODP groups and e for the FFDHE groups.
+static MODP_1536: &'static str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA237327FFFFFFFFFFFFFFFF";
+static MODP_2048: &'static str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6955817183995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF";
+static MODP_3072: &'static str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6955817183995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E208E24FA074E5AB3143DB5BFCE0FD108E4B82D120A93AD2CAFFFFFFFFFFFFFFFF";
+static MODP_4096: &'static str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6955817183995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E208E24FA074E5AB3143DB5BFCE0FD108E4B82D120A92108011A723C12A787E6D788719A10BDBA5B2699C327186AF4E23C1A946834B6150BDA2583E9CA2AD44CE8DBBBC2DB04DE8EF92E8EFC141FBECAA6287C59474E6BC05D99B2964FA090C3A2233BA186515BE7ED1F612970CEE2D7AFB81BDD762170481CD0069127D5B05AA993B4EA988D8FDDC186FFB7DC90A6C08F4DF435C934063199FFFFFFFFFFFFFFFF";
+static MODP_6144: &'static str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6955817183995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E208E24FA074E5AB3143DB5BFCE0FD108E4B82D120A92108011A723C12A787E6D788719A10BDBA5B2699C327186AF4E23C1A946834B6150BDA2583E9CA2AD44CE8DBBBC2DB04DE8EF92E8EFC141FBECAA6287C59474E6BC05D99B2964FA090C3A2233BA186515BE7ED1F612970CEE2D7AFB81BDD762170481CD0069127D5B05AA993B4EA988D8FDDC186FFB7DC90A6C08F4DF435C93402849236C3FAB4D27C7026C1D4DCB2602646DEC9751E763DBA37BDF8FF9406AD9E530EE5DB382F413001AEB06A53ED9027D831179727B0865A8918DA3EDBEBCF9B14ED44CE6CBACED4BB1BDB7F1447E6CC254B332051512BD7AF426FB8F401378CD2BF5983CA01C64B92ECF032EA15D1721D03F482D7CE6E74FEF6D55E702F46980C82B5A84031900B1C9E59E7C97FBEC7E8F323A97A7E36CC88BE0F1D45B7FF585AC54BD407B22B4154AACC8F6D7EBF48E1D814CC5ED20F8037E0A79715EEF29BE32806A1D58BB7C5DA76F550AA3D8A1FBFF0EB19CCB1A313D55CDA56C9EC2EF29632387FE8D76E3C0468043E8F663F4860EE12BF2D5B0B7474D6E694F91E6DCC4024FFFFFFFFFFFFFFFF";
+static MODP_8192: &'static str = "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F14374FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7EDEE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF0598DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3BE39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF6955817183995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E208E24FA074E5AB3143DB5BFCE0FD108E4B82D120A92108011A723C12A787E6D788719A10BDBA5B2699C327186AF4E23C1A946834B6150BDA2583E9CA2AD44CE8DBBBC2DB04DE8EF92E8EFC141FBECAA6287C59474E6BC05D99B2964FA090C3A2233BA186515BE7ED1F612970CEE2D7AFB81BDD762170481CD0069127D5B05AA993B4EA988D8FDDC186FFB7DC90A6C08F4DF435C93402849236C3FAB4D27C7026C1D4DCB2602646DEC9751E763DBA37BDF8FF9406AD9E530EE5DB382F413001AEB06A53ED9027D831179727B0865A8918DA3EDBEBCF9B14ED44CE6CBACED4BB1BDB7F1447E6CC254B332051512BD7AF426FB8F401378CD2BF5983CA01C64B92ECF032EA15D1721D03F482D7CE6E74FEF6D55E702F46980C82B5A84031900B1C9E59E7C97FBEC7E8F323A97A7E36CC88BE0F1D45B7FF585AC54BD407B22B4154AACC8F6D7EBF48E1D814CC5ED20F8037E0A79715EEF29BE32806A1D58BB7C5DA76F550AA3D8A1FBFF0EB19CCB1A313D55CDA56C9EC2EF29632387FE8D76E3C0468043E8F663F4860EE12BF2D5B0B7474D6E694F91E6DBE115974A3926F12FEE5E438777CB6A932DF8CD8BEC4D073B931BA3BC832B68D9DD300741FA7BF8AFC47ED2576F6936BA424663AAB639C5AE4F5683423B4742BF1C978238F16CBE39D652DE3FDB8BEFC848AD922222E04A4037C0713EB57A81A23F0C73473FC646CEA306B4BCBC8862F8385DDFA9D4B7FA2C087E879683303ED5BDD3A062B3CF5B3A278A66D2A13F83F44F82DDF310EE074AB6A364597E899A0255DC164F31CC50846851DF9AB48195DED7EA1B1D510BD7EE74D73FAF36BC31ECFA268359046F4EB879F924009438B481C6CD7889A002ED5EE382BC9190DA6FC026E479558E4475677E9AA9E3050E2765694DFC81F56E880B96E7160C980DD98EDD3DFFFFFFFFFFFFFFFFF";
+static FFDHE_2048: &'static str = "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B423861285C97FFFFFFFFFFFFFFFF";
+static FFDHE_3072: &'static str = "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035BBC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91CAEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B66C62E37FFFFFFFFFFFFFFFF";
+static FFDHE_4096: &'static str = "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035BBC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91CAEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B669E1EF16E6F52C3164DF4FB7930E9E4E58857B6AC7D5F42D69F6D187763CF1D5503400487F55BA57E31CC7A7135C886EFB4318AED6A1E012D9E6832A907600A918130C46DC778F971AD0038092999A333CB8B7A1A1DB93D7140003C2A4ECEA9F98D0ACC0A8291CDCEC97DCF8EC9B55A7F88A46B4DB5A851F44182E1C68A007E5E655F6AFFFFFFFFFFFFFFFF";
+static FFDHE_6144: &'static str = "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035BBC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91CAEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B669E1EF16E6F52C3164DF4FB7930E9E4E58857B6AC7D5F42D69F6D187763CF1D5503400487F55BA57E31CC7A7135C886EFB4318AED6A1E012D9E6832A907600A918130C46DC778F971AD0038092999A333CB8B7A1A1DB93D7140003C2A4ECEA9F98D0ACC0A8291CDCEC97DCF8EC9B55A7F88A46B4DB5A851F44182E1C68A007E5E0DD9020BFD64B645036C7A4E677D2C38532A3A23BA4442CAF53EA63BB454329B7624C8917BDD64B1C0FD4CB38E8C334C701C3ACDAD0657FCCFEC719B1F5C3E4E46041F388147FB4CFDB477A52471F7A9A96910B855322EDB6340D8A00EF092350511E30ABEC1FFF9E3A26E7FB29F8C183023C3587E38DA0077D9B4763E4E4B94B2BBC194C6651E77CAF992EEAAC0232A281BF6B3A739C1226116820AE8DB5847A67CBEF9C9091B462D538CD72B03746AE77F5E62292C311562A846505DC82DB854338AE49F5235C95B91178CCF2DD5CACEF403EC9D1810C6272B045B3B71F9DC6B80D63FDD4A8E9ADB1E6962A69526D43161C1A41D570D7938DAD4A40E329CD0E40E65FFFFFFFFFFFFFFFF";
+static FFDHE_8192: &'static str = "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035BBC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91CAEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B669E1EF16E6F52C3164DF4FB7930E9E4E58857B6AC7D5F42D69F6D187763CF1D5503400487F55BA57E31CC7A7135C886EFB4318AED6A1E012D9E6832A907600A918130C46DC778F971AD0038092999A333CB8B7A1A1DB93D7140003C2A4ECEA9F98D0ACC0A8291CDCEC97DCF8EC9B55A7F88A46B4DB5A851F44182E1C68A007E5E0DD9020BFD64B645036C7A4E677D2C38532A3A23BA4442CAF53EA63BB454329B7624C8917BDD64B1C0FD4CB38E8C334C701C3ACDAD0657FCCFEC719B1F5C3E4E46041F388147FB4CFDB477A52471F7A9A96910B855322EDB6340D8A00EF092350511E30ABEC1FFF9E3A26E7FB29F8C183023C3587E38DA0077D9B4763E4E4B94B2BBC194C6651E77CAF992EEAAC0232A281BF6B3A739C1226116820AE8DB5847A67CBEF9C9091B462D538CD72B03746AE77F5E62292C311562A846505DC82DB854338AE49F5235C95B91178CCF2DD5CACEF403EC9D1810C6272B045B3B71F9DC6B80D63FDD4A8E9ADB1E6962A69526D43161C1A41D570D7938DAD4A40E329CCFF46AAA36AD004CF600C8381E425A31D951AE64FDB23FCEC9509D43687FEB69EDD1CC5E0B8CC3BDF64B10EF86B63142A3AB8829555B2F747C932665CB2C0F1CC01BD70229388839D2AF05E454504AC78B7582822846C0BA35C35F5C59160CC046FD8251541FC68C9C86B022BB7099876A460E7451A8A93109703FEE1C217E6C3826E52C51AA691E0E423CFC99E9E31650C1217B624816CDAD9A95F9D5B8019488D9C0A0A1FE3075A577E23183F81D4A3F2FA4571EFC8CE0BA8A4FE8B6855DFE72B0A66EDED2FBABFBE58A30FAFABE1C5D71A87E2F741EF8C1FE86FEA6BBFDE530677F0D97D11D49F7A8443D0822E506A9F4614E011E2A94838FF88CD68C8BB7C5C6424CFFFFFFFFFFFFFFFF";
+
+impl DhGroup {
+    /// The domain parameters of the group
+    pub fn params(&self) -> DomainParams {
+        let p = match *self {
+            Modp1536 => MODP_1536,
+            Modp2048 => MODP_2048,
+            Modp3072 => MODP_3072,
+            Modp4096 => MODP_4096,
+            Modp6144 => MODP_6144,
+            Modp8192 => MODP_8192,
+            Ffdhe2048 => FFDHE_2048,
+            Ffdhe3072 => FFDHE_3072,
+            Ffdhe4096 => FFDHE_4096,
+            Ffdhe6144 => FFDHE_6144,
+            Ffdhe8192 => FFDHE_8192
+        };
+        let p = BigUint::parse_bytes(p.as_bytes(), 16).unwrap();
+        let q = p >> 1;
+        DomainParams {
+            p: p,
+            q: q,
+            g: 2u.to_biguint().unwrap()
+        }
+    }
+}
+
+/**
+ * Check that y is a valid public value for the domain parameters: 2 <= y <= p - 2 and
+ * y^q = 1 mod p.
+ */
+pub fn validate_public_value(params: &DomainParams, y: &BigUint) -> bool {
+    let one: BigUint = One::one();
+    if *y <= one || *y >= params.p - one {
+        return false;
+    }
+    return montgomery::modular_exp(y, &params.q, &params.p) == one;
+}
+
+/// A Diffie-Hellman private key
+#[deriving(Clone, PartialEq)]
+pub struct DhPrivateKey {
+    params: DomainParams,
+    x: BigUint,
+    mont: Montgomery
+}
+
+impl DhPrivateKey {
+    /// Create a private key from the private value x, which must be in the range [1, q - 1]
+    pub fn new(params: &DomainParams, x: BigUint) -> Result<DhPrivateKey, DhError> {
+        let one: BigUint = One::one();
+        if x < one || x >= params.q {
+            return Err(InvalidPrivateValue);
+        }
+        Ok(DhPrivateKey {
+            params: params.clone(),
+            x: x,
+            mont: Montgomery::new(&params.p)
+        })
+    }
+
+    /// Generate a private key with a private value chosen uniformly from [1, q - 1]
+    pub fn generate<R: Rng>(rng: &mut R, params: &DomainParams) -> DhPrivateKey {
+        let one: BigUint = One::one();
+        let x = rng.gen_biguint_range(&one, &params.q);
+        DhPrivateKey::new(params, x).unwrap()
+    }
+
+    /// The domain parameters
+    pub fn params<'a>(&'a self) -> &'a DomainParams { &self.params }
+
+    /// The size of p, and so of public values and shared secrets, in bytes
+    pub fn size(&self) -> uint { (self.params.p.bits() + 7) / 8 }
+
+    /// The public value g^x mod p as a big endian byte string of size() bytes
+    pub fn public_value(&self) -> Vec<u8> {
+        let y = self.mont.modular_exp(&self.params.g, &self.x);
+        return biguint_to_bytes_be(&y, self.size());
+    }
+
+    /**
+     * Compute the shared secret from the peer's public value, which is a big endian byte string.
+     * The secret is returned as a big endian byte string of size() bytes, including any leading
+     * zeros.
+     */
+    pub fn exchange(&self, peer: &[u8]) -> Result<Vec<u8>, DhError> {
+        let y = biguint_from_bytes_be(peer);
+        if !validate_public_value(&self.params, &y) {
+            return Err(InvalidPublicValue);
+        }
+        let z = self.mont.modular_exp(&y, &self.x);
+        return Ok(biguint_to_bytes_be(&z, self.size()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::IsaacRng;
+
+    use num::bigint::{BigUint, ToBigUint};
+    use serialize::hex::FromHex;
+
+    use dh::{DhPrivateKey, InvalidPrivateValue, InvalidPublicValue, Modp1536, Modp2048, Modp3072,
+        Modp4096, Modp6144, Modp8192, Ffdhe2048, Ffdhe3072, Ffdhe4096, Ffdhe6144, Ffdhe8192};
+    use montgomery::modular_exp;
+    use cryptoutil::biguint_to_bytes_be;
+
+    fn big(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    #[test]
+    fn test_groups() {
+        let groups = [
+            (Modp1536, 1536u), (Modp2048, 2048), (Modp3072, 3072), (Modp4096, 4096),
+            (Modp6144, 6144), (Modp8192, 8192), (Ffdhe2048, 2048), (Ffdhe3072, 3072),
+            (Ffdhe4096, 4096), (Ffdhe6144, 6144), (Ffdhe8192, 8192)
+        ];
+        let one = 1u.to_biguint().unwrap();
+        let ones = big("ffffffffffffffff");
+        for &(group, bits) in groups.iter() {
+            let params = group.params();
+            assert_eq!(params.p.bits(), bits);
+            assert!(params.p == (params.q << 1) + one);
+            assert!(params.g == 2u.to_biguint().unwrap());
+            // The top and bottom 64 bits are all ones
+            assert!(params.p >> (bits - 64) == ones);
+            assert!(params.p & ones == ones);
+        }
+
+        for group in [Modp2048, Ffdhe2048].iter() {
+            let params = group.params();
+            assert!(modular_exp(&params.g, &params.q, &params.p) == one);
+        }
+    }
+
+    #[test]
+    fn test_known_answer() {
+        let params = Ffdhe2048.params();
+        let a = DhPrivateKey::new(&params, big("2bd806c97f0e00af1a1fc3328fa763a9269723c8db8fac4f93af71db186d6e90")).unwrap();
+        let b = DhPrivateKey::new(&params, big("81b637d8fcd2c6da6359e6963113a1170de795e4b725b84d1e0b4cfd9ec58ce9")).unwrap();
+        let ya = "1cbbba4598bfa87f45b1817c5bb20b1ccb0c03712acef503c0a88ee471e6e74b19d584970dc14704db27f1ee0083b35e74866d7fd8d448455c76a7eb988efd2c966f7c1ef4e083e072f0ab81bec91153cecc751b96fffd7b9a46c596b402324c72aeccf1e6508a0c815b2d142fc5e0394266cd8b565ef274853f035ee890eadd99ab360563cfe234a59c0a0fe28f869c18b9993b7225f14eb1a364a10d146eca88171efa8401c0eb81eb85b8d32d77f0fd605cc0343243e9c813f924bc0937d5562cae21e76e98c7249a87ce2e6563c3d02481b718396d679a202a04adebf0e778ccf5c969b99cd67eeaf52e7cd0460b6ab7d3e327890ed290f55ea6ff6422c2".from_hex().unwrap();
+        let yb = "4da7098cbb4928543d21d4604d40d6cf476714fb58a995d5070ec50e40fa18897dcfae6207ccd454c16319212691a41d9fccf3c1e058570279bf16a384a11bcfb2d2fa939c7a856d3e3dfb91fdaf42406a78ad8505feede12dd2c4d3ea166a184f4986396f0e86ffdd5ccb5c03a4f8847eb3950f65ac3e26622cee6e14a3b2c17275bb1c63fbf0d91e97c06b5c866900b27eb69f8fc531d83da8fc448b6c5a5358b8a177ecd76a1e3b02fce68f277c8c5b72f66b43ea9c4c2415d1bb84692caa0955f22af325bf4acbfa0585e171fce7daeaeb52828577d0e90568a8b72b069f315101082dfcfebda1dbbf2a780d9c0deabfa1276a069d07d054874d06abdd80".from_hex().unwrap();
+        let z = "fbb13dc453651a99894bb93268a31ba170b16cf05d25a6530a3e116d209f13cad7e9e390550524287b9461b3137c5136369cab014affe48c3fe840bdfe922cbffdc9d5fe67fb3227ceae5bb3021c6fd33bb9375fc7c6f474fc8b1a104188df3ac2cb2e67132b24a17c113d303c484900adceceabb0af1d61090c52ab61de7559cd0cb7f73030bc129fc0809c5eaf4e8b483cac00b233df97b2a972002a710825265df135c67931c9aa0ccce27bba4d2c9c6deca28a239f2cb65ea7c88753aa5711a635c02343f79c85d833a71f561715bde9d2ef0a61b0121e55fe1bf3b8af92fab4bec4091b2cd99ee84e5c0d181973414cdcc214d5544eb824ca8fec11d7ec".from_hex().unwrap();
+
+        assert!(a.public_value() == ya);
+        assert!(b.public_value() == yb);
+        assert!(a.exchange(yb.as_slice()).unwrap() == z);
+        assert!(b.exchange(ya.as_slice()).unwrap() == z);
+    }
+
+    #[test]
+    fn test_exchange() {
+        let mut rng = IsaacRng::new_unseeded();
+        for group in [Modp1536, Ffdhe2048].iter() {
+            let params = group.params();
+            let a = DhPrivateKey::generate(&mut rng, &params);
+            let b = DhPrivateKey::generate(&mut rng, &params);
+            let za = a.exchange(b.public_value().as_slice()).unwrap();
+            let zb = b.exchange(a.public_value().as_slice()).unwrap();
+            assert_eq!(za.len(), params.p.bits() / 8);
+            assert!(za == zb);
+        }
+    }
+
+    #[test]
+    fn test_invalid_private_value() {
+        let params = Ffdhe2048.params();
+        assert!(DhPrivateKey::new(&params, big("0")).is_err());
+        assert_eq!(DhPrivateKey::new(&params, params.q.clone()).err(), Some(InvalidPrivateValue));
+        assert!(DhPrivateKey::new(&params, big("1")).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_public_value() {
+        let mut rng = IsaacRng::new_unseeded();
+        let params = Ffdhe2048.params();
+        let key = DhPrivateKey::generate(&mut rng, &params);
+        let p = params.p.clone();
+        let one = 1u.to_biguint().unwrap();
+
+        // 7 is not a quadratic residue modulo p, so it isn't in the subgroup of order q
+        let invalid = [big("0"), big("1"), p - one, p.clone(), p + one, big("7")];
+        for y in invalid.iter() {
+            let y = biguint_to_bytes_be(y, 257);
+            assert_eq!(key.exchange(y.as_slice()).err(), Some(InvalidPublicValue));
+        }
+
+        // Leading zeros are allowed
+        let y = biguint_to_bytes_be(&big("4"), 300);
+        assert!(key.exchange(y.as_slice()).is_ok());
+    }
+}
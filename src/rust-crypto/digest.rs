@@ -76,6 +76,59 @@ pub trait Digest {
     }
 }
 
+/**
+ * The DigestBits trait is implemented by digests that can process messages whose length is not a
+ * multiple of 8 bits, such as the bit-oriented messages in the NIST test vectors.
+ */
+pub trait DigestBits {
+    /**
+     * Provide the final part of the message as a number of bits. No further data may be provided
+     * until the digest is reset.
+     *
+     * # Arguments
+     *
+     * * input - The message data. The bits are taken from the start of input, starting with the
+     *           most significant bit of each byte.
+     * * bits - The number of bits of input that belong to the message
+     */
+    fn input_bits(&mut self, input: &[u8], bits: uint);
+}
+
+/**
+ * The CopyResult trait is implemented by every Digest that can be cloned. It retrieves the result
+ * for the data provided so far from a copy of the current state, leaving the digest itself
+ * unfinished so that more data may be provided. This is useful for running hashes, such as a
+ * transcript that needs to be hashed at several points.
+ */
+pub trait CopyResult {
+    /**
+     * Retrieve the digest result for the data provided so far without finishing the digest.
+     *
+     * # Arguments
+     *
+     * * out - the vector to hold the result. Must be large enough to contain output_bits().
+     */
+    fn copy_result(&self, out: &mut [u8]);
+
+    /**
+     * Convenience function that retrieves the result of copy_result() as a String in hexadecimal
+     * format.
+     */
+    fn copy_result_str(&self) -> String;
+}
+
+impl <D: Digest + Clone> CopyResult for D {
+    fn copy_result(&self, out: &mut [u8]) {
+        let mut copy = self.clone();
+        copy.result(out);
+    }
+
+    fn copy_result_str(&self) -> String {
+        let mut copy = self.clone();
+        return copy.result_str();
+    }
+}
+
 fn to_hex(rr: &[u8]) -> String {
     let mut s = String::new();
     for b in rr.iter() {
@@ -84,3 +137,33 @@ fn to_hex(rr: &[u8]) -> String {
     }
     return s;
 }
+
+/**
+ * The DigestState trait is implemented by digests whose intermediate state can be exported and
+ * imported again later, for example to resume hashing a long stream after a restart. The exported
+ * state covers the state of the compression function, the length of the input so far and any
+ * buffered input. A state may only be imported into the same algorithm that exported it.
+ */
+pub trait DigestState {
+    /**
+     * Export the current state.
+     */
+    fn export_state(&self) -> Vec<u8>;
+
+    /**
+     * Replace the current state with one that was previously exported.
+     *
+     * # Arguments
+     *
+     * * state - A state returned by export_state()
+     */
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError>;
+}
+
+/// The errors that can occur when importing a digest state
+pub enum DigestStateError {
+    /// The state is not the length of a state exported by the algorithm
+    InvalidStateLength,
+    /// The state contains values that the algorithm could not have exported
+    InvalidState
+}
@@ -0,0 +1,426 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Generation and validation of finite field domain parameters (p, q, g) as used by DSA and
+ * Diffie-Hellman. The primes are generated from a seed using the procedure in FIPS 186-4 appendix
+ * A.1.1.2 and the generator using the verifiable canonical procedure in appendix A.2.3, so anyone
+ * who is given the seed can check that the parameters weren't chosen maliciously.
+ */
+
+use std::num::{One, Zero};
+use std::rand::Rng;
+
+use num::bigint::{BigUint, ToBigUint};
+use num::integer::Integer;
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be};
+use digest::Digest;
+use prime_rng::{modular_exp, probably_prime_faster};
+
+/// Domain parameters: the primes p and q, where q divides p - 1, and a generator g of the subgroup
+/// of order q.
+#[deriving(Clone, PartialEq)]
+pub struct DomainParams {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub g: BigUint
+}
+
+/// The values needed to verify that domain parameters were generated from a seed.
+#[deriving(Clone, PartialEq)]
+pub struct DomainParamsSeed {
+    /// The seed that p and q were generated from
+    pub seed: Vec<u8>,
+    /// The number of candidates for p that were rejected before p was found
+    pub counter: uint,
+    /// The index that distinguishes generators computed for the same p and q
+    pub index: u8
+}
+
+/// The reasons domain parameters can fail validation
+#[deriving(PartialEq, Show)]
+pub enum DomainParamsError {
+    /// The lengths of p and q are not one of the pairs allowed by FIPS 186-4
+    InvalidLengths,
+    /// p or q is not prime or q does not divide p - 1
+    InvalidPrimes,
+    /// p and q were not generated from the seed
+    InvalidSeed,
+    /// g does not generate the subgroup of order q or was not generated from the seed
+    InvalidGenerator
+}
+
+// The lengths of p and q, (L, N), allowed by FIPS 186-4
+static APPROVED_LENGTHS: [(uint, uint), ..4] = [(1024, 160), (2048, 224), (2048, 256), (3072, 256)];
+
+fn approved_lengths(l: uint, n: uint) -> bool {
+    APPROVED_LENGTHS.iter().any(|&(al, an)| al == l && an == n)
+}
+
+fn hash<D: Digest>(digest: &mut D, input: &[u8]) -> BigUint {
+    let mut out = Vec::from_elem(digest.output_bytes(), 0u8);
+    digest.reset();
+    digest.input(input);
+    digest.result(out.as_mut_slice());
+    return biguint_from_bytes_be(out.as_slice());
+}
+
+// Compute q from the seed. This is steps 6 and 7 of A.1.1.2.
+fn compute_q<D: Digest>(digest: &mut D, seed: &[u8], n: uint) -> BigUint {
+    let one: BigUint = One::one();
+    let top = one << (n - 1);
+    let u = hash(digest, seed) % top;
+    return top + u + one - (u % 2u.to_biguint().unwrap());
+}
+
+// Search for p, trying candidates until one is prime or max_counter candidates have been rejected.
+// Returns p and the number of rejected candidates. This is steps 10 and 11 of A.1.1.2.
+fn compute_p<D: Digest, R: Rng>(
+        digest: &mut D,
+        rng: &mut R,
+        l: uint,
+        seed: &[u8],
+        q: &BigUint,
+        max_counter: uint,
+        security: uint) -> Option<(BigUint, uint)> {
+    let one: BigUint = One::one();
+    let outlen = digest.output_bits();
+    let blocks = (l + outlen - 1) / outlen - 1;
+    let b = l - 1 - blocks * outlen;
+
+    let seed_value = biguint_from_bytes_be(seed);
+    let seed_modulus = one << (seed.len() * 8);
+    let top = one << (l - 1);
+    let two_q = *q << 1;
+
+    let mut offset = 1u;
+    for counter in range(0, max_counter + 1) {
+        let mut w: BigUint = Zero::zero();
+        for j in range(0, blocks + 1) {
+            let s = (seed_value + (offset + j).to_biguint().unwrap()) % seed_modulus;
+            let mut v = hash(digest, biguint_to_bytes_be(&s, seed.len()).as_slice());
+            if j == blocks {
+                v = v % (one << b);
+            }
+            w = w + (v << (j * outlen));
+        }
+
+        // p is the largest value less than or equal to x that is congruent to 1 mod 2q
+        let x = w + top;
+        let c = x % two_q;
+        let p = x - c + one;
+        if p >= top && probably_prime_faster(rng, &p, security) {
+            return Some((p, counter));
+        }
+
+        offset += blocks + 1;
+    }
+
+    return None;
+}
+
+// Compute the generator. This is the verifiable canonical generation procedure in A.2.3. Returns
+// None in the extremely unlikely case that no generator is found.
+fn compute_g<D: Digest>(
+        digest: &mut D,
+        p: &BigUint,
+        q: &BigUint,
+        seed: &[u8],
+        index: u8) -> Option<BigUint> {
+    let one: BigUint = One::one();
+    let two = 2u.to_biguint().unwrap();
+    let e = (*p - one) / *q;
+
+    for count in range(1u, 0x10000) {
+        let mut u = Vec::from_slice(seed);
+        u.push_all(b"ggen");
+        u.push(index);
+        u.push((count >> 8) as u8);
+        u.push(count as u8);
+        let w = hash(digest, u.as_slice());
+        let g = modular_exp(&w, &e, p);
+        if g >= two {
+            return Some(g);
+        }
+    }
+
+    return None;
+}
+
+/**
+ * Generate domain parameters from a given seed. This fails and returns None if the seed doesn't
+ * produce a prime q, in which case FIPS 186-4 requires trying again with a new seed. Given the same
+ * arguments, this always produces the same parameters.
+ *
+ * # Arguments
+ *
+ * * digest - The hash function to use. Its output must be at least n bits long.
+ * * rng - The random number generator used for primality testing
+ * * l - The length of p in bits
+ * * n - The length of q in bits. (l, n) must be one of (1024, 160), (2048, 224), (2048, 256) or
+ *       (3072, 256).
+ * * seed - The seed, which must be at least n bits long
+ * * index - An arbitrary value that allows generating different g values for the same p and q
+ * * security - The number of Miller-Rabin rounds used to test p and q
+ */
+pub fn generate_domain_params_from_seed<D: Digest, R: Rng>(
+        digest: &mut D,
+        rng: &mut R,
+        l: uint,
+        n: uint,
+        seed: &[u8],
+        index: u8,
+        security: uint) -> Option<(DomainParams, DomainParamsSeed)> {
+    assert!(approved_lengths(l, n));
+    assert!(digest.output_bits() >= n);
+    assert!(seed.len() * 8 >= n);
+
+    let q = compute_q(digest, seed, n);
+    if !probably_prime_faster(rng, &q, security) {
+        return None;
+    }
+
+    let (p, counter) = match compute_p(digest, rng, l, seed, &q, 4 * l - 1, security) {
+        Some(x) => x,
+        None => return None
+    };
+
+    let g = match compute_g(digest, &p, &q, seed, index) {
+        Some(x) => x,
+        None => return None
+    };
+
+    let params = DomainParams {
+        p: p,
+        q: q,
+        g: g
+    };
+    let params_seed = DomainParamsSeed {
+        seed: Vec::from_slice(seed),
+        counter: counter,
+        index: index
+    };
+    return Some((params, params_seed));
+}
+
+/**
+ * Generate domain parameters from a random seed. The arguments are the same as for
+ * generate_domain_params_from_seed(). The seed is taken from rng, which should be
+ * cryptographically secure.
+ */
+pub fn generate_domain_params<D: Digest, R: Rng>(
+        digest: &mut D,
+        rng: &mut R,
+        l: uint,
+        n: uint,
+        index: u8,
+        security: uint) -> (DomainParams, DomainParamsSeed) {
+    let mut seed = Vec::from_elem(n / 8, 0u8);
+    loop {
+        rng.fill_bytes(seed.as_mut_slice());
+        match generate_domain_params_from_seed(
+                digest, rng, l, n, seed.as_slice(), index, security) {
+            Some(x) => return x,
+            None => {}
+        }
+    }
+}
+
+/**
+ * Validate domain parameters, for example ones received from a peer. Without a seed, this checks
+ * that p and q are prime, that q divides p - 1 and that g generates the subgroup of order q
+ * (FIPS 186-4 A.2.2). With a seed, it also checks that p, q and g were generated from it (A.1.1.3
+ * and A.2.4). The digest must be the same hash function that the parameters were generated with.
+ */
+pub fn validate_domain_params<D: Digest, R: Rng>(
+        digest: &mut D,
+        rng: &mut R,
+        params: &DomainParams,
+        seed: Option<&DomainParamsSeed>,
+        security: uint) -> Result<(), DomainParamsError> {
+    let one: BigUint = One::one();
+    let two = 2u.to_biguint().unwrap();
+    let l = params.p.bits();
+    let n = params.q.bits();
+
+    if !approved_lengths(l, n) {
+        return Err(InvalidLengths);
+    }
+
+    match seed {
+        Some(s) => {
+            if s.counter > 4 * l - 1 || s.seed.len() * 8 < n || digest.output_bits() < n {
+                return Err(InvalidSeed);
+            }
+            if compute_q(digest, s.seed.as_slice(), n) != params.q {
+                return Err(InvalidSeed);
+            }
+            if !probably_prime_faster(rng, &params.q, security) {
+                return Err(InvalidPrimes);
+            }
+            // compute_p() stops at the first prime it finds, so this also checks that the
+            // counter is correct.
+            let valid = match compute_p(
+                    digest, rng, l, s.seed.as_slice(), &params.q, s.counter, security) {
+                Some((p, counter)) => p == params.p && counter == s.counter,
+                None => false
+            };
+            if !valid {
+                return Err(InvalidSeed);
+            }
+        }
+        None => {
+            if !probably_prime_faster(rng, &params.q, security) ||
+                    !probably_prime_faster(rng, &params.p, security) ||
+                    !(params.p - one).is_multiple_of(&params.q) {
+                return Err(InvalidPrimes);
+            }
+        }
+    }
+
+    if params.g < two || params.g >= params.p ||
+            modular_exp(&params.g, &params.q, &params.p) != one {
+        return Err(InvalidGenerator);
+    }
+
+    match seed {
+        Some(s) => {
+            match compute_g(digest, &params.p, &params.q, s.seed.as_slice(), s.index) {
+                Some(ref g) if *g == params.g => {}
+                _ => return Err(InvalidGenerator)
+            }
+        }
+        None => {}
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::One;
+    use std::rand::IsaacRng;
+
+    use num::bigint::{BigUint, ToBigUint};
+
+    use domain_params::{DomainParams, DomainParamsSeed, generate_domain_params,
+        generate_domain_params_from_seed, validate_domain_params, InvalidLengths, InvalidPrimes,
+        InvalidSeed, InvalidGenerator};
+    use sha2::Sha256;
+
+    fn from_hex(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    fn test_seed(i: u8) -> Vec<u8> {
+        let mut seed = vec![0u8, i];
+        seed.push_all(Vec::from_fn(18, |j| j as u8).as_slice());
+        return seed;
+    }
+
+    // The expected values were computed with an independent implementation of A.1.1.2 and A.2.3.
+    fn expected() -> (DomainParams, DomainParamsSeed) {
+        let params = DomainParams {
+            p: from_hex("dd5b37926664ff7008f5702e96896b169e7f3636e1eb5a1b28ed48e5ef2812e2dbd9cb068d6e68a695f6853cf5c2666e46002b11d72556ac545361fdbb12577840f82997795c6e907f3e7e943fec8e87c8f53509adab06dbc4e68b33675fa3ebfd89d8fa528521a2eb98b0cd9b4d4dbda266962e062dceb8397a11331d067a0d"),
+            q: from_hex("aa366d274d94b9736f984a19396b40a6fd995211"),
+            g: from_hex("a7845f8caaeea0d45acffa5bca432991e249a2300983f7c64d8023d2f19c7308a57f51082d0676b1723cc628ff5c1d846f2f275ee5098aea2ba73bd034324e21c00e54f244d07edf6c73e81a0c64fa411e013a445c4344c5d9a812dd83528c889136d6a2502feac62ac281f55da34ca51af1ecff1b8388ac3b620e62cb99dffa")
+        };
+        let params_seed = DomainParamsSeed {
+            seed: test_seed(13),
+            counter: 617,
+            index: 1
+        };
+        return (params, params_seed);
+    }
+
+    #[test]
+    fn test_generate_from_seed() {
+        let mut rng = IsaacRng::new_unseeded();
+
+        // None of the earlier seeds produce a prime q
+        for i in range(0u8, 13) {
+            assert!(generate_domain_params_from_seed(
+                &mut Sha256::new(), &mut rng, 1024, 160, test_seed(i).as_slice(), 1, 40).is_none());
+        }
+
+        let (params, params_seed) = generate_domain_params_from_seed(
+            &mut Sha256::new(), &mut rng, 1024, 160, test_seed(13).as_slice(), 1, 40).unwrap();
+        let (expected_params, expected_seed) = expected();
+        assert!(params == expected_params);
+        assert!(params_seed == expected_seed);
+    }
+
+    #[test]
+    fn test_generate_reproducible() {
+        let mut rng1 = IsaacRng::new_unseeded();
+        let mut rng2 = IsaacRng::new_unseeded();
+        let (params1, seed1) =
+            generate_domain_params(&mut Sha256::new(), &mut rng1, 1024, 160, 1, 40);
+        let (params2, seed2) =
+            generate_domain_params(&mut Sha256::new(), &mut rng2, 1024, 160, 1, 40);
+        assert!(params1 == params2);
+        assert!(seed1 == seed2);
+
+        let mut rng = IsaacRng::new_unseeded();
+        assert!(validate_domain_params(
+            &mut Sha256::new(), &mut rng, &params1, Some(&seed1), 40).is_ok());
+        assert!(validate_domain_params(&mut Sha256::new(), &mut rng, &params1, None, 40).is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut rng = IsaacRng::new_unseeded();
+        let one: BigUint = One::one();
+        let (params, params_seed) = expected();
+
+        let validate = |params: &DomainParams, params_seed: Option<&DomainParamsSeed>| {
+            validate_domain_params(&mut Sha256::new(), &mut rng, params, params_seed, 40)
+        };
+
+        assert_eq!(validate(&params, Some(&params_seed)), Ok(()));
+        assert_eq!(validate(&params, None), Ok(()));
+
+        let mut bad_seed = params_seed.clone();
+        *bad_seed.seed.get_mut(19) ^= 1;
+        assert_eq!(validate(&params, Some(&bad_seed)), Err(InvalidSeed));
+
+        let mut bad_seed = params_seed.clone();
+        bad_seed.counter += 1;
+        assert_eq!(validate(&params, Some(&bad_seed)), Err(InvalidSeed));
+
+        let mut bad_seed = params_seed.clone();
+        bad_seed.index = 2;
+        assert_eq!(validate(&params, Some(&bad_seed)), Err(InvalidGenerator));
+
+        // A different generator of the same subgroup is fine without a seed, but doesn't match the
+        // one generated from the seed
+        let mut other_g = params.clone();
+        other_g.g = (params.g * params.g) % params.p;
+        assert_eq!(validate(&other_g, None), Ok(()));
+        assert_eq!(validate(&other_g, Some(&params_seed)), Err(InvalidGenerator));
+
+        let mut bad_g = params.clone();
+        bad_g.g = one.clone();
+        assert_eq!(validate(&bad_g, None), Err(InvalidGenerator));
+        bad_g.g = params.p - one;
+        assert_eq!(validate(&bad_g, None), Err(InvalidGenerator));
+        bad_g.g = params.p.clone();
+        assert_eq!(validate(&bad_g, None), Err(InvalidGenerator));
+
+        let mut bad_p = params.clone();
+        bad_p.p = params.p + (params.q << 1);
+        assert_eq!(validate(&bad_p, None), Err(InvalidPrimes));
+
+        let mut bad_q = params.clone();
+        bad_q.q = params.q + 2u.to_biguint().unwrap();
+        assert_eq!(validate(&bad_q, None), Err(InvalidPrimes));
+
+        let mut bad_lengths = params.clone();
+        bad_lengths.p = params.p >> 1;
+        assert_eq!(validate(&bad_lengths, None), Err(InvalidLengths));
+    }
+}
@@ -0,0 +1,984 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * The deterministic random bit generators HMAC_DRBG, Hash_DRBG and CTR_DRBG from NIST SP 800-90A.
+ *
+ * A DRBG is instantiated from entropy input, a nonce and an optional personalization string, and
+ * its output is completely determined by those and by the inputs of later reseed and generate
+ * calls. The caller provides the entropy input, which must come from a suitable source, such as
+ * OsRng, and contain at least as many bits of entropy as the security strength of the DRBG: the
+ * security strength of the digest for HMAC_DRBG and Hash_DRBG, and the key size for CTR_DRBG. The
+ * nonce should be at least half as long. Since the output only depends on the inputs, a DRBG
+ * seeded with fixed values is also useful for reproducible tests.
+ *
+ * A single request is limited to 2^16 bytes, and a reseed is required after 2^48 requests or a
+ * smaller interval set with set_reseed_interval(). CTR_DRBG always uses the derivation function,
+ * so all of its inputs may have any length. Prediction resistance isn't built in; a caller that
+ * needs it reseeds with fresh entropy before every request.
+ *
+ * The generators also implement Rng, so they can be used for key generation. Since the methods of
+ * Rng can't return errors, they fail if a reseed is required.
+ */
+
+use std::rand::Rng;
+use std::slice;
+
+use aes::{KeySize, KeySize128, KeySize192, KeySize256};
+use aessafe;
+use cryptoutil::{push_u32_be, read_u32_be, read_u64_be, write_u32_be, write_u64_be};
+use digest::Digest;
+use hmac::Hmac;
+use mac::Mac;
+use symmetriccipher::BlockEncryptor;
+
+/// The maximum number of bytes that may be requested from a single call to generate()
+pub static MAX_REQUEST_BYTES: uint = 1 << 16;
+
+/// The maximum and default number of requests between reseeds
+pub static MAX_RESEED_INTERVAL: u64 = 1 << 48;
+
+/// The errors that can occur when generating output
+#[deriving(PartialEq, Show)]
+pub enum DrbgError {
+    /// The reseed interval has been reached, so the DRBG must be reseeded before further use
+    ReseedRequired,
+    /// More than MAX_REQUEST_BYTES bytes were requested
+    RequestTooLarge
+}
+
+/// The operations that are common to all of the DRBGs
+pub trait Drbg {
+    /// Reseed the DRBG with fresh entropy input and optional additional input, which may be empty
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]);
+
+    /**
+     * Fill the output with pseudorandom bytes. Optional additional input, which may be empty, is
+     * mixed into the state before and after generating the bytes.
+     */
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError>;
+
+    /// One more than the number of requests since the DRBG was instantiated or last reseeded
+    fn reseed_counter(&self) -> u64;
+
+    /// Set the number of requests after which a reseed is required, which is at least 1 and at
+    /// most MAX_RESEED_INTERVAL
+    fn set_reseed_interval(&mut self, interval: u64);
+}
+
+// Check a request against the limits shared by all of the DRBGs.
+fn check_request(out: &[u8], reseed_counter: u64, reseed_interval: u64) -> Result<(), DrbgError> {
+    if out.len() > MAX_REQUEST_BYTES {
+        return Err(RequestTooLarge);
+    }
+    if reseed_counter > reseed_interval {
+        return Err(ReseedRequired);
+    }
+    Ok(())
+}
+
+fn check_reseed_interval(interval: u64) {
+    assert!(interval >= 1 && interval <= MAX_RESEED_INTERVAL);
+}
+
+// Fill the output for Rng, splitting it into requests of the maximum size.
+fn rng_fill_bytes<G: Drbg>(drbg: &mut G, out: &mut [u8]) {
+    for chunk in out.mut_chunks(MAX_REQUEST_BYTES) {
+        match drbg.generate(chunk, []) {
+            Ok(()) => { }
+            Err(e) => fail!("DRBG failed to generate output: {}", e)
+        }
+    }
+}
+
+fn rng_next_u32<G: Drbg>(drbg: &mut G) -> u32 {
+    let mut buf = [0u8, ..4];
+    rng_fill_bytes(drbg, buf);
+    read_u32_be(buf)
+}
+
+fn rng_next_u64<G: Drbg>(drbg: &mut G) -> u64 {
+    let mut buf = [0u8, ..8];
+    rng_fill_bytes(drbg, buf);
+    read_u64_be(buf)
+}
+
+/// HMAC_DRBG (SP 800-90A section 10.1.2) using HMAC with any digest
+pub struct HmacDrbg<D> {
+    digest: D,
+    k: Vec<u8>,
+    v: Vec<u8>,
+    reseed_counter: u64,
+    reseed_interval: u64
+}
+
+impl <D: Digest + Clone> HmacDrbg<D> {
+    /**
+     * Instantiate a new HMAC_DRBG.
+     *
+     * # Arguments
+     * * digest - The Digest to use with HMAC.
+     * * entropy - The entropy input.
+     * * nonce - The nonce.
+     * * personalization - The personalization string, which may be empty.
+     *
+     */
+    pub fn new(digest: D, entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> HmacDrbg<D> {
+        let len = digest.output_bytes();
+        let mut drbg = HmacDrbg {
+            digest: digest,
+            k: Vec::from_elem(len, 0u8),
+            v: Vec::from_elem(len, 1u8),
+            reseed_counter: 1,
+            reseed_interval: MAX_RESEED_INTERVAL
+        };
+        drbg.update([entropy, nonce, personalization]);
+        return drbg;
+    }
+
+    // Compute HMAC_K(data).
+    fn hmac(&self, data: &[u8]) -> Vec<u8> {
+        let mut digest = self.digest.clone();
+        digest.reset();
+        let mut mac = Hmac::new(digest, self.k.as_slice());
+        mac.input(data);
+        let mut out = Vec::from_elem(mac.output_bytes(), 0u8);
+        mac.raw_result(out.as_mut_slice());
+        return out;
+    }
+
+    // The HMAC_DRBG_Update function, where the provided data is the concatenation of the slices.
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        let rounds = if provided_data.iter().all(|d| d.is_empty()) { 1 } else { 2 };
+        for i in range(0u8, rounds) {
+            let mut data = self.v.clone();
+            data.push(i);
+            for d in provided_data.iter() {
+                data.push_all(*d);
+            }
+            self.k = self.hmac(data.as_slice());
+            self.v = self.hmac(self.v.as_slice());
+        }
+    }
+}
+
+impl <D: Digest + Clone> Drbg for HmacDrbg<D> {
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        self.update([entropy, additional_input]);
+        self.reseed_counter = 1;
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        try!(check_request(out, self.reseed_counter, self.reseed_interval));
+        if !additional_input.is_empty() {
+            self.update([additional_input]);
+        }
+        for chunk in out.mut_chunks(self.v.len()) {
+            self.v = self.hmac(self.v.as_slice());
+            slice::bytes::copy_memory(chunk, self.v.slice_to(chunk.len()));
+        }
+        self.update([additional_input]);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn set_reseed_interval(&mut self, interval: u64) {
+        check_reseed_interval(interval);
+        self.reseed_interval = interval;
+    }
+}
+
+impl <D: Digest + Clone> Rng for HmacDrbg<D> {
+    fn next_u32(&mut self) -> u32 {
+        rng_next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rng_next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rng_fill_bytes(self, dest)
+    }
+}
+
+// Hash the concatenation of the slices.
+fn hash<D: Digest>(digest: &mut D, data: &[&[u8]]) -> Vec<u8> {
+    digest.reset();
+    for d in data.iter() {
+        digest.input(*d);
+    }
+    let mut out = Vec::from_elem(digest.output_bytes(), 0u8);
+    digest.result(out.as_mut_slice());
+    return out;
+}
+
+// The Hash_df function, which derives len bytes from the concatenation of the slices.
+fn hash_df<D: Digest>(digest: &mut D, data: &[&[u8]], len: uint) -> Vec<u8> {
+    let mut bits = [0u8, ..4];
+    write_u32_be(bits, (len * 8) as u32);
+    let mut out = Vec::with_capacity(len + digest.output_bytes());
+    let mut counter = 1u8;
+    while out.len() < len {
+        let counter_bytes = [counter];
+        let mut input = vec![counter_bytes.as_slice(), bits.as_slice()];
+        input.push_all(data);
+        out.push_all(hash(digest, input.as_slice()).as_slice());
+        counter += 1;
+    }
+    out.truncate(len);
+    return out;
+}
+
+// Add the big endian number b to a modulo 2^(8 * a.len()). b must not be longer than a.
+fn add_be(a: &mut [u8], b: &[u8]) {
+    let mut carry = 0u16;
+    let mut j = b.len();
+    for x in a.mut_iter().rev() {
+        let mut sum = *x as u16 + carry;
+        if j > 0 {
+            j -= 1;
+            sum += b[j] as u16;
+        }
+        *x = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+/// Hash_DRBG (SP 800-90A section 10.1.1) using any digest
+pub struct HashDrbg<D> {
+    digest: D,
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: u64,
+    reseed_interval: u64
+}
+
+impl <D: Digest> HashDrbg<D> {
+    /**
+     * Instantiate a new Hash_DRBG.
+     *
+     * # Arguments
+     * * digest - The Digest to use.
+     * * entropy - The entropy input.
+     * * nonce - The nonce.
+     * * personalization - The personalization string, which may be empty.
+     *
+     */
+    pub fn new(mut digest: D, entropy: &[u8], nonce: &[u8], personalization: &[u8])
+            -> HashDrbg<D> {
+        // The seed length is 440 bits for digests of up to 256 bits and 888 bits otherwise
+        let seed_len = if digest.output_bytes() <= 32 { 55 } else { 111 };
+        let v = hash_df(&mut digest, [entropy, nonce, personalization], seed_len);
+        let c = hash_df(&mut digest, [[0u8].as_slice(), v.as_slice()], seed_len);
+        HashDrbg {
+            digest: digest,
+            v: v,
+            c: c,
+            reseed_counter: 1,
+            reseed_interval: MAX_RESEED_INTERVAL
+        }
+    }
+}
+
+impl <D: Digest> Drbg for HashDrbg<D> {
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        let seed_len = self.v.len();
+        self.v = hash_df(&mut self.digest,
+            [[1u8].as_slice(), self.v.as_slice(), entropy, additional_input], seed_len);
+        self.c = hash_df(&mut self.digest, [[0u8].as_slice(), self.v.as_slice()], seed_len);
+        self.reseed_counter = 1;
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        try!(check_request(out, self.reseed_counter, self.reseed_interval));
+        if !additional_input.is_empty() {
+            let w = hash(&mut self.digest, [[2u8].as_slice(), self.v.as_slice(), additional_input]);
+            add_be(self.v.as_mut_slice(), w.as_slice());
+        }
+
+        // Hashgen
+        let mut data = self.v.clone();
+        for chunk in out.mut_chunks(self.digest.output_bytes()) {
+            let w = hash(&mut self.digest, [data.as_slice()]);
+            slice::bytes::copy_memory(chunk, w.slice_to(chunk.len()));
+            add_be(data.as_mut_slice(), [1]);
+        }
+
+        // V = V + H + C + reseed_counter
+        let h = hash(&mut self.digest, [[3u8].as_slice(), self.v.as_slice()]);
+        let mut counter = [0u8, ..8];
+        write_u64_be(counter, self.reseed_counter);
+        add_be(self.v.as_mut_slice(), h.as_slice());
+        add_be(self.v.as_mut_slice(), self.c.as_slice());
+        add_be(self.v.as_mut_slice(), counter);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn set_reseed_interval(&mut self, interval: u64) {
+        check_reseed_interval(interval);
+        self.reseed_interval = interval;
+    }
+}
+
+impl <D: Digest> Rng for HashDrbg<D> {
+    fn next_u32(&mut self) -> u32 {
+        rng_next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rng_next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rng_fill_bytes(self, dest)
+    }
+}
+
+fn key_len(key_size: KeySize) -> uint {
+    match key_size {
+        KeySize128 => 16,
+        KeySize192 => 24,
+        KeySize256 => 32
+    }
+}
+
+// CTR_DRBG replaces its key on every request, so it uses the software AES implementation, which
+// has a cheap key schedule and runs in constant time.
+fn new_cipher(key_size: KeySize, key: &[u8]) -> Box<BlockEncryptor> {
+    match key_size {
+        KeySize128 => box aessafe::AesSafe128Encryptor::new(key) as Box<BlockEncryptor>,
+        KeySize192 => box aessafe::AesSafe192Encryptor::new(key) as Box<BlockEncryptor>,
+        KeySize256 => box aessafe::AesSafe256Encryptor::new(key) as Box<BlockEncryptor>
+    }
+}
+
+// The BCC function: the CBC-MAC of the initial block followed by the data, which is a multiple of
+// the block size long.
+fn bcc(cipher: &BlockEncryptor, iv: &[u8], data: &[u8]) -> [u8, ..16] {
+    let mut chain = [0u8, ..16];
+    cipher.encrypt_block(iv, chain);
+    for block in data.chunks(16) {
+        let mut input = [0u8, ..16];
+        for (x, (&c, &b)) in input.mut_iter().zip(chain.iter().zip(block.iter())) {
+            *x = c ^ b;
+        }
+        cipher.encrypt_block(input, chain);
+    }
+    return chain;
+}
+
+// The Block_Cipher_df function, which derives len bytes from the concatenation of the slices.
+fn block_cipher_df(key_size: KeySize, data: &[&[u8]], len: uint) -> Vec<u8> {
+    let key_len = key_len(key_size);
+
+    // S = L || N || input || 0x80, padded with zeros to a multiple of the block size
+    let mut s = Vec::new();
+    push_u32_be(&mut s, data.iter().fold(0, |sum, d| sum + d.len()) as u32);
+    push_u32_be(&mut s, len as u32);
+    for d in data.iter() {
+        s.push_all(*d);
+    }
+    s.push(0x80);
+    while s.len() % 16 != 0 {
+        s.push(0);
+    }
+
+    let key: Vec<u8> = range(0u8, key_len as u8).collect();
+    let cipher = new_cipher(key_size, key.as_slice());
+    let mut temp = Vec::with_capacity(key_len + 16);
+    let mut i = 0u32;
+    while temp.len() < key_len + 16 {
+        let mut iv = [0u8, ..16];
+        write_u32_be(iv.mut_slice_to(4), i);
+        temp.push_all(bcc(&*cipher, iv, s.as_slice()));
+        i += 1;
+    }
+
+    let cipher = new_cipher(key_size, temp.slice_to(key_len));
+    let mut x = [0u8, ..16];
+    slice::bytes::copy_memory(x, temp.slice(key_len, key_len + 16));
+    let mut out = Vec::with_capacity(len + 16);
+    while out.len() < len {
+        let mut y = [0u8, ..16];
+        cipher.encrypt_block(x, y);
+        x = y;
+        out.push_all(x);
+    }
+    out.truncate(len);
+    return out;
+}
+
+// Increment a big endian counter modulo 2^(8 * v.len()).
+fn increment(v: &mut [u8]) {
+    for x in v.mut_iter().rev() {
+        *x += 1;
+        if *x != 0 {
+            break;
+        }
+    }
+}
+
+/// CTR_DRBG (SP 800-90A section 10.2.1) using AES with the derivation function
+pub struct CtrDrbg {
+    key_size: KeySize,
+    cipher: Box<BlockEncryptor>,
+    v: [u8, ..16],
+    reseed_counter: u64,
+    reseed_interval: u64
+}
+
+impl CtrDrbg {
+    /**
+     * Instantiate a new CTR_DRBG.
+     *
+     * # Arguments
+     * * key_size - The size of the AES key.
+     * * entropy - The entropy input.
+     * * nonce - The nonce.
+     * * personalization - The personalization string, which may be empty.
+     *
+     */
+    pub fn new(key_size: KeySize, entropy: &[u8], nonce: &[u8], personalization: &[u8])
+            -> CtrDrbg {
+        let key_len = key_len(key_size);
+        let key = Vec::from_elem(key_len, 0u8);
+        let mut drbg = CtrDrbg {
+            key_size: key_size,
+            cipher: new_cipher(key_size, key.as_slice()),
+            v: [0u8, ..16],
+            reseed_counter: 1,
+            reseed_interval: MAX_RESEED_INTERVAL
+        };
+        let seed = block_cipher_df(key_size, [entropy, nonce, personalization], key_len + 16);
+        drbg.update(seed.as_slice());
+        return drbg;
+    }
+
+    fn seed_len(&self) -> uint {
+        key_len(self.key_size) + 16
+    }
+
+    // The CTR_DRBG_Update function. The provided data is seed_len() bytes long.
+    fn update(&mut self, provided_data: &[u8]) {
+        let key_len = key_len(self.key_size);
+        let mut temp = Vec::with_capacity(key_len + 32);
+        while temp.len() < key_len + 16 {
+            increment(self.v);
+            let mut block = [0u8, ..16];
+            self.cipher.encrypt_block(self.v, block);
+            temp.push_all(block);
+        }
+        temp.truncate(key_len + 16);
+        for (t, &p) in temp.mut_iter().zip(provided_data.iter()) {
+            *t ^= p;
+        }
+        self.cipher = new_cipher(self.key_size, temp.slice_to(key_len));
+        slice::bytes::copy_memory(self.v, temp.slice_from(key_len));
+    }
+}
+
+impl Drbg for CtrDrbg {
+    fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) {
+        let seed = block_cipher_df(self.key_size, [entropy, additional_input], self.seed_len());
+        self.update(seed.as_slice());
+        self.reseed_counter = 1;
+    }
+
+    fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), DrbgError> {
+        try!(check_request(out, self.reseed_counter, self.reseed_interval));
+        let additional = if additional_input.is_empty() {
+            Vec::from_elem(self.seed_len(), 0u8)
+        } else {
+            let additional = block_cipher_df(self.key_size, [additional_input], self.seed_len());
+            self.update(additional.as_slice());
+            additional
+        };
+        for chunk in out.mut_chunks(16) {
+            increment(self.v);
+            let mut block = [0u8, ..16];
+            self.cipher.encrypt_block(self.v, block);
+            slice::bytes::copy_memory(chunk, block.slice_to(chunk.len()));
+        }
+        self.update(additional.as_slice());
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    fn set_reseed_interval(&mut self, interval: u64) {
+        check_reseed_interval(interval);
+        self.reseed_interval = interval;
+    }
+}
+
+impl Rng for CtrDrbg {
+    fn next_u32(&mut self) -> u32 {
+        rng_next_u32(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rng_next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rng_fill_bytes(self, dest)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::Rng;
+
+    use serialize::hex::{FromHex, ToHex};
+
+    use aes::{KeySize128, KeySize256};
+    use cryptoutil::read_u32_be;
+    use drbg::{Drbg, HmacDrbg, HashDrbg, CtrDrbg, ReseedRequired, RequestTooLarge,
+        MAX_REQUEST_BYTES};
+    use ec::{Curve, EcPrivateKey};
+    use sha2::{Sha256, Sha512};
+
+    struct Test {
+        entropy: &'static str,
+        nonce: &'static str,
+        personalization: &'static str,
+        entropy_reseed: &'static str,
+        additional_reseed: &'static str,
+        additional: [&'static str, ..2],
+        output: &'static str
+    }
+
+    // The tests are from the CAVP DRBG response files HMAC_DRBG.rsp, Hash_DRBG.rsp and
+    // CTR_DRBG.rsp, CAVS 14.3, in
+    // http://csrc.nist.gov/groups/STM/cavp/documents/drbg/drbgtestvectors.zip.
+    //
+    // HMAC_DRBG with SHA-256: sections without prediction resistance or reseeding.
+    // HMAC_DRBG with SHA-512: COUNT = 0 without prediction resistance, with neither a
+    // personalization string nor additional input and with both.
+    // CTR_DRBG with AES-128: COUNT = 0 of [AES-128 use df] without reseeding, with and without
+    // additional input, and COUNT = 0 without prediction resistance.
+    // CTR_DRBG with AES-256: COUNT = 0 of [AES-256 use df] without prediction resistance.
+    static HMAC_SHA256_TESTS: &'static [Test] = &[
+        Test {
+            entropy: "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488",
+            nonce: "659ba96c601dc69fc902940805ec0ca8",
+            personalization: "",
+            entropy_reseed: "",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8"
+        },
+        Test {
+            entropy: "79737479ba4e7642a221fcfd1b820b134e9e3540a35bb48ffae29c20f5418ea3",
+            nonce: "3593259c092bef4129bc2c6c9e19f343",
+            personalization: "",
+            entropy_reseed: "",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "cf5ad5984f9e43917aa9087380dac46e410ddc8a7731859c84e9d0f31bd43655b924159413e2293b17610f211e09f770f172b8fb693a35b85d3b9e5e63b1dc252ac0e115002e9bedfb4b5b6fd43f33b8e0eafb2d072e1a6fee1f159df9b51e6c8da737e60d5032dd30544ec51558c6f080bdbdab1de8a939e961e06b5f1aca37"
+        },
+        Test {
+            entropy: "d3cc4d1acf3dde0c4bd2290d262337042dc632948223d3a2eaab87da44295fbd",
+            nonce: "0109b0e729f457328aa18569a9224921",
+            personalization: "",
+            entropy_reseed: "",
+            additional_reseed: "",
+            additional: ["3c311848183c9a212a26f27f8c6647e40375e466a0857cc39c4e47575d53f1f6", "fcb9abd19ccfbccef88c9c39bfb3dd7b1c12266c9808992e305bc3cff566e4e4"],
+            output: "9c7b758b212cd0fcecd5daa489821712e3cdea4467b560ef5ddc24ab47749a1f1ffdbbb118f4e62fcfca3371b8fbfc5b0646b83e06bfbbab5fac30ea09ea2bc76f1ea568c9be0444b2cc90517b20ca825f2d0eccd88e7175538b85d90ab390183ca6395535d34473af6b5a5b88f5a59ee7561573337ea819da0dcc3573a22974"
+        },
+        Test {
+            entropy: "f97a3cfd91faa046b9e61b9493d436c4931f604b22f1081521b3419151e8ff06",
+            nonce: "11f3a7d43595357d58120bd1e2dd8aed",
+            personalization: "",
+            entropy_reseed: "",
+            additional_reseed: "",
+            additional: ["517289afe444a0fe5ed1a41dbbb5eb17150079bdd31e29cf2ff30034d8268e3b", "88028d29ef80b4e6f0fe12f91d7449fe75062682e89c571440c0c9b52c42a6e0"],
+            output: "c6871cff0824fe55ea7689a52229886730450e5d362da5bf590dcf9acd67fed4cb32107df5d03969a66b1f6494fdf5d63d5b4d0d34ea7399a07d0116126d0d518c7c55ba46e12f62efc8fe28a51c9d428e6d371d7397ab319fc73ded4722e5b4f30004032a6128df5e7497ecf82ca7b0a50e867ef6728a4f509a8c859087039c"
+        }
+    ];
+
+    static HMAC_SHA512_TESTS: &'static [Test] = &[
+        Test {
+            entropy: "48c121b18733af15c27e1dd9ba66a9a81a5579cdba0f5b657ec53c2b9e90bbf6",
+            nonce: "bbb7c777428068fad9970891f879b1af",
+            personalization: "",
+            entropy_reseed: "e0ffefdadb9ccf990504d568bdb4d862cbe17ccce6e22dfcab8b4804fd21421a",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "05da6aac7d980da038f65f392841476d37fe70fbd3e369d1f80196e66e54b8fadb1d60e1a0f3d4dc173769d75fc3410549d7a843270a54a068b4fe767d7d9a59604510a875ad1e9731c8afd0fd50b825e2c50d062576175106a9981be37e02ec7c5cd0a69aa0ca65bddaee1b0de532e10cfa1f5bf6a026e47379736a099d6750ab121dbe3622b841baf8bdcbe875c85ba4b586b8b5b57b0fecbec08c12ff2a9453c47c6e32a52103d972c62ab9affb8e728a31fcefbbccc556c0f0a35f4b10ace2d96b906e36cbb72233201e536d3e13b045187b417d2449cad1edd192e061f12d22147b0a176ea8d9c4c35404395b6502ef333a813b6586037479e0fa3c6a23"
+        },
+        Test {
+            entropy: "da740cbc36057a8e282ae717fe7dfbb245e9e5d49908a0119c5dbcf0a1f2d5ab",
+            nonce: "46561ff612217ba3ff91baa06d4b5440",
+            personalization: "fc227293523ecb5b1e28c87863626627d958acc558a672b148ce19e2abd2dde4",
+            entropy_reseed: "1d61d4d8a41c3254b92104fd555adae0569d1835bb52657ec7fbba0fe03579c5",
+            additional_reseed: "b9ed8e35ad018a375b61189c8d365b00507cb1b4510d21cac212356b5bbaa8b2",
+            additional: ["b7998998eaf9e5d34e64ff7f03de765b31f407899d20535573e670c1b402c26a", "2089d49d63e0c4df58879d0cb1ba998e5b3d1a7786b785e7cf13ca5ea5e33cfd"],
+            output: "5b70f3e4da95264233efbab155b828d4e231b67cc92757feca407cc9615a660871cb07ad1a2e9a99412feda8ee34dc9c57fa08d3f8225b30d29887d20907d12330fffd14d1697ba0756d37491b0a8814106e46c8677d49d9157109c402ad0c247a2f50cd5d99e538c850b906937a05dbb8888d984bc77f6ca00b0e3bc97b16d6d25814a54aa12143afddd8b2263690565d545f4137e593bb3ca88a37b0aadf79726b95c61906257e6dc47acd5b6b7e4b534243b13c16ad5a0a1163c0099fce43f428cd27c3e6463cf5e9a9621f4b3d0b3d4654316f4707675df39278d5783823049477dcce8c57fdbd576711c91301e9bd6bb0d3e72dc46d480ed8f61fd63811"
+        }
+    ];
+
+    static CTR_AES128_TESTS: &'static [Test] = &[
+        Test {
+            entropy: "890eb067acf7382eff80b0c73bc872c6",
+            nonce: "aad471ef3ef1d203",
+            personalization: "",
+            entropy_reseed: "",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "a5514ed7095f64f3d0d3a5760394ab42062f373a25072a6ea6bcfd8489e94af6cf18659fea22ed1ca0a9e33f718b115ee536b12809c31b72b08ddd8be1910fa3"
+        },
+        Test {
+            entropy: "b408cefb5bc7157d3f26cb95a8b1d7ac",
+            nonce: "026c768fd577b92a",
+            personalization: "",
+            entropy_reseed: "",
+            additional_reseed: "",
+            additional: ["5737ef81dee365b6dadb3feebf5d1084", "3368a516b3431a3daaa60dc8743c8297"],
+            output: "4e909ebb24147a0004063a5e47ee044fead610d62324bd0f963f756fb91361e8b87e3a76a398143fe88130fe1b547b661a6480c711b739f18a9df3ae51d41bc9"
+        },
+        Test {
+            entropy: "0f65da13dca407999d4773c2b4a11d85",
+            nonce: "5209e5b4ed82a234",
+            personalization: "",
+            entropy_reseed: "1dea0a12c52bf64339dd291c80d8ca89",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "2859cc468a76b08661ffd23b28547ffd0997ad526a0f51261b99ed3a37bd407bf418dbe6c6c3e26ed0ddefcb7474d899bd99f3655427519fc5b4057bcaf306d4"
+        }
+    ];
+
+    static CTR_AES256_TESTS: &'static [Test] = &[
+        Test {
+            entropy: "2d4c9f46b981c6a0b2b5d8c69391e569ff13851437ebc0fc00d616340252fed5",
+            nonce: "0bf814b411f65ec4866be1abb59d3c32",
+            personalization: "",
+            entropy_reseed: "93500fae4fa32b86033b7a7bac9d37e710dcc67ca266bc8607d665937766d207",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "322dd28670e75c0ea638f3cb68d6a9d6e50ddfd052b772a7b1d78263a7b8978b6740c2b65a9550c3a76325866fa97e16d74006bc96f26249b9f0a90d076f08e5"
+        }
+    ];
+
+    // The Hash_DRBG tests without prediction resistance aren't from CAVP. They follow the CAVP
+    // procedure with a reseed, cover additional input and the 888 bit seed length of SHA-512 and
+    // were checked against the HASH-DRBG of OpenSSL 3.5.
+    static HASH_SHA256_TESTS: &'static [Test] = &[
+        Test {
+            entropy: "ab6ec188b5d1b7bfb2c1247f8021f8deb8178d8fbedcd08678a1f4f78027d7d3",
+            nonce: "c1d2e0f67ee0bc6da3ba8d3a6306501e",
+            personalization: "",
+            entropy_reseed: "7012fbd85d4b370d1ba4de9063dd5d2d11950665d64ea30a0496e1cc9cea83fd",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "f3f69f4e61f7af9c6c763378611a62d060d259c031928c4920d49edef29e8a9cdbcfd9f1b976cf0754c125b61a2e95a9799f20b8dd81a131a1725e3124b12aac8760c41850d84913dcf94e8fb32e9bba5f705bb36f8dc9d0dc7047a439988ddf9757f2227ff50f43f8c4e289db38102b099e7cbfc455dd19529ba2ad3e58c730"
+        },
+        Test {
+            entropy: "cbbcf86598c349e3b834f6039ae0bae0ec60e9a051bb534693d2586f4e040839",
+            nonce: "3c60900f72f75be7f419289a47d6d61d",
+            personalization: "745ad070cb042c5e1d2891e975194541274d9bb5115d72510fa20f7645a2a077",
+            entropy_reseed: "7c1696e06c2754e8157c108d393f681741791cdc0f88a7347d0e116593b3773b",
+            additional_reseed: "ad2fe1d5001ea51bd867c0cd99a9cc55b85df359b26596c3131f5d63f76641f9",
+            additional: ["1c52ee485abd7ba3006a52f4f086e089e6d715a3238695fdc43b2556667e5fda", "90c29ecd898446be7db07645484f33c11c59ba9730e6a42b17a5dd11a94307c0"],
+            output: "4d85cf08ae572356785bbc682372e326812f28475ef6529fad6b6a409b59a51b4533acf076ccb75fd515086bb29a3ede203549931c89c0fbc41b5cfbb88d595e849167db8bccadfa485c1a510f04ae632bc57dbe6cc0489dd1255994b10f488f150313c0fdc2b04451b253c4dbc3e8632f1a7f10b4b854d932c730431b9b5697"
+        }
+    ];
+
+    static HASH_SHA512_TESTS: &'static [Test] = &[
+        Test {
+            entropy: "3204a1eee9f2c9c6007cd75c6c0882a25e5a9ab8da8507257730a023558ddaac",
+            nonce: "714910f290d99125f935aa282ecb1c91",
+            personalization: "",
+            entropy_reseed: "bc88a8d9cd205bb18bfbc74983650ad97e86911d2b0ecc9f455b488085b07575",
+            additional_reseed: "",
+            additional: ["", ""],
+            output: "1c4ff3abc72080de607eb59e6e32724fa76ae4a4f08b9b2a297c0a0a51d71367fef445aedd2aabcb2bfd6684b449ca128afd3f9f7e403724d9126b51618dfecb3eeac73af406b61fbade243b1eaf993c2f2a302510ca92fc4448798440e3f293d1bed67bd8d007fb626ff141ec8f7e99df230d32d07281a5966f42d104f96b84330f850b1691f3a91610682aeb50289ce08f4aa14603534e4c4097b37b016d1f5d6177b5fc19159834b0eeb053a244c27f3a018cfb89632c724a165136fce11832631612bf5612f42fe3c3c31a81c75a8ef733761d4f9c0a5ee486284016e7e40674e6271467f4cccfc8a258304efd10c1dfe3d2ce8c8b4f37cc91ab8f1dffaf"
+        },
+        Test {
+            entropy: "9ba150115f3ee901e761b8971d06a374a2243f2fb00c4a1ac898216e10e2e61d",
+            nonce: "22953cd7bbbd05150dba099e32250d83",
+            personalization: "3e2866e214152554f6703111bad7f4c7bb57139c1f98518780010540f9927c38",
+            entropy_reseed: "2beb6e1ef8b460ecd05dcc72a12867784af0f9a76858a80b521d329cc71c5edc",
+            additional_reseed: "a645ae5b904aaf2d7f8ef9fd98f0f5b9fc1c960409806b9a19dfca0dc93157b0",
+            additional: ["dbf065196d5ab33da455e0ea27eb555afaf305f6e5ee556c8eefab2fdc3f8735", "765c1e89a09f5c83b05a23d9afe89d762b50f491c308ce5f691eac9d53000cb8"],
+            output: "86309ab3f872051dbf7a1f18654590b4618b5002c0afe844e84a5be27cbc441f5b3a13cd51a11b58e7a9b81f5a63303970c28ed95cfe8060c23d62e409bef39936e0a1c03ae22fa82d85c4b2a7f722316fba7d1c9e87077b5acdd15a9acf58105770173600686689b29c96f2f2fb551ffe0ed449af0ea21e8778f7298d8c4db1d783423a50e9a897901576b3b13ca5ce656c70c466dc125bb8364f1df113f29cf0a20b06d4587df449169f07a46eab5a1b55e3897e8c266e235ba468b9d9ebe04ce10bd9947ddf6bccb79dd8be1668a6fd8342e5f75e74de9ba1eb8f4b746f9241a3c9c2f1fa531454c7f6aef3b277148c330564429be36ca0ce70eb4c3346bd"
+        }
+    ];
+
+    // A test with prediction resistance: the DRBG is reseeded with entropy_pr and the additional
+    // input before each request, which then has no additional input of its own.
+    struct PrTest {
+        entropy: &'static str,
+        nonce: &'static str,
+        personalization: &'static str,
+        entropy_pr: [&'static str, ..2],
+        additional: [&'static str, ..2],
+        output: &'static str
+    }
+
+    // The tests with prediction resistance are from the same files: COUNT = 14 of [SHA-256] in
+    // Hash_DRBG.rsp, COUNT = 0 of [SHA-256] in HMAC_DRBG.rsp and COUNT = 0 of [AES-128 use df] in
+    // CTR_DRBG.rsp, all with a personalization string and additional input.
+    static HASH_SHA256_PR_TESTS: &'static [PrTest] = &[
+        PrTest {
+            entropy: "066dc8ce75b28966a685163fe2a4d427fbdb616650616ba282fc332b4e6f1220",
+            nonce: "559f7c64897083ec2d7370d9f0e5071f",
+            personalization: "886f549aad1ac63d18cbcc6685daa2c2f79eb0894cb4aef1ac544fce57f15e11",
+            entropy_pr: ["ff80b7d26a05bc8a7abe53286b0eeb733b715a205bfa4ff63703deadb6ea0ef4", "c73832534681ede37e03846d3c841767297d246c689241d2e775be7ec996293d"],
+            additional: ["b7215f14ac7bafd0a91772ba22f719afbd20b311636c2b1e83e4a823353fc6ea", "ced31f7e0dae5bb5c043e246b29473e2fd39512ead4569eee3e3803314aba7a3"],
+            output: "60c234cfafb468033bf195e578ce266e1465326a96a9e03f8b893670ef62754d5e80d553a1f84950208b9343079f2ef856e9c570618597b5dc82a2daeaa3fd9b2fd2a0d71bc62935ccb83da0679805a0e31efee4f0e513b08317faca935e382948d272db763e6df32510ff1b99fff8c60eb0dd292ebcbbc80a016ed3b00e4eab"
+        }
+    ];
+
+    static HMAC_SHA256_PR_TESTS: &'static [PrTest] = &[
+        PrTest {
+            entropy: "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488",
+            nonce: "659ba96c601dc69fc902940805ec0ca8",
+            personalization: "e72dd8590d4ed5295515c35ed6199e9d211b8f069b3058caa6670b96ef1208d0",
+            entropy_pr: ["5cacc68165a2e2ee20812f35ec73a79dbf30fd475476ac0c44fc6174cdac2b55", "8df013b4d103523073917ddf6a869793059e9943fc8654549e7ab22f7c29f122"],
+            additional: ["793a7ef8f6f0482beac542bb785c10f8b7b406a4de92667ab168ecc2cf7573c6", "2238cdb4e23d629fe0c2a83dd8d5144ce1a6229ef41dabe2a99ff722e510b530"],
+            output: "b1d17c002a7febd28412d8e58a7f32318e4ee3605a99b05b05d59356d5f0c6b4960a4b8f963b7efa55bb6872fbeac7b99b78dea8f3531973637c946a9cab3349744b24a0851dd47f2b3b460c2c61846e91181d62d42c60a4efda5ed57902bfd702b349c54952c7f644769d8ef4015ecc5f5bbd4af06134688e30050e0497fb0a"
+        }
+    ];
+
+    static CTR_AES128_PR_TESTS: &'static [PrTest] = &[
+        PrTest {
+            entropy: "92898f31fa1cff6d182f260643dff818",
+            nonce: "c2a4d972c3b9b697",
+            personalization: "ea65ee60264e7eb60e8268c4373c5c0b",
+            entropy_pr: ["20728a06f86f8dd441e272b7c42ce810", "3db0f094f305503317863e2208f7a501"],
+            additional: ["1a40fae3cc6c7ca0f8daba59236dad1d", "9f72766cc746e5ed2e532012bc59318c"],
+            output: "5a3539870f4d22a40924ee71c96fac720ad6f08882d0832873ec3f93d8ab4523f07eac45145e939fb1d676433db6e80888f6da89087742fe1af43fc423c51f68"
+        }
+    ];
+
+    // Run a test on a DRBG instantiated from its inputs: reseed if the test has reseed inputs,
+    // generate twice and compare the output of the second request.
+    fn check<G: Drbg>(drbg: &mut G, t: &Test) {
+        if !t.entropy_reseed.is_empty() {
+            drbg.reseed(t.entropy_reseed.from_hex().unwrap().as_slice(),
+                t.additional_reseed.from_hex().unwrap().as_slice());
+        }
+        let mut out = Vec::from_elem(t.output.len() / 2, 0u8);
+        for additional in t.additional.iter() {
+            let additional = additional.from_hex().unwrap();
+            assert!(drbg.generate(out.as_mut_slice(), additional.as_slice()).is_ok());
+        }
+        assert_eq!(out.as_slice().to_hex(), t.output.to_string());
+        assert_eq!(drbg.reseed_counter(), 3);
+    }
+
+    fn inputs(t: &Test) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        (t.entropy.from_hex().unwrap(), t.nonce.from_hex().unwrap(),
+            t.personalization.from_hex().unwrap())
+    }
+
+    // Run a test with prediction resistance on a DRBG instantiated from its inputs
+    fn check_pr<G: Drbg>(drbg: &mut G, t: &PrTest) {
+        let mut out = Vec::from_elem(t.output.len() / 2, 0u8);
+        for (entropy, additional) in t.entropy_pr.iter().zip(t.additional.iter()) {
+            drbg.reseed(entropy.from_hex().unwrap().as_slice(),
+                additional.from_hex().unwrap().as_slice());
+            assert!(drbg.generate(out.as_mut_slice(), []).is_ok());
+        }
+        assert_eq!(out.as_slice().to_hex(), t.output.to_string());
+        assert_eq!(drbg.reseed_counter(), 2);
+    }
+
+    fn pr_inputs(t: &PrTest) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        (t.entropy.from_hex().unwrap(), t.nonce.from_hex().unwrap(),
+            t.personalization.from_hex().unwrap())
+    }
+
+    #[test]
+    fn test_hmac_drbg() {
+        for t in HMAC_SHA256_TESTS.iter() {
+            let (entropy, nonce, personalization) = inputs(t);
+            let mut drbg = HmacDrbg::new(Sha256::new(), entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check(&mut drbg, t);
+        }
+        for t in HMAC_SHA512_TESTS.iter() {
+            let (entropy, nonce, personalization) = inputs(t);
+            let mut drbg = HmacDrbg::new(Sha512::new(), entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check(&mut drbg, t);
+        }
+        for t in HMAC_SHA256_PR_TESTS.iter() {
+            let (entropy, nonce, personalization) = pr_inputs(t);
+            let mut drbg = HmacDrbg::new(Sha256::new(), entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check_pr(&mut drbg, t);
+        }
+    }
+
+    #[test]
+    fn test_hash_drbg() {
+        for t in HASH_SHA256_TESTS.iter() {
+            let (entropy, nonce, personalization) = inputs(t);
+            let mut drbg = HashDrbg::new(Sha256::new(), entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check(&mut drbg, t);
+        }
+        for t in HASH_SHA512_TESTS.iter() {
+            let (entropy, nonce, personalization) = inputs(t);
+            let mut drbg = HashDrbg::new(Sha512::new(), entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check(&mut drbg, t);
+        }
+        for t in HASH_SHA256_PR_TESTS.iter() {
+            let (entropy, nonce, personalization) = pr_inputs(t);
+            let mut drbg = HashDrbg::new(Sha256::new(), entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check_pr(&mut drbg, t);
+        }
+    }
+
+    #[test]
+    fn test_ctr_drbg() {
+        for t in CTR_AES128_TESTS.iter() {
+            let (entropy, nonce, personalization) = inputs(t);
+            let mut drbg = CtrDrbg::new(KeySize128, entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check(&mut drbg, t);
+        }
+        for t in CTR_AES256_TESTS.iter() {
+            let (entropy, nonce, personalization) = inputs(t);
+            let mut drbg = CtrDrbg::new(KeySize256, entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check(&mut drbg, t);
+        }
+        for t in CTR_AES128_PR_TESTS.iter() {
+            let (entropy, nonce, personalization) = pr_inputs(t);
+            let mut drbg = CtrDrbg::new(KeySize128, entropy.as_slice(), nonce.as_slice(),
+                personalization.as_slice());
+            check_pr(&mut drbg, t);
+        }
+    }
+
+    fn check_reseed_interval<G: Drbg>(drbg: &mut G) {
+        let mut out = [0u8, ..32];
+        drbg.set_reseed_interval(2);
+        assert!(drbg.generate(out, []).is_ok());
+        assert!(drbg.generate(out, [1, 2, 3]).is_ok());
+        assert_eq!(drbg.reseed_counter(), 3);
+        assert_eq!(drbg.generate(out, []), Err(ReseedRequired));
+        drbg.reseed([0x55, ..32], []);
+        assert_eq!(drbg.reseed_counter(), 1);
+        assert!(drbg.generate(out, []).is_ok());
+    }
+
+    #[test]
+    fn test_reseed_interval() {
+        let entropy = [0xaa, ..32];
+        let nonce = [0xbb, ..16];
+        check_reseed_interval(&mut HmacDrbg::new(Sha256::new(), entropy, nonce, []));
+        check_reseed_interval(&mut HashDrbg::new(Sha256::new(), entropy, nonce, []));
+        check_reseed_interval(&mut CtrDrbg::new(KeySize256, entropy, nonce, []));
+    }
+
+    fn check_request_size<G: Drbg>(drbg: &mut G) {
+        let mut out = Vec::from_elem(MAX_REQUEST_BYTES + 1, 0u8);
+        assert_eq!(drbg.generate(out.as_mut_slice(), []), Err(RequestTooLarge));
+        assert_eq!(drbg.reseed_counter(), 1);
+        assert!(drbg.generate(out.mut_slice_to(MAX_REQUEST_BYTES), []).is_ok());
+    }
+
+    #[test]
+    fn test_request_size() {
+        let entropy = [0xaa, ..32];
+        let nonce = [0xbb, ..16];
+        check_request_size(&mut HmacDrbg::new(Sha256::new(), entropy, nonce, []));
+        check_request_size(&mut HashDrbg::new(Sha256::new(), entropy, nonce, []));
+        check_request_size(&mut CtrDrbg::new(KeySize128, entropy, nonce, []));
+    }
+
+    #[test]
+    fn test_rng() {
+        let seeded = || HashDrbg::new(Sha256::new(), [0xaa, ..32], [0xbb, ..16], b"rng");
+
+        // Rng requests are split at the maximum request size
+        let mut rng = seeded();
+        let mut drbg = seeded();
+        let mut out = Vec::from_elem(MAX_REQUEST_BYTES + 100, 0u8);
+        rng.fill_bytes(out.as_mut_slice());
+        let mut expected = Vec::from_elem(MAX_REQUEST_BYTES + 100, 0u8);
+        assert!(drbg.generate(expected.mut_slice_to(MAX_REQUEST_BYTES), []).is_ok());
+        assert!(drbg.generate(expected.mut_slice_from(MAX_REQUEST_BYTES), []).is_ok());
+        assert!(out == expected);
+
+        let mut buf = [0u8, ..4];
+        assert!(drbg.generate(buf, []).is_ok());
+        assert_eq!(rng.next_u32(), read_u32_be(buf));
+
+        // The same seed gives the same keys
+        let curve = Curve::p256();
+        let key1 = EcPrivateKey::generate(&mut seeded(), &curve);
+        let key2 = EcPrivateKey::generate(&mut seeded(), &curve);
+        assert!(key1.d() == key2.d());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_rng_reseed_required() {
+        let mut rng = CtrDrbg::new(KeySize128, [0xaa, ..16], [0xbb, ..8], []);
+        rng.set_reseed_interval(1);
+        rng.next_u32();
+        rng.next_u32();
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use test::Bencher;
+
+    use aes::KeySize128;
+    use drbg::{Drbg, HmacDrbg, HashDrbg, CtrDrbg};
+    use sha2::Sha256;
+
+    #[bench]
+    pub fn hmac_drbg_sha256_1k(bh: &mut Bencher) {
+        let mut drbg = HmacDrbg::new(Sha256::new(), [0, ..32], [0, ..16], []);
+        let mut out = [0u8, ..1024];
+        bh.iter( || {
+            drbg.generate(out, []).unwrap();
+        });
+        bh.bytes = out.len() as u64;
+    }
+
+    #[bench]
+    pub fn hash_drbg_sha256_1k(bh: &mut Bencher) {
+        let mut drbg = HashDrbg::new(Sha256::new(), [0, ..32], [0, ..16], []);
+        let mut out = [0u8, ..1024];
+        bh.iter( || {
+            drbg.generate(out, []).unwrap();
+        });
+        bh.bytes = out.len() as u64;
+    }
+
+    #[bench]
+    pub fn ctr_drbg_aes128_1k(bh: &mut Bencher) {
+        let mut drbg = CtrDrbg::new(KeySize128, [0, ..16], [0, ..8], []);
+        let mut out = [0u8, ..1024];
+        bh.iter( || {
+            drbg.generate(out, []).unwrap();
+        });
+        bh.bytes = out.len() as u64;
+    }
+}
@@ -0,0 +1,707 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Elliptic curves y^2 = x^3 + ax + b over prime fields, and elliptic curve Diffie-Hellman.
+ *
+ * The NIST curves P-256 and P-384 and the curve secp256k1 from SEC 2 are built in. Field elements
+ * are kept in Montgomery form and points in projective coordinates, and points are added with the
+ * complete formulas of Renes, Costello and Batina ("Complete addition formulas for prime order
+ * elliptic curves", algorithm 1), which have no special cases for doubling or the point at
+ * infinity. Scalar multiplication is a Montgomery ladder over every bit of the group order, so its
+ * running time doesn't depend on the scalar.
+ *
+ * Points are encoded as described in SEC 1 section 2.3.3, either uncompressed (0x04 followed by
+ * x and y) or compressed (0x02 or 0x03, depending on the parity of y, followed by x). Decoding
+ * checks that the point is on the curve. All the built in curves have a cofactor of 1, so every
+ * point on the curve other than the point at infinity is in the subgroup generated by the base
+ * point.
+ */
+
+use std::num::{One, Zero};
+use std::rand::Rng;
+
+use num::bigint::{BigUint, ToBigUint, RandBigInt};
+use num::integer::Integer;
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be};
+use montgomery::Montgomery;
+
+/// The errors that can occur when creating keys or decoding points
+#[deriving(PartialEq, Show)]
+pub enum EcError {
+    /// The private key is not in the range [1, n - 1]
+    InvalidPrivateKey,
+    /// The encoding of a point is malformed, or the point is not on the curve
+    InvalidPoint
+}
+
+/// An elliptic curve and a base point of prime order n
+#[deriving(Clone, PartialEq)]
+pub struct Curve {
+    p: BigUint,
+    n: BigUint,
+    gx: BigUint,
+    gy: BigUint,
+    field: Montgomery,
+    // The coefficients a, b and 3b in Montgomery form
+    a: Vec<u32>,
+    b: Vec<u32>,
+    b3: Vec<u32>
+}
+
+/// A point in projective coordinates (X : Y : Z), representing the affine point (X / Z, Y / Z)
+#[deriving(Clone)]
+pub struct EcPoint {
+    x: Vec<u32>,
+    y: Vec<u32>,
+    z: Vec<u32>
+}
+
+fn big(s: &str) -> BigUint {
+    BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+}
+
+// Swap a and b if mask is 0xffffffff and leave them alone if it is 0.
+fn cswap(a: &mut [u32], b: &mut [u32], mask: u32) {
+    for (x, y) in a.mut_iter().zip(b.mut_iter()) {
+        let t = (*x ^ *y) & mask;
+        *x ^= t;
+        *y ^= t;
+    }
+}
+
+fn is_zero(a: &[u32]) -> bool {
+    a.iter().all(|x| *x == 0)
+}
+
+impl Curve {
+    fn new(p: &str, a: &str, b: &str, gx: &str, gy: &str, n: &str) -> Curve {
+        let p = big(p);
+        let b = big(b);
+        let three = 3u.to_biguint().unwrap();
+        let field = Montgomery::new(&p);
+        Curve {
+            a: field.to_montgomery(&big(a)),
+            b: field.to_montgomery(&b),
+            b3: field.to_montgomery(&(b * three)),
+            field: field,
+            p: p,
+            n: big(n),
+            gx: big(gx),
+            gy: big(gy)
+        }
+    }
+
+    /// The curve P-256 from FIPS 186-4, also known as secp256r1 and prime256v1
+    pub fn p256() -> Curve {
+        Curve::new(
+            "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+            "ffffffff00000001000000000000000000000000fffffffffffffffffffffffc",
+            "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+            "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+            "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+            "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551")
+    }
+
+    /// The curve P-384 from FIPS 186-4, also known as secp384r1
+    pub fn p384() -> Curve {
+        Curve::new(
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000ffffffff",
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffff0000000000000000fffffffc",
+            "b3312fa7e23ee7e4988e056be3f82d19181d9c6efe8141120314088f5013875ac656398d8a2ed19d2a85c8edd3ec2aef",
+            "aa87ca22be8b05378eb1c71ef320ad746e1d3b628ba79b9859f741e082542a385502f25dbf55296c3a545e3872760ab7",
+            "3617de4a96262c6f5d9e98bf9292dc29f8f41dbd289a147ce9da3113b5f0b8c00a60b1ce1d7e819d7a431d7c90ea0e5f",
+            "ffffffffffffffffffffffffffffffffffffffffffffffffc7634d81f4372ddf581a0db248b0a77aecec196accc52973")
+    }
+
+    /// The curve secp256k1 from SEC 2, which is used by Bitcoin and Ethereum
+    pub fn secp256k1() -> Curve {
+        Curve::new(
+            "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+            "0",
+            "7",
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
+    }
+
+    /// The prime p of the field
+    pub fn p<'a>(&'a self) -> &'a BigUint { &self.p }
+
+    /// The order n of the base point
+    pub fn n<'a>(&'a self) -> &'a BigUint { &self.n }
+
+    /// The size of a field element in bytes
+    pub fn field_size(&self) -> uint { (self.p.bits() + 7) / 8 }
+
+    /// The size of a scalar modulo n in bytes
+    pub fn scalar_size(&self) -> uint { (self.n.bits() + 7) / 8 }
+
+    /// The point at infinity
+    pub fn identity(&self) -> EcPoint {
+        let zero: BigUint = Zero::zero();
+        let one: BigUint = One::one();
+        EcPoint {
+            x: self.field.to_montgomery(&zero),
+            y: self.field.to_montgomery(&one),
+            z: self.field.to_montgomery(&zero)
+        }
+    }
+
+    /// The base point G
+    pub fn generator(&self) -> EcPoint {
+        let one: BigUint = One::one();
+        EcPoint {
+            x: self.field.to_montgomery(&self.gx),
+            y: self.field.to_montgomery(&self.gy),
+            z: self.field.to_montgomery(&one)
+        }
+    }
+
+    // Compute x^3 + ax + b in Montgomery form.
+    fn rhs(&self, x: &[u32]) -> Vec<u32> {
+        let f = &self.field;
+        let x2 = f.mul(x, x);
+        let t = f.add(x2.as_slice(), self.a.as_slice());
+        let t = f.mul(t.as_slice(), x);
+        return f.add(t.as_slice(), self.b.as_slice());
+    }
+
+    /// The point with affine coordinates (x, y), if it is on the curve
+    pub fn from_affine(&self, x: &BigUint, y: &BigUint) -> Result<EcPoint, EcError> {
+        if *x >= self.p || *y >= self.p {
+            return Err(InvalidPoint);
+        }
+        let f = &self.field;
+        let one: BigUint = One::one();
+        let xm = f.to_montgomery(x);
+        let ym = f.to_montgomery(y);
+        if f.mul(ym.as_slice(), ym.as_slice()) != self.rhs(xm.as_slice()) {
+            return Err(InvalidPoint);
+        }
+        Ok(EcPoint {
+            x: xm,
+            y: ym,
+            z: f.to_montgomery(&one)
+        })
+    }
+
+    /// The affine coordinates (x, y) of a point, or None for the point at infinity
+    pub fn to_affine(&self, point: &EcPoint) -> Option<(BigUint, BigUint)> {
+        if is_zero(point.z.as_slice()) {
+            return None;
+        }
+        let f = &self.field;
+        let two = 2u.to_biguint().unwrap();
+        let z_inv = f.exp(point.z.as_slice(), &(self.p - two));
+        let x = f.mul(point.x.as_slice(), z_inv.as_slice());
+        let y = f.mul(point.y.as_slice(), z_inv.as_slice());
+        Some((f.from_montgomery(x.as_slice()), f.from_montgomery(y.as_slice())))
+    }
+
+    /// Compute p + q. This works for any points, including equal points and the point at infinity.
+    pub fn add(&self, p: &EcPoint, q: &EcPoint) -> EcPoint {
+        let f = &self.field;
+        let (x1, y1, z1) = (p.x.as_slice(), p.y.as_slice(), p.z.as_slice());
+        let (x2, y2, z2) = (q.x.as_slice(), q.y.as_slice(), q.z.as_slice());
+        let a = self.a.as_slice();
+        let b3 = self.b3.as_slice();
+
+        let mut t0 = f.mul(x1, x2);
+        let mut t1 = f.mul(y1, y2);
+        let mut t2 = f.mul(z1, z2);
+        let mut t3 = f.add(x1, y1);
+        let mut t4 = f.add(x2, y2);
+        t3 = f.mul(t3.as_slice(), t4.as_slice());
+        t4 = f.add(t0.as_slice(), t1.as_slice());
+        t3 = f.sub(t3.as_slice(), t4.as_slice());
+        t4 = f.add(x1, z1);
+        let mut t5 = f.add(x2, z2);
+        t4 = f.mul(t4.as_slice(), t5.as_slice());
+        t5 = f.add(t0.as_slice(), t2.as_slice());
+        t4 = f.sub(t4.as_slice(), t5.as_slice());
+        t5 = f.add(y1, z1);
+        let mut x3 = f.add(y2, z2);
+        t5 = f.mul(t5.as_slice(), x3.as_slice());
+        x3 = f.add(t1.as_slice(), t2.as_slice());
+        t5 = f.sub(t5.as_slice(), x3.as_slice());
+        let mut z3 = f.mul(a, t4.as_slice());
+        x3 = f.mul(b3, t2.as_slice());
+        z3 = f.add(x3.as_slice(), z3.as_slice());
+        x3 = f.sub(t1.as_slice(), z3.as_slice());
+        z3 = f.add(t1.as_slice(), z3.as_slice());
+        let mut y3 = f.mul(x3.as_slice(), z3.as_slice());
+        t1 = f.add(t0.as_slice(), t0.as_slice());
+        t1 = f.add(t1.as_slice(), t0.as_slice());
+        t2 = f.mul(a, t2.as_slice());
+        t4 = f.mul(b3, t4.as_slice());
+        t1 = f.add(t1.as_slice(), t2.as_slice());
+        t2 = f.sub(t0.as_slice(), t2.as_slice());
+        t2 = f.mul(a, t2.as_slice());
+        t4 = f.add(t4.as_slice(), t2.as_slice());
+        t0 = f.mul(t1.as_slice(), t4.as_slice());
+        y3 = f.add(y3.as_slice(), t0.as_slice());
+        t0 = f.mul(t5.as_slice(), t4.as_slice());
+        x3 = f.mul(t3.as_slice(), x3.as_slice());
+        x3 = f.sub(x3.as_slice(), t0.as_slice());
+        t0 = f.mul(t3.as_slice(), t1.as_slice());
+        z3 = f.mul(t5.as_slice(), z3.as_slice());
+        z3 = f.add(z3.as_slice(), t0.as_slice());
+
+        EcPoint {
+            x: x3,
+            y: y3,
+            z: z3
+        }
+    }
+
+    /// Compute -p
+    pub fn neg(&self, p: &EcPoint) -> EcPoint {
+        let zero = Vec::from_elem(self.field.len(), 0u32);
+        EcPoint {
+            x: p.x.clone(),
+            y: self.field.sub(zero.as_slice(), p.y.as_slice()),
+            z: p.z.clone()
+        }
+    }
+
+    /**
+     * Compute k * p, where k is less than n. The time taken doesn't depend on the value of k or of
+     * the point.
+     */
+    pub fn mul(&self, p: &EcPoint, k: &BigUint) -> EcPoint {
+        assert!(*k < self.n);
+        let len = self.scalar_size();
+        let k = biguint_to_bytes_be(k, len);
+
+        let mut r0 = self.identity();
+        let mut r1 = p.clone();
+        for i in range(0, self.n.bits()).rev() {
+            let bit = ((k[len - 1 - i / 8] >> (i % 8)) & 1) as u32;
+            let mask = 0 - bit;
+            cswap(r0.x.as_mut_slice(), r1.x.as_mut_slice(), mask);
+            cswap(r0.y.as_mut_slice(), r1.y.as_mut_slice(), mask);
+            cswap(r0.z.as_mut_slice(), r1.z.as_mut_slice(), mask);
+            r1 = self.add(&r0, &r1);
+            r0 = self.add(&r0, &r0);
+            cswap(r0.x.as_mut_slice(), r1.x.as_mut_slice(), mask);
+            cswap(r0.y.as_mut_slice(), r1.y.as_mut_slice(), mask);
+            cswap(r0.z.as_mut_slice(), r1.z.as_mut_slice(), mask);
+        }
+        return r0;
+    }
+
+    /// Compute k * G, where k is less than n
+    pub fn mul_base(&self, k: &BigUint) -> EcPoint {
+        self.mul(&self.generator(), k)
+    }
+
+    /**
+     * Encode a point, which must not be the point at infinity, in the compressed or uncompressed
+     * form.
+     */
+    pub fn encode_point(&self, point: &EcPoint, compressed: bool) -> Vec<u8> {
+        let (x, y) = match self.to_affine(point) {
+            Some(xy) => xy,
+            None => fail!("The point at infinity can't be encoded")
+        };
+        let len = self.field_size();
+        let mut out = Vec::with_capacity(1 + 2 * len);
+        if compressed {
+            out.push(if y.is_odd() { 0x03 } else { 0x02 });
+            out.push_all(biguint_to_bytes_be(&x, len).as_slice());
+        } else {
+            out.push(0x04);
+            out.push_all(biguint_to_bytes_be(&x, len).as_slice());
+            out.push_all(biguint_to_bytes_be(&y, len).as_slice());
+        }
+        return out;
+    }
+
+    /**
+     * Decode a point in the compressed or uncompressed form, checking that it is on the curve. The
+     * point at infinity is rejected. Only curves with p = 3 mod 4 are supported for compressed
+     * points, which includes all the built in curves.
+     */
+    pub fn decode_point(&self, data: &[u8]) -> Result<EcPoint, EcError> {
+        let len = self.field_size();
+        if data.len() == 1 + 2 * len && data[0] == 0x04 {
+            let x = biguint_from_bytes_be(data.slice(1, 1 + len));
+            let y = biguint_from_bytes_be(data.slice_from(1 + len));
+            return self.from_affine(&x, &y);
+        }
+        if data.len() != 1 + len || (data[0] != 0x02 && data[0] != 0x03) {
+            return Err(InvalidPoint);
+        }
+
+        let x = biguint_from_bytes_be(data.slice_from(1));
+        if x >= self.p {
+            return Err(InvalidPoint);
+        }
+
+        // Since p = 3 mod 4, a square root of c is c^((p + 1) / 4), if c has one.
+        let f = &self.field;
+        let one: BigUint = One::one();
+        let xm = f.to_montgomery(&x);
+        let c = self.rhs(xm.as_slice());
+        let ym = f.exp(c.as_slice(), &((self.p + one) >> 2));
+        if f.mul(ym.as_slice(), ym.as_slice()) != c {
+            return Err(InvalidPoint);
+        }
+        let mut y = f.from_montgomery(ym.as_slice());
+        if y.is_odd() != (data[0] == 0x03) {
+            if y.is_zero() {
+                return Err(InvalidPoint);
+            }
+            y = self.p - y;
+        }
+        return self.from_affine(&x, &y);
+    }
+}
+
+/// An elliptic curve public key: a point Q on the curve
+#[deriving(Clone, PartialEq)]
+pub struct EcPublicKey {
+    curve: Curve,
+    x: BigUint,
+    y: BigUint
+}
+
+/// An elliptic curve private key: a scalar d in the range [1, n - 1] and its public key Q = d * G
+#[deriving(Clone, PartialEq)]
+pub struct EcPrivateKey {
+    curve: Curve,
+    d: BigUint,
+    public_key: EcPublicKey
+}
+
+impl EcPublicKey {
+    /// Create a public key from a point, which must not be the point at infinity
+    pub fn new(curve: &Curve, point: &EcPoint) -> Result<EcPublicKey, EcError> {
+        match curve.to_affine(point) {
+            Some((x, y)) => Ok(EcPublicKey {
+                curve: curve.clone(),
+                x: x,
+                y: y
+            }),
+            None => Err(InvalidPoint)
+        }
+    }
+
+    /// Decode a public key from a point in the compressed or uncompressed form
+    pub fn from_bytes(curve: &Curve, data: &[u8]) -> Result<EcPublicKey, EcError> {
+        let point = try!(curve.decode_point(data));
+        EcPublicKey::new(curve, &point)
+    }
+
+    /// Encode the public key as a point in the compressed or uncompressed form
+    pub fn to_bytes(&self, compressed: bool) -> Vec<u8> {
+        self.curve.encode_point(&self.point(), compressed)
+    }
+
+    /// The curve
+    pub fn curve<'a>(&'a self) -> &'a Curve { &self.curve }
+
+    /// The point Q
+    pub fn point(&self) -> EcPoint {
+        self.curve.from_affine(&self.x, &self.y).unwrap()
+    }
+}
+
+impl EcPrivateKey {
+    /// Create a private key from the scalar d, which must be in the range [1, n - 1]
+    pub fn new(curve: &Curve, d: BigUint) -> Result<EcPrivateKey, EcError> {
+        if d.is_zero() || d >= curve.n {
+            return Err(InvalidPrivateKey);
+        }
+        let public_key = EcPublicKey::new(curve, &curve.mul_base(&d)).unwrap();
+        Ok(EcPrivateKey {
+            curve: curve.clone(),
+            d: d,
+            public_key: public_key
+        })
+    }
+
+    /// Create a private key from the big endian encoding of d
+    pub fn from_bytes(curve: &Curve, data: &[u8]) -> Result<EcPrivateKey, EcError> {
+        EcPrivateKey::new(curve, biguint_from_bytes_be(data))
+    }
+
+    /// Generate a private key with d chosen uniformly from [1, n - 1]
+    pub fn generate<R: Rng>(rng: &mut R, curve: &Curve) -> EcPrivateKey {
+        let one: BigUint = One::one();
+        let d = rng.gen_biguint_range(&one, &curve.n);
+        EcPrivateKey::new(curve, d).unwrap()
+    }
+
+    /// The big endian encoding of d, of scalar_size() bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        biguint_to_bytes_be(&self.d, self.curve.scalar_size())
+    }
+
+    /// The curve
+    pub fn curve<'a>(&'a self) -> &'a Curve { &self.curve }
+
+    /// The scalar d
+    pub fn d<'a>(&'a self) -> &'a BigUint { &self.d }
+
+    /// The public key
+    pub fn public_key<'a>(&'a self) -> &'a EcPublicKey { &self.public_key }
+
+    /**
+     * Compute the ECDH shared secret from the peer's public key, which is an encoded point in the
+     * compressed or uncompressed form. The secret is the x coordinate of d * Q as a big endian byte
+     * string of field_size() bytes.
+     */
+    pub fn exchange(&self, peer: &[u8]) -> Result<Vec<u8>, EcError> {
+        let q = try!(self.curve.decode_point(peer));
+        match self.curve.to_affine(&self.curve.mul(&q, &self.d)) {
+            Some((x, _)) => Ok(biguint_to_bytes_be(&x, self.curve.field_size())),
+            None => Err(InvalidPoint)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::IsaacRng;
+
+    use num::bigint::{BigUint, ToBigUint};
+    use num::integer::Integer;
+    use serialize::hex::{FromHex, ToHex};
+
+    use ec::{Curve, EcPrivateKey, EcPublicKey, InvalidPoint, InvalidPrivateKey};
+
+    fn big(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    fn curves() -> Vec<Curve> {
+        vec![Curve::p256(), Curve::p384(), Curve::secp256k1()]
+    }
+
+    #[test]
+    fn test_generator() {
+        for curve in curves().iter() {
+            let g = curve.generator();
+            let (gx, gy) = curve.to_affine(&g).unwrap();
+            assert!(curve.from_affine(&gx, &gy).is_ok());
+
+            // (n - 1) * G = -G, so n * G is the point at infinity
+            let one = 1u.to_biguint().unwrap();
+            let p = curve.mul(&g, &(*curve.n() - one));
+            assert!(curve.to_affine(&p) == Some((gx.clone(), *curve.p() - gy)));
+            assert!(curve.to_affine(&curve.add(&p, &g)).is_none());
+            assert!(curve.to_affine(&curve.neg(&g)) == curve.to_affine(&p));
+
+            // Adding a point to itself or to the point at infinity
+            let two = 2u.to_biguint().unwrap();
+            let three = 3u.to_biguint().unwrap();
+            let g2 = curve.add(&g, &g);
+            assert!(curve.to_affine(&g2) == curve.to_affine(&curve.mul_base(&two)));
+            assert!(curve.to_affine(&curve.add(&g2, &g)) ==
+                    curve.to_affine(&curve.mul_base(&three)));
+            assert!(curve.to_affine(&curve.add(&curve.identity(), &g)) == Some((gx, gy)));
+            assert!(curve.to_affine(&curve.mul_base(&0u.to_biguint().unwrap())).is_none());
+        }
+    }
+
+    #[test]
+    fn test_public_key() {
+        // The keys from RFC 6979 appendix A.2.5 and A.2.6
+        let tests = [
+            (Curve::p256(),
+             "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+             "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6",
+             "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"),
+            (Curve::p384(),
+             "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5",
+             "ec3a4e415b4e19a4568618029f427fa5da9a8bc4ae92e02e06aae5286b300c64def8f0ea9055866064a254515480bc13",
+             "8015d9b72d7d57244ea8ef9ac0c621896708a59367f9dfb9f54ca84b3f1c9db1288b231c3ae0d4fe7344fd2533264720")
+        ];
+        for &(ref curve, d, x, y) in tests.iter() {
+            let key = EcPrivateKey::from_bytes(curve, d.from_hex().unwrap().as_slice()).unwrap();
+            assert_eq!(key.to_bytes().as_slice().to_hex(), d.to_string());
+
+            let uncompressed = key.public_key().to_bytes(false);
+            assert_eq!(uncompressed.as_slice().to_hex(), format!("04{}{}", x, y));
+            let compressed = key.public_key().to_bytes(true);
+            let tag = if big(y).is_odd() { "03" } else { "02" };
+            assert_eq!(compressed.as_slice().to_hex(), format!("{}{}", tag, x));
+
+            assert!(EcPublicKey::from_bytes(curve, uncompressed.as_slice()).unwrap() ==
+                    *key.public_key());
+            assert!(EcPublicKey::from_bytes(curve, compressed.as_slice()).unwrap() ==
+                    *key.public_key());
+
+            // The compressed point with the other parity is the negation
+            let mut negated = compressed.clone();
+            *negated.get_mut(0) ^= 1;
+            let neg = EcPublicKey::from_bytes(curve, negated.as_slice()).unwrap();
+            assert!(curve.to_affine(&neg.point()) == Some((big(x), *curve.p() - big(y))));
+        }
+    }
+
+    #[test]
+    fn test_invalid_private_key() {
+        let curve = Curve::p256();
+        let one = 1u.to_biguint().unwrap();
+        let zero = 0u.to_biguint().unwrap();
+        assert!(EcPrivateKey::new(&curve, zero).err() == Some(InvalidPrivateKey));
+        assert!(EcPrivateKey::new(&curve, curve.n().clone()).err() == Some(InvalidPrivateKey));
+        assert!(EcPrivateKey::new(&curve, *curve.n() - one).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_points() {
+        let curve = Curve::p256();
+        let x = "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6";
+        let y = "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299";
+        let p = "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff";
+        let invalid = [
+            // The point at infinity
+            "00".to_string(),
+            // Bad tags
+            format!("05{}{}", x, y),
+            format!("04{}", x),
+            format!("02{}{}", x, y),
+            // Truncated
+            format!("04{}{}", x, y.slice_to(62)),
+            format!("02{}", x.slice_to(62)),
+            // Not on the curve
+            format!("04{}7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462298", x),
+            // Coordinates that aren't reduced modulo p
+            format!("04{}{}", p, y),
+            format!("02{}", p),
+            // x^3 - 3x + b has no square root for x = 1
+            "020000000000000000000000000000000000000000000000000000000000000001".to_string()
+        ];
+        for data in invalid.iter() {
+            let data = data.as_slice().from_hex().unwrap();
+            assert!(curve.decode_point(data.as_slice()).err() == Some(InvalidPoint));
+            assert!(EcPublicKey::from_bytes(&curve, data.as_slice()).err() == Some(InvalidPoint));
+        }
+    }
+
+    // Excerpts from the NIST CAVP SP 800-56A ECC CDH primitive test vectors: COUNT = 0, 1 and 2 for
+    // P-256 and COUNT = 0 and 1 for P-384 from KAS_ECC_CDH_PrimitiveTest.txt in
+    // http://csrc.nist.gov/groups/STM/cavp/documents/components/ecccdhtestvectors.zip
+    #[test]
+    fn test_exchange_cavp() {
+        // (curve, dIUT, QCAVSx, QCAVSy, ZIUT)
+        let tests = [
+            (Curve::p256(),
+             "7d7dc5f71eb29ddaf80d6214632eeae03d9058af1fb6d22ed80badb62bc1a534",
+             "700c48f77f56584c5cc632ca65640db91b6bacce3a4df6b42ce7cc838833d287",
+             "db71e509e3fd9b060ddb20ba5c51dcc5948d46fbf640dfe0441782cab85fa4ac",
+             "46fc62106420ff012e54a434fbdd2d25ccc5852060561e68040dd7778997bd7b"),
+            (Curve::p256(),
+             "38f65d6dce47676044d58ce5139582d568f64bb16098d179dbab07741dd5caf5",
+             "809f04289c64348c01515eb03d5ce7ac1a8cb9498f5caa50197e58d43a86a7ae",
+             "b29d84e811197f25eba8f5194092cb6ff440e26d4421011372461f579271cda3",
+             "057d636096cb80b67a8c038c890e887d1adfa4195e9b3ce241c8a778c59cda67"),
+            (Curve::p256(),
+             "1accfaf1b97712b85a6f54b148985a1bdc4c9bec0bd258cad4b3d603f49f32c8",
+             "a2339c12d4a03c33546de533268b4ad667debf458b464d77443636440ee7fec3",
+             "ef48a3ab26e20220bcda2c1851076839dae88eae962869a497bf73cb66faf536",
+             "2d457b78b4614132477618a5b077965ec90730a8c81a1c75d6d4ec68005d67ec"),
+            (Curve::p384(),
+             "3cc3122a68f0d95027ad38c067916ba0eb8c38894d22e1b15618b6818a661774ad463b205da88cf699ab4d43c9cf98a1",
+             "a7c76b970c3b5fe8b05d2838ae04ab47697b9eaf52e764592efda27fe7513272734466b400091adbf2d68c58e0c50066",
+             "ac68f19f2e1cb879aed43a9969b91a0839c4c38a49749b661efedf243451915ed0905a32b060992b468c64766fc8437a",
+             "5f9d29dc5e31a163060356213669c8ce132e22f57c9a04f40ba7fcead493b457e5621e766c40a2e3d4d6a04b25e533f1"),
+            (Curve::p384(),
+             "92860c21bde06165f8e900c687f8ef0a05d14f290b3f07d8b3a8cc6404366e5d5119cd6d03fb12dc58e89f13df9cd783",
+             "30f43fcf2b6b00de53f624f1543090681839717d53c7c955d1d69efaf0349b7363acb447240101cbb3af6641ce4b88e0",
+             "25e46c0c54f0162a77efcc27b6ea792002ae2ba82714299c860857a68153ab62e525ec0530d81b5aa15897981e858757",
+             "a23742a2c267d7425fda94b93f93bbcc24791ac51cd8fd501a238d40812f4cbfc59aac9520d758cf789c76300c69d2ff")
+        ];
+        for &(ref curve, d, x, y, z) in tests.iter() {
+            let key = EcPrivateKey::from_bytes(curve, d.from_hex().unwrap().as_slice()).unwrap();
+
+            // The uncompressed and compressed forms of the peer's point give the same secret
+            let uncompressed = format!("04{}{}", x, y).as_slice().from_hex().unwrap();
+            let secret = key.exchange(uncompressed.as_slice()).unwrap();
+            assert_eq!(secret.as_slice().to_hex(), z.to_string());
+            let tag = if big(y).is_odd() { "03" } else { "02" };
+            let compressed = format!("{}{}", tag, x).as_slice().from_hex().unwrap();
+            let secret = key.exchange(compressed.as_slice()).unwrap();
+            assert_eq!(secret.as_slice().to_hex(), z.to_string());
+
+            // Changing y moves the point off the curve
+            let mut bad = uncompressed.clone();
+            let last = bad.len() - 1;
+            *bad.get_mut(last) ^= 1;
+            assert!(key.exchange(bad.as_slice()).err() == Some(InvalidPoint));
+
+            // A compressed x with the uncompressed tag, or an uncompressed point with a compressed
+            // tag, isn't a valid encoding
+            let mut bad = compressed.clone();
+            *bad.get_mut(0) = 0x04;
+            assert!(key.exchange(bad.as_slice()).err() == Some(InvalidPoint));
+            let mut bad = uncompressed.clone();
+            *bad.get_mut(0) = 0x02;
+            assert!(key.exchange(bad.as_slice()).err() == Some(InvalidPoint));
+        }
+    }
+
+    #[test]
+    fn test_exchange() {
+        let mut rng = IsaacRng::new_unseeded();
+        for curve in curves().iter() {
+            for _ in range(0u, 3) {
+                let a = EcPrivateKey::generate(&mut rng, curve);
+                let b = EcPrivateKey::generate(&mut rng, curve);
+                let za = a.exchange(b.public_key().to_bytes(true).as_slice()).unwrap();
+                let zb = b.exchange(a.public_key().to_bytes(false).as_slice()).unwrap();
+                assert_eq!(za, zb);
+                assert_eq!(za.len(), curve.field_size());
+            }
+        }
+    }
+
+    #[test]
+    fn test_exchange_invalid_point() {
+        let mut rng = IsaacRng::new_unseeded();
+        let p256 = Curve::p256();
+        let p384 = Curve::p384();
+        let a = EcPrivateKey::generate(&mut rng, &p256);
+        let b = EcPrivateKey::generate(&mut rng, &p384);
+        let mut peer = a.public_key().to_bytes(false);
+        assert!(b.exchange(peer.as_slice()).err() == Some(InvalidPoint));
+        *peer.get_mut(64) ^= 1;
+        assert!(a.exchange(peer.as_slice()).err() == Some(InvalidPoint));
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use std::rand::IsaacRng;
+    use test::Bencher;
+
+    use ec::{Curve, EcPrivateKey};
+
+    fn bench_exchange(bh: &mut Bencher, curve: Curve) {
+        let mut rng = IsaacRng::new_unseeded();
+        let a = EcPrivateKey::generate(&mut rng, &curve);
+        let b = EcPrivateKey::generate(&mut rng, &curve);
+        let peer = b.public_key().to_bytes(false);
+        bh.iter( || {
+            a.exchange(peer.as_slice())
+        });
+    }
+
+    #[bench]
+    pub fn exchange_p256(bh: &mut Bencher) {
+        bench_exchange(bh, Curve::p256());
+    }
+
+    #[bench]
+    pub fn exchange_p384(bh: &mut Bencher) {
+        bench_exchange(bh, Curve::p384());
+    }
+}
@@ -0,0 +1,648 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * The Elliptic Curve Digital Signature Algorithm, as specified in FIPS 186-4 section 6, over the
+ * curves in the ec module.
+ *
+ * Signing is deterministic: the nonce k is derived from the private key and the hash of the message
+ * with HMAC_DRBG, as described in RFC 6979 section 3.2, using the same digest as the one that
+ * hashes the message. This means that no random number generator is needed and that a weak one
+ * can't leak the private key. k * G is computed in constant time and k is inverted by
+ * exponentiation modulo n.
+ */
+
+use std::num::Zero;
+
+use num::bigint::{BigUint, ToBigUint};
+use num::integer::Integer;
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be};
+use digest::Digest;
+use ec::{Curve, EcPrivateKey, EcPublicKey};
+use hmac::Hmac;
+use mac::Mac;
+use montgomery::Montgomery;
+use prime_rng::modular_inverse;
+
+/// An ECDSA signature
+#[deriving(Clone, PartialEq, Show)]
+pub struct EcdsaSignature {
+    pub r: BigUint,
+    pub s: BigUint
+}
+
+impl EcdsaSignature {
+    /**
+     * Decode a signature from the concatenation of r and s, each a big endian byte string of the
+     * curve's scalar_size() bytes. Returns None if the length is wrong.
+     */
+    pub fn from_bytes(curve: &Curve, data: &[u8]) -> Option<EcdsaSignature> {
+        let len = curve.scalar_size();
+        if data.len() != 2 * len {
+            return None;
+        }
+        Some(EcdsaSignature {
+            r: biguint_from_bytes_be(data.slice_to(len)),
+            s: biguint_from_bytes_be(data.slice_from(len))
+        })
+    }
+
+    /// Encode the signature as the concatenation of r and s, each of scalar_size() bytes
+    pub fn to_bytes(&self, curve: &Curve) -> Vec<u8> {
+        let len = curve.scalar_size();
+        let mut out = biguint_to_bytes_be(&self.r, len);
+        out.push_all(biguint_to_bytes_be(&self.s, len).as_slice());
+        return out;
+    }
+
+    /// Whether s is at most n / 2
+    pub fn is_low_s(&self, curve: &Curve) -> bool {
+        self.s <= (*curve.n() >> 1)
+    }
+
+    /**
+     * The equivalent signature with s replaced by n - s if s is greater than n / 2. (r, s) and
+     * (r, n - s) are both valid signatures of the same message; some protocols only accept the one
+     * with the lower s so that signatures can't be modified.
+     */
+    pub fn normalize_s(&self, curve: &Curve) -> EcdsaSignature {
+        if self.is_low_s(curve) {
+            return self.clone();
+        }
+        EcdsaSignature {
+            r: self.r.clone(),
+            s: *curve.n() - self.s
+        }
+    }
+}
+
+fn hash<D: Digest>(digest: &mut D, msg: &[u8]) -> Vec<u8> {
+    let mut h = Vec::from_elem(digest.output_bytes(), 0u8);
+    digest.reset();
+    digest.input(msg);
+    digest.result(h.as_mut_slice());
+    return h;
+}
+
+// Convert the leftmost qlen bits of data to an integer (RFC 6979 section 2.3.2).
+fn bits2int(data: &[u8], qlen: uint) -> BigUint {
+    let x = biguint_from_bytes_be(data);
+    if data.len() * 8 > qlen {
+        return x >> (data.len() * 8 - qlen);
+    }
+    return x;
+}
+
+// The HMAC_DRBG instance of RFC 6979 section 3.2, which produces the candidates for k.
+struct NonceGenerator<D> {
+    digest: D,
+    k: Vec<u8>,
+    v: Vec<u8>
+}
+
+impl <D: Digest + Clone> NonceGenerator<D> {
+    // Set up the generator from the private key and the hash of the message, which are both
+    // encoded as big endian byte strings of the length of n.
+    fn new(digest: &D, x: &[u8], h: &[u8]) -> NonceGenerator<D> {
+        let len = digest.output_bytes();
+        let mut gen = NonceGenerator {
+            digest: digest.clone(),
+            k: Vec::from_elem(len, 0u8),
+            v: Vec::from_elem(len, 1u8)
+        };
+        for i in range(0u8, 2) {
+            let mut data = gen.v.clone();
+            data.push(i);
+            data.push_all(x);
+            data.push_all(h);
+            gen.k = gen.hmac(data.as_slice());
+            gen.v = gen.hmac(gen.v.as_slice());
+        }
+        return gen;
+    }
+
+    // Compute HMAC_K(data).
+    fn hmac(&self, data: &[u8]) -> Vec<u8> {
+        let mut digest = self.digest.clone();
+        digest.reset();
+        let mut mac = Hmac::new(digest, self.k.as_slice());
+        mac.input(data);
+        let mut out = Vec::from_elem(mac.output_bytes(), 0u8);
+        mac.raw_result(out.as_mut_slice());
+        return out;
+    }
+
+    // Produce the next k in the range [1, q - 1].
+    fn next(&mut self, q: &BigUint) -> BigUint {
+        let qlen = q.bits();
+        loop {
+            let mut t = Vec::with_capacity((qlen + 7) / 8);
+            while t.len() * 8 < qlen {
+                self.v = self.hmac(self.v.as_slice());
+                t.push_all(self.v.as_slice());
+            }
+            let k = bits2int(t.as_slice(), qlen);
+
+            // Update the state in case k is rejected, either here or by the caller
+            let mut data = self.v.clone();
+            data.push(0);
+            self.k = self.hmac(data.as_slice());
+            self.v = self.hmac(self.v.as_slice());
+
+            if !k.is_zero() && k < *q {
+                return k;
+            }
+        }
+    }
+}
+
+/**
+ * Sign a message with ECDSA, hashing it with the digest. The same digest is used to derive the
+ * nonce as described in RFC 6979.
+ */
+pub fn sign<D: Digest + Clone>(key: &EcPrivateKey, digest: &mut D, msg: &[u8]) -> EcdsaSignature {
+    let (sig, _) = sign_recoverable(key, digest, msg);
+    return sig;
+}
+
+/**
+ * Sign a message like sign(), and also return the recovery id that recover_public_key() needs.
+ * Bit 0 of the recovery id is the parity of the y coordinate of k * G and bit 1 is set if its x
+ * coordinate is greater than or equal to n.
+ */
+pub fn sign_recoverable<D: Digest + Clone>(key: &EcPrivateKey, digest: &mut D, msg: &[u8])
+        -> (EcdsaSignature, u8) {
+    let curve = key.curve();
+    let n = curve.n();
+    let len = curve.scalar_size();
+    let h = hash(digest, msg);
+    let e = bits2int(h.as_slice(), n.bits());
+
+    let h_octets = biguint_to_bytes_be(&(e % *n), len);
+    let mut gen = NonceGenerator::new(digest, key.to_bytes().as_slice(), h_octets.as_slice());
+
+    let scalar = Montgomery::new(n);
+    let d = scalar.to_montgomery(key.d());
+    let e = scalar.to_montgomery(&e);
+    let n_minus_two = *n - 2u.to_biguint().unwrap();
+    loop {
+        let k = gen.next(n);
+        let (x, y) = match curve.to_affine(&curve.mul_base(&k)) {
+            Some(xy) => xy,
+            None => continue
+        };
+        let r = x % *n;
+        if r.is_zero() {
+            continue;
+        }
+
+        // s = k^-1 * (e + r * d) mod n
+        let k = scalar.to_montgomery(&k);
+        let k_inv = scalar.exp(k.as_slice(), &n_minus_two);
+        let rd = scalar.mul(scalar.to_montgomery(&r).as_slice(), d.as_slice());
+        let t = scalar.add(e.as_slice(), rd.as_slice());
+        let s = scalar.from_montgomery(scalar.mul(k_inv.as_slice(), t.as_slice()).as_slice());
+        if s.is_zero() {
+            continue;
+        }
+
+        let recovery_id = (if y.is_odd() { 1 } else { 0 }) | (if x >= *n { 2 } else { 0 });
+        let sig = EcdsaSignature {
+            r: r,
+            s: s
+        };
+        return (sig, recovery_id);
+    }
+}
+
+/// Verify an ECDSA signature of a message, which is hashed with the digest
+pub fn verify<D: Digest>(key: &EcPublicKey, digest: &mut D, msg: &[u8], sig: &EcdsaSignature)
+        -> bool {
+    let curve = key.curve();
+    let n = curve.n();
+    if sig.r.is_zero() || sig.r >= *n || sig.s.is_zero() || sig.s >= *n {
+        return false;
+    }
+
+    let h = hash(digest, msg);
+    let e = bits2int(h.as_slice(), n.bits());
+    let w = modular_inverse(&sig.s, n).unwrap();
+    let u1 = (e * w) % *n;
+    let u2 = (sig.r * w) % *n;
+    let p = curve.add(&curve.mul_base(&u1), &curve.mul(&key.point(), &u2));
+    match curve.to_affine(&p) {
+        Some((x, _)) => x % *n == sig.r,
+        None => false
+    }
+}
+
+/**
+ * Recover the public key that produced a signature of a message, which is hashed with the digest,
+ * from the signature and its recovery id. Returns None if there is no such key. If the signature
+ * was produced by sign_recoverable() then the key is the signer's; if s was negated since, bit 0 of
+ * the recovery id must be flipped.
+ */
+pub fn recover_public_key<D: Digest>(
+        curve: &Curve,
+        digest: &mut D,
+        msg: &[u8],
+        sig: &EcdsaSignature,
+        recovery_id: u8) -> Option<EcPublicKey> {
+    let n = curve.n();
+    if recovery_id > 3 || sig.r.is_zero() || sig.r >= *n || sig.s.is_zero() || sig.s >= *n {
+        return None;
+    }
+
+    // R is the point k * G, which has x coordinate r or r + n
+    let mut x = sig.r.clone();
+    if recovery_id & 2 != 0 {
+        x = x + *n;
+        if x >= *curve.p() {
+            return None;
+        }
+    }
+    let mut encoded = vec![0x02 | (recovery_id & 1)];
+    encoded.push_all(biguint_to_bytes_be(&x, curve.field_size()).as_slice());
+    let r_point = match curve.decode_point(encoded.as_slice()) {
+        Ok(p) => p,
+        Err(_) => return None
+    };
+
+    // Q = r^-1 * (s * R - e * G)
+    let h = hash(digest, msg);
+    let e = bits2int(h.as_slice(), n.bits()) % *n;
+    let r_inv = modular_inverse(&sig.r, n).unwrap();
+    let u1 = ((*n - e) * r_inv) % *n;
+    let u2 = (sig.s * r_inv) % *n;
+    let q = curve.add(&curve.mul_base(&u1), &curve.mul(&r_point, &u2));
+    return EcPublicKey::new(curve, &q).ok();
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::IsaacRng;
+    use std::slice::bytes::copy_memory;
+
+    use num::bigint::{BigUint, ToBigUint};
+    use serialize::hex::FromHex;
+
+    use digest::Digest;
+    use ec::{Curve, EcPrivateKey, EcPublicKey};
+    use ecdsa::{EcdsaSignature, sign, sign_recoverable, verify, recover_public_key};
+    use sha1::Sha1;
+    use sha2::{Sha224, Sha256, Sha384, Sha512};
+
+    fn big(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    // Sign the message, check that the signature is (r, s) and that it verifies, and check that
+    // changing the message or the signature makes verification fail.
+    fn check<D: Digest + Clone>(key: &EcPrivateKey, digest: &mut D, msg: &str, r: &str, s: &str) {
+        let msg = msg.as_bytes();
+        let sig = sign(key, digest, msg);
+        assert!(sig == EcdsaSignature { r: big(r), s: big(s) });
+        assert!(verify(key.public_key(), digest, msg, &sig));
+
+        assert!(!verify(key.public_key(), digest, msg.slice_from(1), &sig));
+        let one = 1u.to_biguint().unwrap();
+        let bad = EcdsaSignature { r: sig.r + one, s: sig.s.clone() };
+        assert!(!verify(key.public_key(), digest, msg, &bad));
+        let bad = EcdsaSignature { r: sig.r.clone(), s: sig.s + one };
+        assert!(!verify(key.public_key(), digest, msg, &bad));
+    }
+
+    // The tests from RFC 6979 appendix A.2.5
+    #[test]
+    fn test_p256_rfc6979() {
+        let curve = Curve::p256();
+        let d = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+        let key = EcPrivateKey::from_bytes(&curve, d.from_hex().unwrap().as_slice()).unwrap();
+
+        check(&key, &mut Sha1::new(), "sample",
+            "61340c88c3aaebeb4f6d667f672ca9759a6ccaa9fa8811313039ee4a35471d32",
+            "6d7f147dac089441bb2e2fe8f7a3fa264b9c475098fdcf6e00d7c996e1b8b7eb");
+        check(&key, &mut Sha224::new(), "sample",
+            "53b2fff5d1752b2c689df257c04c40a587fababb3f6fc2702f1343af7ca9aa3f",
+            "b9afb64fdc03dc1a131c7d2386d11e349f070aa432a4acc918bea988bf75c74c");
+        check(&key, &mut Sha256::new(), "sample",
+            "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+            "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8");
+        check(&key, &mut Sha384::new(), "sample",
+            "0eafea039b20e9b42309fb1d89e213057cbf973dc0cfc8f129edddc800ef7719",
+            "4861f0491e6998b9455193e34e7b0d284ddd7149a74b95b9261f13abde940954");
+        check(&key, &mut Sha512::new(), "sample",
+            "8496a60b5e9b47c825488827e0495b0e3fa109ec4568fd3f8d1097678eb97f00",
+            "2362ab1adbe2b8adf9cb9edab740ea6049c028114f2460f96554f61fae3302fe");
+        check(&key, &mut Sha1::new(), "test",
+            "0cbcc86fd6abd1d99e703e1ec50069ee5c0b4ba4b9ac60e409e8ec5910d81a89",
+            "01b9d7b73dfaa60d5651ec4591a0136f87653e0fd780c3b1bc872ffdeae479b1");
+        check(&key, &mut Sha224::new(), "test",
+            "c37edb6f0ae79d47c3c27e962fa269bb4f441770357e114ee511f662ec34a692",
+            "c820053a05791e521fcaad6042d40aea1d6b1a540138558f47d0719800e18f2d");
+        check(&key, &mut Sha256::new(), "test",
+            "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+            "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083");
+        check(&key, &mut Sha384::new(), "test",
+            "83910e8b48bb0c74244ebdf7f07a1c5413d61472bd941ef3920e623fbccebeb6",
+            "8ddbec54cf8cd5874883841d712142a56a8d0f218f5003cb0296b6b509619f2c");
+        check(&key, &mut Sha512::new(), "test",
+            "461d93f31b6540894788fd206c07cfa0cc35f46fa3c91816fff1040ad1581a04",
+            "39af9f15de0db8d97e72719c74820d304ce5226e32dedae67519e840d1194e55");
+    }
+
+    // The tests from RFC 6979 appendix A.2.6
+    #[test]
+    fn test_p384_rfc6979() {
+        let curve = Curve::p384();
+        let d = "6b9d3dad2e1b8c1c05b19875b6659f4de23c3b667bf297ba9aa47740787137d896d5724e4c70a825f872c9ea60d2edf5";
+        let key = EcPrivateKey::from_bytes(&curve, d.from_hex().unwrap().as_slice()).unwrap();
+
+        check(&key, &mut Sha1::new(), "sample",
+            "ec748d839243d6fbef4fc5c4859a7dffd7f3abddf72014540c16d73309834fa37b9ba002899f6fda3a4a9386790d4eb2",
+            "a3bcfa947beef4732bf247ac17f71676cb31a847b9ff0cbc9c9ed4c1a5b3facf26f49ca031d4857570ccb5ca4424a443");
+        check(&key, &mut Sha224::new(), "sample",
+            "42356e76b55a6d9b4631c865445dbe54e056d3b3431766d0509244793c3f9366450f76ee3de43f5a125333a6be060122",
+            "9da0c81787064021e78df658f2fbb0b042bf304665db721f077a4298b095e4834c082c03d83028efbf93a3c23940ca8d");
+        check(&key, &mut Sha256::new(), "sample",
+            "21b13d1e013c7fa1392d03c5f99af8b30c570c6f98d4ea8e354b63a21d3daa33bde1e888e63355d92fa2b3c36d8fb2cd",
+            "f3aa443fb107745bf4bd77cb3891674632068a10ca67e3d45db2266fa7d1feebefdc63eccd1ac42ec0cb8668a4fa0ab0");
+        check(&key, &mut Sha384::new(), "sample",
+            "94edbb92a5ecb8aad4736e56c691916b3f88140666ce9fa73d64c4ea95ad133c81a648152e44acf96e36dd1e80fabe46",
+            "99ef4aeb15f178cea1fe40db2603138f130e740a19624526203b6351d0a3a94fa329c145786e679e7b82c71a38628ac8");
+        check(&key, &mut Sha512::new(), "sample",
+            "ed0959d5880ab2d869ae7f6c2915c6d60f96507f9cb3e047c0046861da4a799cfe30f35cc900056d7c99cd7882433709",
+            "512c8cceee3890a84058ce1e22dbc2198f42323ce8aca9135329f03c068e5112dc7cc3ef3446defceb01a45c2667fdd5");
+        check(&key, &mut Sha1::new(), "test",
+            "4bc35d3a50ef4e30576f58cd96ce6bf638025ee624004a1f7789a8b8e43d0678acd9d29876daf46638645f7f404b11c7",
+            "d5a6326c494ed3ff614703878961c0fde7b2c278f9a65fd8c4b7186201a2991695ba1c84541327e966fa7b50f7382282");
+        check(&key, &mut Sha224::new(), "test",
+            "e8c9d0b6ea72a0e7837fea1d14a1a9557f29faa45d3e7ee888fc5bf954b5e62464a9a817c47ff78b8c11066b24080e72",
+            "07041d4a7a0379ac7232ff72e6f77b6ddb8f09b16cce0ec3286b2bd43fa8c6141c53ea5abef0d8231077a04540a96b66");
+        check(&key, &mut Sha256::new(), "test",
+            "6d6defac9ab64dabafe36c6bf510352a4cc27001263638e5b16d9bb51d451559f918eedaf2293be5b475cc8f0188636b",
+            "2d46f3becbcc523d5f1a1256bf0c9b024d879ba9e838144c8ba6baeb4b53b47d51ab373f9845c0514eefb14024787265");
+        check(&key, &mut Sha384::new(), "test",
+            "8203b63d3c853e8d77227fb377bcf7b7b772e97892a80f36ab775d509d7a5feb0542a7f0812998da8f1dd3ca3cf023db",
+            "ddd0760448d42d8a43af45af836fce4de8be06b485e9b61b827c2f13173923e06a739f040649a667bf3b828246baa5a5");
+        check(&key, &mut Sha512::new(), "test",
+            "a0d5d090c9980faf3c2ce57b7ae951d31977dd11c775d314af55f76c676447d06fb6495cd21b4b6e340fc236584fb277",
+            "976984e59b4c77b0e8e4460dca3d9f20e07b9bb1f63beefaf576f6b2e8b224634a2092cd3792e0159ad9cee37659c736");
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = IsaacRng::new_unseeded();
+        let curves = [Curve::p256(), Curve::p384()];
+        for curve in curves.iter() {
+            let key = EcPrivateKey::generate(&mut rng, curve);
+            let other = EcPrivateKey::generate(&mut rng, curve);
+            let msg = b"The quick brown fox jumps over the lazy dog";
+            let sig = sign(&key, &mut Sha256::new(), msg);
+            assert!(verify(key.public_key(), &mut Sha256::new(), msg, &sig));
+            assert!(!verify(other.public_key(), &mut Sha256::new(), msg, &sig));
+            assert!(!verify(key.public_key(), &mut Sha384::new(), msg, &sig));
+
+            let bytes = sig.to_bytes(curve);
+            assert_eq!(bytes.len(), 2 * curve.scalar_size());
+            assert!(EcdsaSignature::from_bytes(curve, bytes.as_slice()) == Some(sig.clone()));
+            assert!(EcdsaSignature::from_bytes(curve, bytes.slice_from(1)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_recover_public_key() {
+        let mut rng = IsaacRng::new_unseeded();
+        let curves = [Curve::p256(), Curve::p384()];
+        for curve in curves.iter() {
+            for i in range(0u, 4) {
+                let key = EcPrivateKey::generate(&mut rng, curve);
+                let msg = [i as u8, ..10];
+                let (sig, recovery_id) = sign_recoverable(&key, &mut Sha256::new(), msg);
+                assert!(sig == sign(&key, &mut Sha256::new(), msg));
+                let recovered =
+                    recover_public_key(curve, &mut Sha256::new(), msg, &sig, recovery_id);
+                assert!(recovered.as_ref() == Some(key.public_key()));
+
+                // The other parity gives a different key, and negating s flips the parity
+                let other =
+                    recover_public_key(curve, &mut Sha256::new(), msg, &sig, recovery_id ^ 1);
+                assert!(other.as_ref() != Some(key.public_key()));
+                let negated = EcdsaSignature { r: sig.r.clone(), s: *curve.n() - sig.s };
+                let recovered =
+                    recover_public_key(curve, &mut Sha256::new(), msg, &negated, recovery_id ^ 1);
+                assert!(recovered.as_ref() == Some(key.public_key()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_out_of_range() {
+        let mut rng = IsaacRng::new_unseeded();
+        let curve = Curve::p256();
+        let key = EcPrivateKey::generate(&mut rng, &curve);
+        let msg = b"message";
+        let sig = sign(&key, &mut Sha256::new(), msg);
+        let zero = 0u.to_biguint().unwrap();
+        let n = curve.n().clone();
+        let bad = [
+            EcdsaSignature { r: zero.clone(), s: sig.s.clone() },
+            EcdsaSignature { r: sig.r.clone(), s: zero.clone() },
+            EcdsaSignature { r: sig.r + n, s: sig.s.clone() },
+            EcdsaSignature { r: sig.r.clone(), s: sig.s + n },
+            EcdsaSignature { r: n.clone(), s: sig.s.clone() }
+        ];
+        for sig in bad.iter() {
+            assert!(!verify(key.public_key(), &mut Sha256::new(), msg, sig));
+        }
+    }
+
+    // A digest that ignores its input and returns a fixed value, for the CAVP tests which give the
+    // digest rather than the message
+    struct FixedDigest {
+        value: Vec<u8>
+    }
+
+    impl Digest for FixedDigest {
+        fn input(&mut self, _: &[u8]) { }
+        fn input_bits(&mut self, _: &[u8], _: uint) { }
+        fn result(&mut self, out: &mut [u8]) {
+            copy_memory(out, self.value.as_slice());
+        }
+        fn reset(&mut self) { }
+        fn output_bits(&self) -> uint { 8 * self.value.len() }
+        fn block_size(&self) -> uint { 64 }
+    }
+
+    // An excerpt from the NIST CAVP FIPS 186-3 ECDSA signature verification tests, the first six
+    // [P-256,SHA-256] entries of SigVer.rsp in
+    // http://csrc.nist.gov/groups/STM/cavp/documents/dss/186-3ecdsatestvectors.zip, with the
+    // messages replaced by their SHA-256 digests
+    #[test]
+    fn test_verify_cavp() {
+        let curve = Curve::p256();
+        // (Qx, Qy, SHA-256(Msg), R, S, Result)
+        let tests = [
+            ("87f8f2b218f49845f6f10eec3877136269f5c1a54736dbdf69f89940cad41555",
+             "e15f369036f49842fac7a86c8a2b0557609776814448b8f5e84aa9f4395205e9",
+             "a82c31412f537135d1c418bd7136fb5fde9426e70c70e7c2fb11f02f30fdeae2",
+             "d19ff48b324915576416097d2544f7cbdf8768b1454ad20e0baac50e211f23b0",
+             "a3e81e59311cdfff2d4784949f7a2cb50ba6c3a91fa54710568e61aca3e847c6",
+             false),
+            ("5cf02a00d205bdfee2016f7421807fc38ae69e6b7ccd064ee689fc1a94a9f7d2",
+             "ec530ce3cc5c9d1af463f264d685afe2b4db4b5828d7e61b748930f3ce622a85",
+             "5984eab8854d0a9aa5f0c70f96deeb510e5f9ff8c51befcdc3c41bac53577f22",
+             "dc23d130c6117fb5751201455e99f36f59aba1a6a21cf2d0e7481a97451d6693",
+             "d6ce7708c18dbf35d4f8aa7240922dc6823f2e7058cbc1484fcad1599db5018c",
+             false),
+            ("2ddfd145767883ffbb0ac003ab4a44346d08fa2570b3120dcce94562422244cb",
+             "5f70c7d11ac2b7a435ccfbbae02c3df1ea6b532cc0e9db74f93fffca7c6f9a64",
+             "44b02ad3088076f997220a68ff0b27a58ecfa528b604427097cce5ca956274c5",
+             "9913111cff6f20c5bf453a99cd2c2019a4e749a49724a08774d14e4c113edda8",
+             "9467cd4cd21ecb56b0cab0a9a453b43386845459127a952421f5c6382866c5cc",
+             false),
+            ("e424dc61d4bb3cb7ef4344a7f8957a0c5134e16f7a67c074f82e6e12f49abf3c",
+             "970eed7aa2bc48651545949de1dddaf0127e5965ac85d1243d6f60e7dfaee927",
+             "d1b8ef21eb4182ee270638061063a3f3c16c114e33937f69fb232cc833965a94",
+             "bf96b99aa49c705c910be33142017c642ff540c76349b9dab72f981fd9347f4f",
+             "17c55095819089c2e03b9cd415abdf12444e323075d98f31920b9e0f57ec871c",
+             true),
+            ("e0fc6a6f50e1c57475673ee54e3a57f9a49f3328e743bf52f335e3eeaa3d2864",
+             "7f59d689c91e463607d9194d99faf316e25432870816dde63f5d4b373f12f22a",
+             "b9336a8d1f3e8ede001d19f41320bc7672d772a3d2cb0e435fff3c27d6804a2c",
+             "1d75830cd36f4c9aa181b2c4221e87f176b7f05b7c87824e82e396c88315c407",
+             "cb2acb01dac96efc53a32d4a0d85d0c2e48955214783ecf50a4f0414a319c05a",
+             true),
+            ("a849bef575cac3c6920fbce675c3b787136209f855de19ffe2e8d29b31a5ad86",
+             "bf5fe4f7858f9b805bd8dcc05ad5e7fb889de2f822f3d8b41694e6c55c16b471",
+             "640c13e290147a48c83e0ea75a0f92723cda125ee21a747e34c8d1b36f16cf2d",
+             "25acc3aa9d9e84c7abf08f73fa4195acc506491d6fc37cb9074528a7db87b9d6",
+             "9b21d5b5259ed3f2ef07dfec6cc90d3a37855d1ce122a85ba6a333f307d31537",
+             false)
+        ];
+        for &(x, y, digest, r, s, result) in tests.iter() {
+            let point = format!("04{}{}", x, y).as_slice().from_hex().unwrap();
+            let key = EcPublicKey::from_bytes(&curve, point.as_slice()).unwrap();
+            let mut digest = FixedDigest { value: digest.from_hex().unwrap() };
+            let sig = EcdsaSignature { r: big(r), s: big(s) };
+            assert_eq!(verify(&key, &mut digest, [], &sig), result);
+        }
+    }
+
+
+    // An excerpt from the Wycheproof ECDSA tests, ecdsa_secp256r1_sha256_test.json in
+    // https://github.com/google/wycheproof/tree/master/testvectors. The message is "123400" for all
+    // of them.
+    #[test]
+    fn test_verify_wycheproof() {
+        let curve = Curve::p256();
+        // (Wx, Wy, r, s, result)
+        let tests = [
+            // A valid signature
+            ("2927b10512bae3eddcfe467828128bad2903269919f7086069c8c4df6c732838",
+             "c7787964eaac00e5921fb1498a60f4606766b3d9685001558d1a974e7341513e",
+             "2ba3a8be6b94d5ec80a6d9d1190a436effe50d85a1eee859b8cc6af9bd5c2e18",
+             "4cd60b855d442f5b3c7b11eb6c4e0ae7525fe710fab9aa7c77a67f79e6fadd76",
+             true),
+            // The same signature with s replaced by n - s
+            ("2927b10512bae3eddcfe467828128bad2903269919f7086069c8c4df6c732838",
+             "c7787964eaac00e5921fb1498a60f4606766b3d9685001558d1a974e7341513e",
+             "2ba3a8be6b94d5ec80a6d9d1190a436effe50d85a1eee859b8cc6af9bd5c2e18",
+             "b329f479a2bbd0a5c384ee1493b1f5186a87139cac5df4087c134b49156847db",
+             true),
+            // r = 1, s = n - 1
+            ("2927b10512bae3eddcfe467828128bad2903269919f7086069c8c4df6c732838",
+             "c7787964eaac00e5921fb1498a60f4606766b3d9685001558d1a974e7341513e",
+             "1",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632550",
+             false),
+            // r = n - 1, s = n - 1
+            ("2927b10512bae3eddcfe467828128bad2903269919f7086069c8c4df6c732838",
+             "c7787964eaac00e5921fb1498a60f4606766b3d9685001558d1a974e7341513e",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632550",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632550",
+             false),
+            // r = n, s = n - 1
+            ("2927b10512bae3eddcfe467828128bad2903269919f7086069c8c4df6c732838",
+             "c7787964eaac00e5921fb1498a60f4606766b3d9685001558d1a974e7341513e",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632550",
+             false),
+            // r = p + 1, s = n - 1
+            ("2927b10512bae3eddcfe467828128bad2903269919f7086069c8c4df6c732838",
+             "c7787964eaac00e5921fb1498a60f4606766b3d9685001558d1a974e7341513e",
+             "ffffffff00000001000000000000000000000001000000000000000000000000",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632550",
+             false),
+            // A valid signature with a small r and s = n - 3
+            ("0ad99500288d466940031d72a9f5445a4d43784640855bf0a69874d2de5fe103",
+             "c5011e6ef2c42dcd50d5d3d29f99ae6eba2c80c9244f4c5422f0979ff0c3ba5e",
+             "4319055358e8617b0c46353d039cdaab",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc63254e",
+             true),
+            // r = p - 3, which is larger than n, and s = n - 3
+            ("0ad99500288d466940031d72a9f5445a4d43784640855bf0a69874d2de5fe103",
+             "c5011e6ef2c42dcd50d5d3d29f99ae6eba2c80c9244f4c5422f0979ff0c3ba5e",
+             "ffffffff00000001000000000000000000000000fffffffffffffffffffffffc",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc63254e",
+             false),
+            // r = n - 2, s = n - 3
+            ("ab05fd9d0de26b9ce6f4819652d9fc69193d0aa398f0fba8013e09c582204554",
+             "19235271228c786759095d12b75af0692dd4103f19f6a8c32f49435a1e9b8d45",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc63254f",
+             "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc63254e",
+             true),
+            // r = 5, s = 1
+            ("a71af64de5126a4a4e02b7922d66ce9415ce88a4c9d25514d91082c8725ac957",
+             "5d47723c8fbe580bb369fec9c2665d8e30a435b9932645482e7c9f11e872296b",
+             "5",
+             "1",
+             true),
+            // r = (n + 1) / 2, s = (n - 1) / 2
+            ("f50d371b91bfb1d7d14e1323523bc3aa8cbf2c57f9e284de628c8b4536787b86",
+             "f94ad887ac94d527247cd2e7d0c8b1291c553c9730405380b14cbb209f5fa2dd",
+             "7fffffff800000007fffffffffffffffde737d56d38bcf4279dce5617e3192a9",
+             "7fffffff800000007fffffffffffffffde737d56d38bcf4279dce5617e3192a8",
+             true)
+        ];
+        for &(x, y, r, s, result) in tests.iter() {
+            let point = format!("04{}{}", x, y).as_slice().from_hex().unwrap();
+            let key = EcPublicKey::from_bytes(&curve, point.as_slice()).unwrap();
+            let sig = EcdsaSignature { r: big(r), s: big(s) };
+            assert_eq!(verify(&key, &mut Sha256::new(), b"123400", &sig), result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use std::rand::IsaacRng;
+    use test::Bencher;
+
+    use ec::{Curve, EcPrivateKey};
+    use ecdsa::{sign, verify};
+    use sha2::Sha256;
+
+    #[bench]
+    pub fn sign_p256(bh: &mut Bencher) {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = EcPrivateKey::generate(&mut rng, &Curve::p256());
+        bh.iter( || {
+            sign(&key, &mut Sha256::new(), b"message")
+        });
+    }
+
+    #[bench]
+    pub fn verify_p256(bh: &mut Bencher) {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = EcPrivateKey::generate(&mut rng, &Curve::p256());
+        let sig = sign(&key, &mut Sha256::new(), b"message");
+        bh.iter( || {
+            verify(key.public_key(), &mut Sha256::new(), b"message", &sig)
+        });
+    }
+}
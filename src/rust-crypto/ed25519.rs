@@ -0,0 +1,643 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * The Ed25519 signature scheme as specified in RFC 8032, including the Ed25519ctx and Ed25519ph
+ * variants.
+ *
+ * Keys are generated from a 32 byte seed. The secret key is the seed followed by the public key,
+ * as in NaCl. Signing performs the same operations for every secret key, but verification works
+ * only with public values and is not constant time.
+ *
+ * Verification is strict: public keys and the R part of signatures must be canonical encodings of
+ * points on the curve and S must be less than the group order L. It checks the cofactored equation
+ * [8][S]B = [8]R + [8][k]A, which RFC 8032 allows, so that verifying signatures one at a time and
+ * in a batch always give the same result.
+ */
+
+use std::rand::Rng;
+
+use curve25519::Fe;
+use digest::Digest;
+use sha2::Sha512;
+
+// The curve constant d = -121665 / 121666
+static D: Fe = Fe([
+    0x78a3, 0x1359, 0x4dca, 0x75eb, 0xd8ab, 0x4141, 0x0a4d, 0x0070,
+    0xe898, 0x7779, 0x4079, 0x8cc7, 0xfe73, 0x2b6f, 0x6cee, 0x5203 ]);
+
+// 2 * d
+static D2: Fe = Fe([
+    0xf159, 0x26b2, 0x9b94, 0xebd6, 0xb156, 0x8283, 0x149a, 0x00e0,
+    0xd130, 0xeef3, 0x80f2, 0x198e, 0xfce7, 0x56df, 0xd9dc, 0x2406 ]);
+
+// A square root of -1
+static SQRT_M1: Fe = Fe([
+    0xa0b0, 0x4a0e, 0x1b27, 0xc4ee, 0xe478, 0xad2f, 0x1806, 0x2f43,
+    0xd7a7, 0x3dfb, 0x0099, 0x2b4d, 0xdf0b, 0x4fc1, 0x2480, 0x2b83 ]);
+
+// The coordinates of the base point B
+static BASE_X: Fe = Fe([
+    0xd51a, 0x8f25, 0x2d60, 0xc956, 0xa7b2, 0x9525, 0xc760, 0x692c,
+    0xdc5c, 0xfdd6, 0xe231, 0xc0a4, 0x53fe, 0xcd6e, 0x36d3, 0x2169 ]);
+static BASE_Y: Fe = Fe([
+    0x6658, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666,
+    0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666, 0x6666 ]);
+
+// The order of the base point, L = 2^252 + 27742317777372353535851937790883648493, little endian
+static L: [u8, ..32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x10 ];
+
+static DOM2_PREFIX: &'static [u8] = b"SigEd25519 no Ed25519 collisions";
+
+// A point on the curve in extended coordinates: x = X / Z, y = Y / Z and x * y = T / Z.
+struct Point {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe
+}
+
+impl Clone for Point { fn clone(&self) -> Point { *self } }
+
+impl Point {
+    fn identity() -> Point {
+        Point {
+            x: Fe::zero(),
+            y: Fe::one(),
+            z: Fe::one(),
+            t: Fe::zero()
+        }
+    }
+
+    fn base() -> Point {
+        Point {
+            x: BASE_X,
+            y: BASE_Y,
+            z: Fe::one(),
+            t: BASE_X.mul(&BASE_Y)
+        }
+    }
+
+    // The unified addition formula from "Twisted Edwards Curves Revisited", which works for any
+    // pair of points, including when they are equal.
+    fn add(&self, q: &Point) -> Point {
+        let a = self.y.sub(&self.x).mul(&q.y.sub(&q.x));
+        let b = self.x.add(&self.y).mul(&q.x.add(&q.y));
+        let c = self.t.mul(&q.t).mul(&D2);
+        let zz = self.z.mul(&q.z);
+        let d = zz.add(&zz);
+        let e = b.sub(&a);
+        let f = d.sub(&c);
+        let g = d.add(&c);
+        let h = b.add(&a);
+        Point {
+            x: e.mul(&f),
+            y: h.mul(&g),
+            z: g.mul(&f),
+            t: e.mul(&h)
+        }
+    }
+
+    fn neg(&self) -> Point {
+        Point {
+            x: self.x.neg(),
+            y: self.y,
+            z: self.z,
+            t: self.t.neg()
+        }
+    }
+
+    fn mul_by_cofactor(&self) -> Point {
+        let p2 = self.add(self);
+        let p4 = p2.add(&p2);
+        p4.add(&p4)
+    }
+
+    fn is_identity(&self) -> bool {
+        !self.x.is_nonzero() && !self.y.sub(&self.z).is_nonzero()
+    }
+
+    fn swap(p: &mut Point, q: &mut Point, bit: i64) {
+        Fe::swap(&mut p.x, &mut q.x, bit);
+        Fe::swap(&mut p.y, &mut q.y, bit);
+        Fe::swap(&mut p.z, &mut q.z, bit);
+        Fe::swap(&mut p.t, &mut q.t, bit);
+    }
+
+    // The encoding is y with the sign of x in the top bit.
+    fn encode(&self) -> [u8, ..32] {
+        let zinv = self.z.invert();
+        let x = self.x.mul(&zinv);
+        let y = self.y.mul(&zinv);
+        let mut out = y.to_bytes();
+        out[31] ^= (x.is_negative() as u8) << 7;
+        out
+    }
+
+    // Decode a point, rejecting encodings where y is not less than p or where x is 0 and the sign
+    // bit is set, so that every point has exactly one encoding.
+    fn decode(input: &[u8]) -> Option<Point> {
+        let y = Fe::from_bytes(input);
+        let canonical = y.to_bytes();
+        if canonical.slice_to(31) != input.slice_to(31) || canonical[31] != input[31] & 0x7f {
+            return None;
+        }
+
+        // x^2 = (y^2 - 1) / (d y^2 + 1). Compute the candidate square root
+        // x = u v^3 (u v^7)^((p - 5) / 8), where u = y^2 - 1 and v = d y^2 + 1.
+        let y2 = y.square();
+        let u = y2.sub(&Fe::one());
+        let v = y2.mul(&D).add(&Fe::one());
+        let v3 = v.square().mul(&v);
+        let v7 = v3.square().mul(&v);
+        let mut x = u.mul(&v3).mul(&u.mul(&v7).pow_p58());
+
+        // The candidate is correct, or needs to be multiplied by sqrt(-1), or x^2 isn't a square.
+        if v.mul(&x.square()).sub(&u).is_nonzero() {
+            x = x.mul(&SQRT_M1);
+            if v.mul(&x.square()).sub(&u).is_nonzero() {
+                return None;
+            }
+        }
+
+        let sign = input[31] >> 7 == 1;
+        if !x.is_nonzero() && sign {
+            return None;
+        }
+        if x.is_negative() != sign {
+            x = x.neg();
+        }
+
+        Some(Point {
+            x: x,
+            y: y,
+            z: Fe::one(),
+            t: x.mul(&y)
+        })
+    }
+}
+
+// Compute [s]p, performing the same operations whatever the scalar is.
+fn scalar_mul(p: &Point, s: &[u8]) -> Point {
+    let mut r = Point::identity();
+    let mut q = *p;
+    for i in range(0u, 256).rev() {
+        let bit = ((s[i >> 3] >> (i & 7)) & 1) as i64;
+        Point::swap(&mut r, &mut q, bit);
+        q = q.add(&r);
+        r = r.add(&r);
+        Point::swap(&mut r, &mut q, bit);
+    }
+    r
+}
+
+// Compute the sum of [scalars[i]]points[i] with a single chain of doublings. The time taken depends
+// on the scalars, so this must only be used with public values.
+fn multi_scalar_mul_vartime(scalars: &[[u8, ..32]], points: &[Point]) -> Point {
+    let mut acc = Point::identity();
+    for i in range(0u, 256).rev() {
+        acc = acc.add(&acc);
+        for (s, p) in scalars.iter().zip(points.iter()) {
+            if (s[i >> 3] >> (i & 7)) & 1 == 1 {
+                acc = acc.add(p);
+            }
+        }
+    }
+    acc
+}
+
+// Reduce a number, given as 64 signed limbs of 8 bits each, modulo L. This is the reduction from
+// TweetNaCl: the limbs above 2^256 are folded in using 2^252 = -(L - 2^252) mod L, and then the
+// result is reduced by subtracting the appropriate multiple of L.
+fn mod_l(x: &mut [i64, ..64]) -> [u8, ..32] {
+    for i in range(32u, 64).rev() {
+        let mut carry = 0i64;
+        for j in range(i - 32, i - 12) {
+            x[j] += carry - 16 * x[i] * (L[j - (i - 32)] as i64);
+            carry = (x[j] + 128) >> 8;
+            x[j] -= carry << 8;
+        }
+        x[i - 12] += carry;
+        x[i] = 0;
+    }
+
+    let mut carry = 0i64;
+    for j in range(0u, 32) {
+        x[j] += carry - (x[31] >> 4) * (L[j] as i64);
+        carry = x[j] >> 8;
+        x[j] &= 255;
+    }
+    for j in range(0u, 32) {
+        x[j] -= carry * (L[j] as i64);
+    }
+
+    let mut out = [0u8, ..32];
+    for i in range(0u, 32) {
+        x[i + 1] += x[i] >> 8;
+        out[i] = (x[i] & 255) as u8;
+    }
+    out
+}
+
+// Reduce a 64 byte little endian number modulo L
+fn reduce(input: &[u8]) -> [u8, ..32] {
+    let mut x = [0i64, ..64];
+    for (x, y) in x.mut_iter().zip(input.iter()) {
+        *x = *y as i64;
+    }
+    mod_l(&mut x)
+}
+
+// Compute a * b + c mod L
+fn mul_add(a: &[u8], b: &[u8], c: &[u8]) -> [u8, ..32] {
+    let mut x = [0i64, ..64];
+    for i in range(0u, 32) {
+        x[i] = c[i] as i64;
+    }
+    for i in range(0u, 32) {
+        for j in range(0u, 32) {
+            x[i + j] += a[i] as i64 * b[j] as i64;
+        }
+    }
+    mod_l(&mut x)
+}
+
+// Whether the 32 byte little endian scalar is less than L
+fn is_canonical_scalar(s: &[u8]) -> bool {
+    for i in range(0u, 32).rev() {
+        if s[i] != L[i] {
+            return s[i] < L[i];
+        }
+    }
+    false
+}
+
+fn to_array(input: &[u8]) -> [u8, ..32] {
+    let mut out = [0u8, ..32];
+    for (x, y) in out.mut_iter().zip(input.iter()) {
+        *x = *y;
+    }
+    out
+}
+
+// Hash the concatenation of the inputs with SHA-512 and reduce the result modulo L
+fn hash_mod_l(inputs: &[&[u8]]) -> [u8, ..32] {
+    let mut sha = Sha512::new();
+    for input in inputs.iter() {
+        sha.input(*input);
+    }
+    let mut hash = [0u8, ..64];
+    sha.result(hash);
+    reduce(hash)
+}
+
+// The dom2(phflag, context) prefix that separates Ed25519ctx and Ed25519ph from plain Ed25519
+fn dom2(phflag: u8, context: &[u8]) -> Vec<u8> {
+    assert!(context.len() <= 255);
+    let mut dom = Vec::from_slice(DOM2_PREFIX);
+    dom.push(phflag);
+    dom.push(context.len() as u8);
+    dom.push_all(context);
+    dom
+}
+
+fn prehash(message: &[u8]) -> [u8, ..64] {
+    let mut sha = Sha512::new();
+    sha.input(message);
+    let mut hash = [0u8, ..64];
+    sha.result(hash);
+    hash
+}
+
+/**
+ * Generate a key pair from a 32 byte seed, which should be generated with a cryptographically
+ * secure random number generator. Returns the 64 byte secret key and the 32 byte public key.
+ */
+pub fn keypair(seed: &[u8]) -> ([u8, ..64], [u8, ..32]) {
+    assert!(seed.len() == 32);
+    let mut h = [0u8, ..64];
+    let mut sha = Sha512::new();
+    sha.input(seed);
+    sha.result(h);
+    h[0] &= 248;
+    h[31] &= 127;
+    h[31] |= 64;
+
+    let public_key = scalar_mul(&Point::base(), h.slice_to(32)).encode();
+    let mut secret_key = [0u8, ..64];
+    for (x, y) in secret_key.mut_iter().zip(seed.iter().chain(public_key.iter())) {
+        *x = *y;
+    }
+    (secret_key, public_key)
+}
+
+fn sign_with_dom(message: &[u8], secret_key: &[u8], dom: &[u8]) -> [u8, ..64] {
+    assert!(secret_key.len() == 64);
+    let seed = secret_key.slice_to(32);
+    let public_key = secret_key.slice_from(32);
+
+    let mut h = [0u8, ..64];
+    let mut sha = Sha512::new();
+    sha.input(seed);
+    sha.result(h);
+    h[0] &= 248;
+    h[31] &= 127;
+    h[31] |= 64;
+
+    let r = hash_mod_l(&[dom, h.slice_from(32), message]);
+    let big_r = scalar_mul(&Point::base(), r).encode();
+    let k = hash_mod_l(&[dom, big_r.as_slice(), public_key, message]);
+    let s = mul_add(k, h.slice_to(32), r);
+
+    let mut signature = [0u8, ..64];
+    for (x, y) in signature.mut_iter().zip(big_r.iter().chain(s.iter())) {
+        *x = *y;
+    }
+    signature
+}
+
+// Decode and check the parts of a signature, returning A, R, S and k.
+fn parse_signature(message: &[u8], public_key: &[u8], signature: &[u8], dom: &[u8])
+        -> Option<(Point, Point, [u8, ..32], [u8, ..32])> {
+    if public_key.len() != 32 || signature.len() != 64 {
+        return None;
+    }
+    let a = match Point::decode(public_key) {
+        Some(a) => a,
+        None => return None
+    };
+    let r = match Point::decode(signature.slice_to(32)) {
+        Some(r) => r,
+        None => return None
+    };
+    let s = signature.slice_from(32);
+    if !is_canonical_scalar(s) {
+        return None;
+    }
+    let k = hash_mod_l(&[dom, signature.slice_to(32), public_key, message]);
+    Some((a, r, to_array(s), k))
+}
+
+fn verify_with_dom(message: &[u8], public_key: &[u8], signature: &[u8], dom: &[u8]) -> bool {
+    match parse_signature(message, public_key, signature, dom) {
+        Some((a, r, s, k)) => {
+            // [8]([S]B - [k]A - R) = 0
+            let check = multi_scalar_mul_vartime(&[s, k], &[Point::base(), a.neg()]).add(&r.neg());
+            check.mul_by_cofactor().is_identity()
+        }
+        None => false
+    }
+}
+
+/// Sign a message with a 64 byte secret key, as returned by keypair()
+pub fn signature(message: &[u8], secret_key: &[u8]) -> [u8, ..64] {
+    sign_with_dom(message, secret_key, &[])
+}
+
+/// Verify the signature of a message
+pub fn verify(message: &[u8], public_key: &[u8], signature: &[u8]) -> bool {
+    verify_with_dom(message, public_key, signature, &[])
+}
+
+/**
+ * Sign a message with Ed25519ctx, which binds the signature to a context string of 1 to 255
+ * bytes. Signatures made with one context don't verify with any other.
+ */
+pub fn signature_ctx(message: &[u8], secret_key: &[u8], context: &[u8]) -> [u8, ..64] {
+    assert!(context.len() > 0);
+    sign_with_dom(message, secret_key, dom2(0, context).as_slice())
+}
+
+/// Verify an Ed25519ctx signature of a message
+pub fn verify_ctx(message: &[u8], public_key: &[u8], signature: &[u8], context: &[u8]) -> bool {
+    if context.len() == 0 || context.len() > 255 {
+        return false;
+    }
+    verify_with_dom(message, public_key, signature, dom2(0, context).as_slice())
+}
+
+/**
+ * Sign a message with Ed25519ph, which signs the SHA-512 hash of the message instead of the message
+ * itself. The context string may be empty and can be at most 255 bytes long.
+ */
+pub fn signature_ph(message: &[u8], secret_key: &[u8], context: &[u8]) -> [u8, ..64] {
+    let hash = prehash(message);
+    sign_with_dom(hash, secret_key, dom2(1, context).as_slice())
+}
+
+/// Verify an Ed25519ph signature of a message
+pub fn verify_ph(message: &[u8], public_key: &[u8], signature: &[u8], context: &[u8]) -> bool {
+    if context.len() > 255 {
+        return false;
+    }
+    let hash = prehash(message);
+    verify_with_dom(hash, public_key, signature, dom2(1, context).as_slice())
+}
+
+/**
+ * Verify many Ed25519 signatures at once, which is faster than verifying them one by one. Returns
+ * true only if every signature is valid.
+ *
+ * The signatures are combined using random 128 bit coefficients, so the random number generator
+ * must be cryptographically secure - otherwise an attacker could construct invalid signatures that
+ * cancel each other out.
+ */
+pub fn verify_batch<R: Rng>(
+        rng: &mut R,
+        messages: &[&[u8]],
+        public_keys: &[&[u8]],
+        signatures: &[&[u8]]) -> bool {
+    assert!(messages.len() == public_keys.len() && messages.len() == signatures.len());
+
+    // Check that [8]([sum(z_i S_i)]B - sum([z_i]R_i) - sum([z_i k_i]A_i)) = 0
+    let zero = [0u8, ..32];
+    let mut b_coefficient = [0u8, ..32];
+    let mut scalars = Vec::with_capacity(2 * messages.len() + 1);
+    let mut points = Vec::with_capacity(2 * messages.len() + 1);
+    for i in range(0, messages.len()) {
+        let (a, r, s, k) = match parse_signature(messages[i], public_keys[i], signatures[i], &[]) {
+            Some(x) => x,
+            None => return false
+        };
+        let mut z = [0u8, ..32];
+        rng.fill_bytes(z.mut_slice_to(16));
+
+        b_coefficient = mul_add(z, s, b_coefficient);
+        scalars.push(z);
+        points.push(r.neg());
+        scalars.push(mul_add(z, k, zero));
+        points.push(a.neg());
+    }
+    scalars.push(b_coefficient);
+    points.push(Point::base());
+
+    let check = multi_scalar_mul_vartime(scalars.as_slice(), points.as_slice());
+    check.mul_by_cofactor().is_identity()
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::IsaacRng;
+
+    use serialize::hex::{FromHex, ToHex};
+
+    use ed25519::{keypair, signature, verify, signature_ctx, verify_ctx, signature_ph, verify_ph,
+        verify_batch};
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        s.from_hex().unwrap()
+    }
+
+    #[test]
+    fn test_rfc8032_ed25519() {
+        // (secret key, public key, message, signature) from RFC 8032 section 7.1
+        let tests = [
+            ("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+             "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+             "",
+             "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"),
+            ("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+             "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+             "72",
+             "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"),
+            ("c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+             "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+             "af82",
+             "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a")
+        ];
+        for &(seed, public_key, message, sig) in tests.iter() {
+            let (secret, public) = keypair(from_hex(seed).as_slice());
+            assert_eq!(public.to_hex().as_slice(), public_key);
+            let message = from_hex(message);
+            let s = signature(message.as_slice(), secret);
+            assert_eq!(s.as_slice().to_hex().as_slice(), sig);
+            assert!(verify(message.as_slice(), public, s));
+
+            let mut bad_message = message.clone();
+            bad_message.push(0);
+            assert!(!verify(bad_message.as_slice(), public, s));
+        }
+    }
+
+    #[test]
+    fn test_rfc8032_ed25519ctx() {
+        // From RFC 8032 section 7.2
+        let (secret, public) = keypair(from_hex(
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6").as_slice());
+        assert_eq!(public.to_hex().as_slice(),
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292");
+        let message = from_hex("f726936d19c800494e3fdaff20b276a8");
+
+        let tests = [
+            ("666f6f", "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d"),
+            ("626172", "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d")
+        ];
+        for &(context, sig) in tests.iter() {
+            let context = from_hex(context);
+            let s = signature_ctx(message.as_slice(), secret, context.as_slice());
+            assert_eq!(s.as_slice().to_hex().as_slice(), sig);
+            assert!(verify_ctx(message.as_slice(), public, s, context.as_slice()));
+            assert!(!verify_ctx(message.as_slice(), public, s, b"baz"));
+            assert!(!verify(message.as_slice(), public, s));
+        }
+        assert!(!verify_ctx(message.as_slice(), public, [0u8, ..64], b""));
+    }
+
+    #[test]
+    fn test_rfc8032_ed25519ph() {
+        // From RFC 8032 section 7.3
+        let (secret, public) = keypair(from_hex(
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42").as_slice());
+        assert_eq!(public.to_hex().as_slice(),
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf");
+        let s = signature_ph(b"abc", secret, b"");
+        assert_eq!(s.as_slice().to_hex().as_slice(), "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406");
+        assert!(verify_ph(b"abc", public, s, b""));
+        assert!(!verify_ph(b"abd", public, s, b""));
+        assert!(!verify_ph(b"abc", public, s, b"context"));
+        assert!(!verify(b"abc", public, s));
+    }
+
+    #[test]
+    fn test_non_canonical() {
+        let (secret, public) = keypair([7u8, ..32]);
+        let message = b"non-canonical";
+        let sig = signature(message, secret);
+        assert!(verify(message, public, sig));
+
+        // S + L is rejected even though it is equivalent to S
+        let l = from_hex("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010");
+        let mut bad_sig = sig;
+        let mut carry = 0u;
+        for i in range(0u, 32) {
+            let x = bad_sig[32 + i] as uint + l[i] as uint + carry;
+            bad_sig[32 + i] = x as u8;
+            carry = x >> 8;
+        }
+        assert!(!verify(message, public, bad_sig));
+
+        // S = L
+        let mut bad_sig = sig;
+        for i in range(0u, 32) {
+            bad_sig[32 + i] = l[i];
+        }
+        assert!(!verify(message, public, bad_sig));
+
+        // A public key with y = p + 1, which is a non-canonical encoding of y = 1
+        let non_canonical_y = from_hex(
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        assert!(!verify(message, non_canonical_y.as_slice(), sig));
+
+        // The identity with the sign bit set, which would mean x = -0
+        let mut negative_zero = [0u8, ..32];
+        negative_zero[0] = 1;
+        negative_zero[31] = 0x80;
+        assert!(!verify(message, negative_zero, sig));
+
+        // A y for which there is no x on the curve
+        let mut not_on_curve = [0u8, ..32];
+        not_on_curve[0] = 2;
+        assert!(!verify(message, not_on_curve, sig));
+
+        // Wrong lengths
+        assert!(!verify(message, public.slice_to(31), sig));
+        assert!(!verify(message, public, sig.slice_to(63)));
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut rng = IsaacRng::new_unseeded();
+        let messages = [b"first", b"second", b"third", b""];
+        let mut public_keys = Vec::new();
+        let mut signatures = Vec::new();
+        for (i, message) in messages.iter().enumerate() {
+            let (secret, public) = keypair([i as u8, ..32]);
+            public_keys.push(public);
+            signatures.push(signature(*message, secret));
+        }
+
+        let messages: Vec<&[u8]> = messages.iter().map(|x| *x).collect();
+        let public_keys: Vec<&[u8]> = public_keys.iter().map(|x| x.as_slice()).collect();
+        let mut signatures: Vec<&[u8]> = signatures.iter().map(|x| x.as_slice()).collect();
+        assert!(verify_batch(
+            &mut rng, messages.as_slice(), public_keys.as_slice(), signatures.as_slice()));
+        assert!(verify_batch(&mut rng, &[], &[], &[]));
+
+        // Swapping two signatures makes both invalid
+        signatures.as_mut_slice().swap(1, 2);
+        assert!(!verify_batch(
+            &mut rng, messages.as_slice(), public_keys.as_slice(), signatures.as_slice()));
+        signatures.as_mut_slice().swap(1, 2);
+
+        let mut other = messages.clone();
+        *other.get_mut(3) = b"fourth";
+        assert!(!verify_batch(
+            &mut rng, other.as_slice(), public_keys.as_slice(), signatures.as_slice()));
+    }
+}
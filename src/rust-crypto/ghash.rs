@@ -0,0 +1,641 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * The ghash module implements the GHASH universal hash function used by GCM and GMAC (NIST SP
+ * 800-38D) and the POLYVAL universal hash function used by AES-GCM-SIV (RFC 8452).
+ *
+ * Both functions are evaluated over GF(2^128). On x86 processors that support the PCLMULQDQ
+ * instruction, the field multiplication is done with carry-less multiplies. Otherwise, a portable
+ * implementation is used which doesn't index any tables with secret data and whose running time
+ * doesn't depend on the values being multiplied.
+ *
+ * POLYVAL is computed using the GHASH field multiplication as described in Appendix A of RFC 8452.
+ *
+ * Neither function is a Mac on its own - the key must be kept secret and used for only a single
+ * message, which is something the enclosing construction (e.g. GCM) has to take care of. Input
+ * that is not a multiple of 16 bytes is padded with zeros.
+ */
+
+use std::cmp::min;
+
+use cryptoutil::{read_u64_be, read_u64_le, write_u64_be, write_u64_le};
+use mac::{Mac, MacResult};
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+use util;
+
+/// Multiply two elements of GF(2^128) using the bit ordering of GHASH. Each element is stored as
+/// two u64s holding the big-endian interpretation of the 16 byte block. This is Algorithm 1 from
+/// NIST SP 800-38D, except that all of the conditional operations are replaced by masks.
+fn gf_mul_soft(x: [u64, ..2], h: [u64, ..2]) -> [u64, ..2] {
+    let mut z = [0u64, 0u64];
+    let mut v = h;
+    for i in range(0u, 128) {
+        let bit = (x[i / 64] >> (63 - (i % 64))) & 1;
+        let mask = 0 - bit;
+        z[0] ^= v[0] & mask;
+        z[1] ^= v[1] & mask;
+        let carry = v[1] & 1;
+        v[1] = (v[1] >> 1) | (v[0] << 63);
+        v[0] = (v[0] >> 1) ^ (0xe100000000000000 & (0 - carry));
+    }
+    z
+}
+
+/// Multiply two elements of GF(2^128) using the PCLMULQDQ instruction. The arguments and the
+/// result are in the same format as used by gf_mul_soft().
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+#[inline(never)]
+fn gf_mul_clmul(x: [u64, ..2], h: [u64, ..2]) -> [u64, ..2] {
+    // The xmm registers are loaded with the blocks as little-endian 128 bit integers, so the two
+    // halves have to be swapped.
+    let a = [x[1], x[0]];
+    let b = [h[1], h[0]];
+    let mut out = [0u64, ..2];
+
+    unsafe {
+        let ap: *const u64 = &a[0];
+        let bp: *const u64 = &b[0];
+        let outp: *mut u64 = &mut out[0];
+
+        asm!(
+        "
+            movdqu ($0), %xmm0
+            movdqu ($1), %xmm1
+
+            /* Compute the 256 bit carry-less product of xmm0 and xmm1 in xmm6:xmm3 */
+            movdqa %xmm0, %xmm3
+            pclmulqdq $$0x00, %xmm1, %xmm3
+            movdqa %xmm0, %xmm4
+            pclmulqdq $$0x10, %xmm1, %xmm4
+            movdqa %xmm0, %xmm5
+            pclmulqdq $$0x01, %xmm1, %xmm5
+            movdqa %xmm0, %xmm6
+            pclmulqdq $$0x11, %xmm1, %xmm6
+            pxor %xmm5, %xmm4
+            movdqa %xmm4, %xmm5
+            psrldq $$8, %xmm4
+            pslldq $$8, %xmm5
+            pxor %xmm5, %xmm3
+            pxor %xmm4, %xmm6
+
+            /* The operands are bit reflected, so the product has to be shifted left by one */
+            movdqa %xmm3, %xmm7
+            movdqa %xmm6, %xmm4
+            pslld $$1, %xmm3
+            pslld $$1, %xmm6
+            psrld $$31, %xmm7
+            psrld $$31, %xmm4
+            movdqa %xmm7, %xmm5
+            pslldq $$4, %xmm4
+            pslldq $$4, %xmm7
+            psrldq $$12, %xmm5
+            por %xmm7, %xmm3
+            por %xmm4, %xmm6
+            por %xmm5, %xmm6
+
+            /* Reduce modulo x^128 + x^7 + x^2 + x + 1 - first phase */
+            movdqa %xmm3, %xmm7
+            movdqa %xmm3, %xmm4
+            movdqa %xmm3, %xmm5
+            pslld $$31, %xmm7
+            pslld $$30, %xmm4
+            pslld $$25, %xmm5
+            pxor %xmm4, %xmm7
+            pxor %xmm5, %xmm7
+            movdqa %xmm7, %xmm4
+            pslldq $$12, %xmm7
+            psrldq $$4, %xmm4
+            pxor %xmm7, %xmm3
+
+            /* Second phase */
+            movdqa %xmm3, %xmm2
+            movdqa %xmm3, %xmm0
+            movdqa %xmm3, %xmm5
+            psrld $$1, %xmm2
+            psrld $$2, %xmm0
+            psrld $$7, %xmm5
+            pxor %xmm0, %xmm2
+            pxor %xmm5, %xmm2
+            pxor %xmm4, %xmm2
+            pxor %xmm2, %xmm3
+            pxor %xmm3, %xmm6
+
+            movdqu %xmm6, ($2)
+        "
+        : // outputs
+        : "r" (ap), "r" (bp), "r" (outp) // inputs
+        : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "memory" // clobbers
+        : "volatile" // options
+        );
+    }
+
+    [out[1], out[0]]
+}
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+fn supports_clmul() -> bool {
+    util::supports_pclmulqdq()
+}
+
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+fn supports_clmul() -> bool {
+    false
+}
+
+/// The state shared by GHASH and POLYVAL. If reversed is set, blocks are byte reversed as they are
+/// read and written which turns the GHASH computation into a POLYVAL computation.
+struct GfHasher {
+    h: [u64, ..2],
+    state: [u64, ..2],
+    buffer: [u8, ..16],
+    leftover: uint,
+    reversed: bool,
+    use_clmul: bool,
+    finished: bool
+}
+
+impl Clone for GfHasher {
+    fn clone(&self) -> GfHasher { *self }
+}
+
+impl GfHasher {
+    fn new(h: [u64, ..2], reversed: bool, use_clmul: bool) -> GfHasher {
+        GfHasher {
+            h: h,
+            state: [0u64, ..2],
+            buffer: [0u8, ..16],
+            leftover: 0,
+            reversed: reversed,
+            use_clmul: use_clmul,
+            finished: false
+        }
+    }
+
+    fn read_block(&self, block: &[u8]) -> [u64, ..2] {
+        if self.reversed {
+            [read_u64_le(block.slice(8, 16)), read_u64_le(block.slice(0, 8))]
+        } else {
+            [read_u64_be(block.slice(0, 8)), read_u64_be(block.slice(8, 16))]
+        }
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    fn mul(&self, x: [u64, ..2]) -> [u64, ..2] {
+        if self.use_clmul {
+            gf_mul_clmul(x, self.h)
+        } else {
+            gf_mul_soft(x, self.h)
+        }
+    }
+
+    #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+    fn mul(&self, x: [u64, ..2]) -> [u64, ..2] {
+        gf_mul_soft(x, self.h)
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        let x = self.read_block(block);
+        let y = [self.state[0] ^ x[0], self.state[1] ^ x[1]];
+        self.state = self.mul(y);
+    }
+
+    fn input(&mut self, data: &[u8]) {
+        assert!(!self.finished);
+        let mut m = data;
+
+        if self.leftover > 0 {
+            let want = min(16 - self.leftover, m.len());
+            for i in range(0, want) {
+                self.buffer[self.leftover + i] = m[i];
+            }
+            m = m.slice_from(want);
+            self.leftover += want;
+
+            if self.leftover < 16 {
+                return;
+            }
+
+            let tmp = self.buffer;
+            self.block(tmp);
+
+            self.leftover = 0;
+        }
+
+        while m.len() >= 16 {
+            self.block(m.slice(0, 16));
+            m = m.slice_from(16);
+        }
+
+        for i in range(0, m.len()) {
+            self.buffer[i] = m[i];
+        }
+        self.leftover = m.len();
+    }
+
+    fn reset(&mut self) {
+        self.state = [0u64, ..2];
+        self.leftover = 0;
+        self.finished = false;
+    }
+
+    fn raw_result(&mut self, output: &mut [u8]) {
+        assert!(output.len() >= 16);
+        if !self.finished {
+            if self.leftover > 0 {
+                for i in range(self.leftover, 16) {
+                    self.buffer[i] = 0;
+                }
+                let tmp = self.buffer;
+                self.block(tmp);
+                self.leftover = 0;
+            }
+            self.finished = true;
+        }
+        if self.reversed {
+            write_u64_le(output.mut_slice(0, 8), self.state[1]);
+            write_u64_le(output.mut_slice(8, 16), self.state[0]);
+        } else {
+            write_u64_be(output.mut_slice(0, 8), self.state[0]);
+            write_u64_be(output.mut_slice(8, 16), self.state[1]);
+        }
+    }
+}
+
+/// The GHASH universal hash function
+#[deriving(Clone)]
+pub struct Ghash {
+    hasher: GfHasher
+}
+
+impl Ghash {
+    /// Create a new Ghash instance with the given 16 byte hash subkey
+    pub fn new(h: &[u8]) -> Ghash {
+        assert!(h.len() == 16);
+        let h = [read_u64_be(h.slice(0, 8)), read_u64_be(h.slice(8, 16))];
+        Ghash {
+            hasher: GfHasher::new(h, false, supports_clmul())
+        }
+    }
+}
+
+impl Mac for Ghash {
+    fn input(&mut self, data: &[u8]) {
+        self.hasher.input(data);
+    }
+
+    fn reset(&mut self) {
+        self.hasher.reset();
+    }
+
+    fn result(&mut self) -> MacResult {
+        let mut mac = [0u8, ..16];
+        self.raw_result(mac.as_mut_slice());
+        return MacResult::new(mac.as_slice());
+    }
+
+    fn raw_result(&mut self, output: &mut [u8]) {
+        self.hasher.raw_result(output);
+    }
+
+    fn output_bytes(&self) -> uint { 16 }
+}
+
+/// The POLYVAL universal hash function
+#[deriving(Clone)]
+pub struct Polyval {
+    hasher: GfHasher
+}
+
+impl Polyval {
+    /// Create a new Polyval instance with the given 16 byte key
+    pub fn new(h: &[u8]) -> Polyval {
+        assert!(h.len() == 16);
+
+        // POLYVAL(H, X) = ByteReverse(GHASH(mulX_GHASH(ByteReverse(H)), ByteReverse(X))), so the
+        // key has to be multiplied by x in the GHASH field.
+        let mut h = [read_u64_le(h.slice(8, 16)), read_u64_le(h.slice(0, 8))];
+        let carry = h[1] & 1;
+        h[1] = (h[1] >> 1) | (h[0] << 63);
+        h[0] = (h[0] >> 1) ^ (0xe100000000000000 & (0 - carry));
+
+        Polyval {
+            hasher: GfHasher::new(h, true, supports_clmul())
+        }
+    }
+}
+
+impl Mac for Polyval {
+    fn input(&mut self, data: &[u8]) {
+        self.hasher.input(data);
+    }
+
+    fn reset(&mut self) {
+        self.hasher.reset();
+    }
+
+    fn result(&mut self) -> MacResult {
+        let mut mac = [0u8, ..16];
+        self.raw_result(mac.as_mut_slice());
+        return MacResult::new(mac.as_slice());
+    }
+
+    fn raw_result(&mut self, output: &mut [u8]) {
+        self.hasher.raw_result(output);
+    }
+
+    fn output_bytes(&self) -> uint { 16 }
+}
+
+#[cfg(test)]
+mod test {
+    use ghash::{Ghash, Polyval};
+    use mac::Mac;
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use std::rand::{Rng, IsaacRng};
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use ghash::{GfHasher, gf_mul_soft, gf_mul_clmul};
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use util;
+
+    struct Test {
+        h: Vec<u8>,
+        input: Vec<u8>,
+        output: Vec<u8>
+    }
+
+    // Test Cases 2 and 4 from the GCM specification (the GHASH input is A || C || len(A) || len(C))
+    fn ghash_tests() -> Vec<Test> {
+        vec![
+            Test {
+                h: vec![
+                    0x66, 0xe9, 0x4b, 0xd4, 0xef, 0x8a, 0x2c, 0x3b,
+                    0x88, 0x4c, 0xfa, 0x59, 0xca, 0x34, 0x2b, 0x2e ],
+                input: vec![
+                    0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92,
+                    0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2, 0xfe, 0x78,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80 ],
+                output: vec![
+                    0xf3, 0x8c, 0xbb, 0x1a, 0xd6, 0x92, 0x23, 0xdc,
+                    0xc3, 0x45, 0x7a, 0xe5, 0xb6, 0xb0, 0xf8, 0x85 ]
+            },
+            Test {
+                h: vec![
+                    0xb8, 0x3b, 0x53, 0x37, 0x08, 0xbf, 0x53, 0x5d,
+                    0x0a, 0xa6, 0xe5, 0x29, 0x80, 0xd5, 0x3b, 0x78 ],
+                input: vec![
+                    0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef,
+                    0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef,
+                    0xab, 0xad, 0xda, 0xd2, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                    0x42, 0x83, 0x1e, 0xc2, 0x21, 0x77, 0x74, 0x24,
+                    0x4b, 0x72, 0x21, 0xb7, 0x84, 0xd0, 0xd4, 0x9c,
+                    0xe3, 0xaa, 0x21, 0x2f, 0x2c, 0x02, 0xa4, 0xe0,
+                    0x35, 0xc1, 0x7e, 0x23, 0x29, 0xac, 0xa1, 0x2e,
+                    0x21, 0xd5, 0x14, 0xb2, 0x54, 0x66, 0x93, 0x1c,
+                    0x7d, 0x8f, 0x6a, 0x5a, 0xac, 0x84, 0xaa, 0x05,
+                    0x1b, 0xa3, 0x0b, 0x39, 0x6a, 0x0a, 0xac, 0x97,
+                    0x3d, 0x58, 0xe0, 0x91, 0x00, 0x00, 0x00, 0x00,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa0,
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xe0 ],
+                output: vec![
+                    0x69, 0x8e, 0x57, 0xf7, 0x0e, 0x6e, 0xcc, 0x7f,
+                    0xd9, 0x46, 0x3b, 0x72, 0x60, 0xa9, 0xae, 0x5f ]
+            }
+        ]
+    }
+
+    // The example from Appendix A of RFC 8452
+    fn polyval_tests() -> Vec<Test> {
+        vec![
+            Test {
+                h: vec![
+                    0x25, 0x62, 0x93, 0x47, 0x58, 0x92, 0x42, 0x76,
+                    0x1d, 0x31, 0xf8, 0x26, 0xba, 0x4b, 0x75, 0x7b ],
+                input: vec![
+                    0x4f, 0x4f, 0x95, 0x66, 0x8c, 0x83, 0xdf, 0xb6,
+                    0x40, 0x17, 0x62, 0xbb, 0x2d, 0x01, 0xa2, 0x62,
+                    0xd1, 0xa2, 0x4d, 0xdd, 0x27, 0x21, 0xd0, 0x06,
+                    0xbb, 0xe4, 0x5f, 0x20, 0xd3, 0xc9, 0xf3, 0x62 ],
+                output: vec![
+                    0xf7, 0xa3, 0xb4, 0x7b, 0x84, 0x61, 0x19, 0xfa,
+                    0xe5, 0xb7, 0x86, 0x6c, 0xf5, 0xe5, 0xb7, 0x7e ]
+            }
+        ]
+    }
+
+    fn run_test<M: Mac>(mac: &mut M, test: &Test) {
+        let mut out = [0u8, ..16];
+
+        mac.input(test.input.as_slice());
+        mac.raw_result(out);
+        assert!(out.as_slice() == test.output.as_slice());
+
+        // Feed the data in one byte at a time
+        mac.reset();
+        for b in test.input.iter() {
+            mac.input([*b]);
+        }
+        mac.raw_result(out);
+        assert!(out.as_slice() == test.output.as_slice());
+    }
+
+    #[test]
+    fn test_ghash() {
+        let tests = ghash_tests();
+        for t in tests.iter() {
+            run_test(&mut Ghash::new(t.h.as_slice()), t);
+        }
+    }
+
+    #[test]
+    fn test_polyval() {
+        let tests = polyval_tests();
+        for t in tests.iter() {
+            run_test(&mut Polyval::new(t.h.as_slice()), t);
+        }
+    }
+
+    #[test]
+    fn test_ghash_zero_padding() {
+        // A partial final block is padded with zeros
+        let h = [0x42u8, ..16];
+        let mut out1 = [0u8, ..16];
+        let mut out2 = [0u8, ..16];
+
+        let mut ghash = Ghash::new(h);
+        ghash.input([1u8, ..21]);
+        ghash.raw_result(out1);
+
+        let mut padded = [0u8, ..32];
+        for i in range(0u, 21) {
+            padded[i] = 1;
+        }
+        ghash.reset();
+        ghash.input(padded);
+        ghash.raw_result(out2);
+
+        assert!(out1 == out2);
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_clmul_matches_portable() {
+        if !util::supports_pclmulqdq() {
+            return;
+        }
+        let mut rng = IsaacRng::new_unseeded();
+        for _ in range(0u, 1000) {
+            let x = [rng.gen::<u64>(), rng.gen::<u64>()];
+            let h = [rng.gen::<u64>(), rng.gen::<u64>()];
+            let soft = gf_mul_soft(x, h);
+            let clmul = gf_mul_clmul(x, h);
+            assert!(soft[0] == clmul[0] && soft[1] == clmul[1]);
+        }
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_clmul_hasher_matches_portable() {
+        if !util::supports_pclmulqdq() {
+            return;
+        }
+        let mut rng = IsaacRng::new_unseeded();
+        let mut out_soft = [0u8, ..16];
+        let mut out_clmul = [0u8, ..16];
+        for len in range(0u, 100) {
+            let h = [rng.gen::<u64>(), rng.gen::<u64>()];
+            let data: Vec<u8> = rng.gen_iter::<u8>().take(len).collect();
+            for &reversed in [false, true].iter() {
+                let mut soft = GfHasher::new(h, reversed, false);
+                let mut clmul = GfHasher::new(h, reversed, true);
+                soft.input(data.as_slice());
+                clmul.input(data.as_slice());
+                soft.raw_result(out_soft);
+                clmul.raw_result(out_clmul);
+                assert!(out_soft == out_clmul);
+            }
+        }
+    }
+
+    #[test]
+    fn test_portable_vectors() {
+        // Make sure the portable implementation is checked against the test vectors even if the
+        // processor supports PCLMULQDQ.
+        let mut out = [0u8, ..16];
+        for t in ghash_tests().iter() {
+            let mut ghash = Ghash::new(t.h.as_slice());
+            ghash.hasher.use_clmul = false;
+            ghash.input(t.input.as_slice());
+            ghash.raw_result(out);
+            assert!(out.as_slice() == t.output.as_slice());
+        }
+        for t in polyval_tests().iter() {
+            let mut polyval = Polyval::new(t.h.as_slice());
+            polyval.hasher.use_clmul = false;
+            polyval.input(t.input.as_slice());
+            polyval.raw_result(out);
+            assert!(out.as_slice() == t.output.as_slice());
+        }
+    }
+
+    fn run_clone_test<M: Mac + Clone>(new_mac: || -> M) {
+        let data1 = Vec::from_fn(100, |i| i as u8);
+        let data2 = Vec::from_fn(100, |i| (i * 5 + 3) as u8);
+        let mut out = [0u8, ..16];
+        let mut expected = [0u8, ..16];
+
+        // Split at points that leave a partial block buffered as well as at block boundaries
+        for split in range(0u, data1.len() + 1) {
+            let mut mac = new_mac();
+            mac.input(data1.slice_to(split));
+            let mut copy = mac.clone();
+            mac.input(data1.slice_from(split));
+            copy.input(data2.slice_from(split));
+
+            let mut fresh = new_mac();
+            fresh.input(data1.as_slice());
+            fresh.raw_result(expected);
+            mac.raw_result(out);
+            assert!(out == expected);
+
+            let mut fresh = new_mac();
+            fresh.input(data1.slice_to(split));
+            fresh.input(data2.slice_from(split));
+            fresh.raw_result(expected);
+            copy.raw_result(out);
+            assert!(out == expected);
+        }
+    }
+
+    #[test]
+    fn test_clone() {
+        let h = [0x42u8, ..16];
+        run_clone_test(|| Ghash::new(h));
+        run_clone_test(|| Polyval::new(h));
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use test::Bencher;
+    use mac::Mac;
+    use ghash::{Ghash, Polyval};
+
+    #[bench]
+    pub fn ghash_1k(bh: & mut Bencher) {
+        let mut mac = [0u8, ..16];
+        let key     = [0u8, ..16];
+        let bytes   = [1u8, ..1024];
+        bh.iter( || {
+            let mut ghash = Ghash::new(key);
+            ghash.input(bytes);
+            ghash.raw_result(mac.as_mut_slice());
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn ghash_portable_1k(bh: & mut Bencher) {
+        let mut mac = [0u8, ..16];
+        let key     = [0u8, ..16];
+        let bytes   = [1u8, ..1024];
+        bh.iter( || {
+            let mut ghash = Ghash::new(key);
+            ghash.hasher.use_clmul = false;
+            ghash.input(bytes);
+            ghash.raw_result(mac.as_mut_slice());
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn polyval_1k(bh: & mut Bencher) {
+        let mut mac = [0u8, ..16];
+        let key     = [0u8, ..16];
+        let bytes   = [1u8, ..1024];
+        bh.iter( || {
+            let mut polyval = Polyval::new(key);
+            polyval.input(bytes);
+            polyval.raw_result(mac.as_mut_slice());
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+}
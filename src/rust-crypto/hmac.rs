@@ -10,7 +10,8 @@
 
 use std::slice;
 
-use digest::Digest;
+use cryptoutil::read_bool;
+use digest::{Digest, DigestState, DigestStateError, InvalidStateLength, InvalidState};
 use mac::{Mac, MacResult};
 
 /**
@@ -18,11 +19,42 @@ use mac::{Mac, MacResult};
  */
 pub struct Hmac<D> {
     digest: D,
-    i_key: Vec<u8>,
-    o_key: Vec<u8>,
+    keys: HmacKeys<D>,
     finished: bool
 }
 
+// The function used to import a keyed state into the digest.
+type ImportFn<D> = fn(&mut D, &[u8]) -> Result<(), DigestStateError>;
+
+// Before processing the message, the digest must be put into the inner keyed state, and before
+// computing the result, into the outer keyed state. This is done either by processing the inner
+// and outer keys or, if the keyed states were precomputed, by importing them - which saves
+// processing a full block of key every time.
+enum HmacKeys<D> {
+    Keys(Vec<u8>, Vec<u8>),
+    KeyedStates(Vec<u8>, Vec<u8>, ImportFn<D>)
+}
+
+impl <D: Clone> Clone for Hmac<D> {
+    fn clone(&self) -> Hmac<D> {
+        Hmac {
+            digest: self.digest.clone(),
+            keys: self.keys.clone(),
+            finished: self.finished
+        }
+    }
+}
+
+impl <D> Clone for HmacKeys<D> {
+    fn clone(&self) -> HmacKeys<D> {
+        match *self {
+            Keys(ref i_key, ref o_key) => Keys(i_key.clone(), o_key.clone()),
+            KeyedStates(ref i_state, ref o_state, import) =>
+                KeyedStates(i_state.clone(), o_state.clone(), import)
+        }
+    }
+}
+
 fn derive_key(key: &mut [u8], mask: u8) {
     for elem in key.mut_iter() {
         *elem ^= mask;
@@ -56,6 +88,23 @@ fn create_keys<D: Digest>(digest: &mut D, key: &[u8]) -> (Vec<u8>, Vec<u8>) {
     return (i_key, o_key);
 }
 
+fn import_keyed_state<D: DigestState>(digest: &mut D, state: &[u8])
+        -> Result<(), DigestStateError> {
+    digest.import_state(state)
+}
+
+// Compute the states of the digest after it has processed the inner and the outer keys.
+fn keyed_states<D: Digest + DigestState>(digest: &mut D, key: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let (i_key, o_key) = create_keys(digest, key);
+    digest.reset();
+    digest.input(i_key.as_slice());
+    let inner = digest.export_state();
+    digest.reset();
+    digest.input(o_key.as_slice());
+    let outer = digest.export_state();
+    return (inner, outer);
+}
+
 impl <D: Digest> Hmac<D> {
     /**
      * Create a new Hmac instance.
@@ -70,11 +119,111 @@ impl <D: Digest> Hmac<D> {
         digest.input(i_key.as_slice());
         return Hmac {
             digest: digest,
-            i_key: i_key,
-            o_key: o_key,
+            keys: Keys(i_key, o_key),
             finished: false
         }
     }
+
+    // Put the digest, which must have just been reset, into the inner keyed state.
+    fn start_inner(&mut self) {
+        match self.keys {
+            Keys(ref i_key, _) => self.digest.input(i_key.as_slice()),
+            KeyedStates(ref inner, _, import) => {
+                assert!(import(&mut self.digest, inner.as_slice()).is_ok());
+            }
+        }
+    }
+
+    // Put the digest, which must have just been reset, into the outer keyed state.
+    fn start_outer(&mut self) {
+        match self.keys {
+            Keys(_, ref o_key) => self.digest.input(o_key.as_slice()),
+            KeyedStates(_, ref outer, import) => {
+                assert!(import(&mut self.digest, outer.as_slice()).is_ok());
+            }
+        }
+    }
+}
+
+impl <D: Digest + DigestState> Hmac<D> {
+    /**
+     * Compute the states of the digest after it has processed the inner and the outer keys. These
+     * may be stored and used with new_from_keyed_states() to create Hmac instances for the key
+     * without needing the key itself.
+     *
+     * # Arguments
+     * * digest - The Digest to use.
+     * * key - The key to use.
+     *
+     */
+    pub fn compute_keyed_states(mut digest: D, key: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        return keyed_states(&mut digest, key);
+    }
+
+    /**
+     * Create a new Hmac instance from precomputed keyed states. Resetting the instance restores
+     * the inner keyed state instead of processing the key again.
+     *
+     * # Arguments
+     * * digest - The Digest to use.
+     * * inner - The inner keyed state returned by compute_keyed_states().
+     * * outer - The outer keyed state returned by compute_keyed_states().
+     *
+     */
+    pub fn new_from_keyed_states(mut digest: D, inner: &[u8], outer: &[u8])
+            -> Result<Hmac<D>, DigestStateError> {
+        // Importing the outer state first checks that it is valid and leaves the digest in the
+        // inner state.
+        try!(digest.import_state(outer));
+        try!(digest.import_state(inner));
+        return Ok(Hmac {
+            digest: digest,
+            keys: KeyedStates(
+                Vec::from_slice(inner), Vec::from_slice(outer), import_keyed_state::<D>),
+            finished: false
+        });
+    }
+
+    /**
+     * Create a new Hmac instance that computes the keyed states once up front. This is equivalent
+     * to calling new_from_keyed_states() with the result of compute_keyed_states().
+     *
+     * # Arguments
+     * * digest - The Digest to use.
+     * * key - The key to use.
+     *
+     */
+    pub fn new_precomputed(mut digest: D, key: &[u8]) -> Hmac<D> {
+        let (inner, outer) = keyed_states(&mut digest, key);
+        return match Hmac::new_from_keyed_states(digest, inner.as_slice(), outer.as_slice()) {
+            Ok(hmac) => hmac,
+            Err(_) => fail!("The digest could not import its own state.")
+        };
+    }
+}
+
+/// The state of an Hmac is exported as the finished flag followed by the state of the digest. The
+/// keys or keyed states are not part of the exported state, so it can only be imported into an
+/// Hmac for the same key.
+impl <D: Digest + DigestState> DigestState for Hmac<D> {
+    fn export_state(&self) -> Vec<u8> {
+        let mut state = vec![self.finished as u8];
+        state.push_all(self.digest.export_state().as_slice());
+        return state;
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        if state.len() == 0 {
+            return Err(InvalidStateLength);
+        }
+        let finished = match read_bool(state[0]) {
+            Some(x) => x,
+            None => return Err(InvalidState)
+        };
+        try!(self.digest.import_state(state.slice_from(1)));
+        self.finished = finished;
+        return Ok(());
+    }
 }
 
 impl <D: Digest> Mac for Hmac<D> {
@@ -85,7 +234,7 @@ impl <D: Digest> Mac for Hmac<D> {
 
     fn reset(&mut self) {
         self.digest.reset();
-        self.digest.input(self.i_key.as_slice());
+        self.start_inner();
         self.finished = false;
     }
 
@@ -103,7 +252,7 @@ impl <D: Digest> Mac for Hmac<D> {
             self.digest.result(output);
 
             self.digest.reset();
-            self.digest.input(self.o_key.as_slice());
+            self.start_outer();
             self.digest.input(output);
 
             self.finished = true;
@@ -119,7 +268,7 @@ impl <D: Digest> Mac for Hmac<D> {
 mod test {
     use mac::{Mac, MacResult};
     use hmac::Hmac;
-    use digest::Digest;
+    use digest::{Digest, DigestState};
     use md5::Md5;
 
     struct Test {
@@ -189,4 +338,79 @@ mod test {
             assert!(result == expected);
         }
     }
+
+    #[test]
+    fn test_hmac_md5_keyed_states() {
+        let tests = tests();
+        for t in tests.iter() {
+            let (inner, outer) = Hmac::compute_keyed_states(Md5::new(), t.key.as_slice());
+            let mut hmac = Hmac::new_from_keyed_states(
+                Md5::new(), inner.as_slice(), outer.as_slice()).ok().unwrap();
+            let expected = MacResult::new(t.expected.as_slice());
+
+            for _ in range(0u, 2) {
+                hmac.input(t.data.as_slice());
+                let result = hmac.result();
+                assert!(result == expected);
+                hmac.reset();
+            }
+
+            let mut hmac = Hmac::new_precomputed(Md5::new(), t.key.as_slice());
+            hmac.input(t.data.as_slice());
+            let result = hmac.result();
+            assert!(result == expected);
+        }
+
+        let (inner, outer) = Hmac::compute_keyed_states(Md5::new(), [1u8, 2, 3]);
+        assert!(Hmac::new_from_keyed_states(
+            Md5::new(), inner.slice_from(1), outer.as_slice()).is_err());
+        assert!(Hmac::new_from_keyed_states(
+            Md5::new(), inner.as_slice(), outer.slice_from(1)).is_err());
+    }
+
+    #[test]
+    fn test_hmac_md5_state() {
+        let tests = tests();
+        for t in tests.iter() {
+            let split = t.data.len() / 2;
+            let mut hmac = Hmac::new(Md5::new(), t.key.as_slice());
+            hmac.input(t.data.slice_to(split));
+            let state = hmac.export_state();
+
+            let mut hmac2 = Hmac::new_precomputed(Md5::new(), t.key.as_slice());
+            assert!(hmac2.import_state(state.as_slice()).is_ok());
+            hmac2.input(t.data.slice_from(split));
+            let result = hmac2.result();
+            let expected = MacResult::new(t.expected.as_slice());
+            assert!(result == expected);
+
+            assert!(hmac2.import_state(state.slice_to(0)).is_err());
+            assert!(hmac2.import_state(state.slice_to(state.len() - 1)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_hmac_md5_clone() {
+        let tests = tests();
+        for t in tests.iter() {
+            let split = t.data.len() / 2;
+            let mut hmacs = vec![
+                Hmac::new(Md5::new(), t.key.as_slice()),
+                Hmac::new_precomputed(Md5::new(), t.key.as_slice())];
+            for hmac in hmacs.mut_iter() {
+                hmac.input(t.data.slice_to(split));
+                let mut copy = hmac.clone();
+                hmac.input(t.data.slice_from(split));
+                copy.input(t.data.slice_from(split));
+                let expected = MacResult::new(t.expected.as_slice());
+                assert!(hmac.result() == expected);
+                assert!(copy.result() == expected);
+
+                // The copy must have its own keys
+                copy.reset();
+                copy.input(t.data.as_slice());
+                assert!(copy.result() == expected);
+            }
+        }
+    }
 }
@@ -10,11 +10,13 @@
 
 use std::iter::range_step;
 
-use cryptoutil::{write_u32_le, read_u32v_le, FixedBuffer, FixedBuffer64, StandardPadding};
-use digest::Digest;
+use cryptoutil::{write_u32_le, read_u32v_le, read_u32_be, read_u64_be, push_u32_be, push_u64_be,
+    push_fixed_buffer, read_fixed_buffer, read_bool, FixedBuffer, FixedBuffer64, StandardPadding};
+use digest::{Digest, DigestState, DigestStateError, InvalidStateLength, InvalidState};
 
 
 // A structure that represents that state of a digest computation for the MD5 digest function
+#[deriving(Clone)]
 struct Md5State {
     s0: u32,
     s1: u32,
@@ -152,6 +154,7 @@ static C4: [u32, ..16] = [
 
 
 /// The MD5 Digest algorithm
+#[deriving(Clone)]
 pub struct Md5 {
     length_bytes: u64,
     buffer: FixedBuffer64,
@@ -209,10 +212,48 @@ impl Digest for Md5 {
     fn block_size(&self) -> uint { 64 }
 }
 
+// The state is exported as the 4 state words, the length, the finished flag and the buffer.
+static STATE_LEN: uint = 16 + 8 + 1 + 65;
+
+impl DigestState for Md5 {
+    fn export_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(STATE_LEN);
+        push_u32_be(&mut state, self.state.s0);
+        push_u32_be(&mut state, self.state.s1);
+        push_u32_be(&mut state, self.state.s2);
+        push_u32_be(&mut state, self.state.s3);
+        push_u64_be(&mut state, self.length_bytes);
+        state.push(self.finished as u8);
+        push_fixed_buffer(&mut state, &self.buffer);
+        return state;
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        if state.len() != STATE_LEN {
+            return Err(InvalidStateLength);
+        }
+        let finished = match read_bool(state[24]) {
+            Some(x) => x,
+            None => return Err(InvalidState)
+        };
+        if !read_fixed_buffer(&mut self.buffer, state.slice_from(25)) {
+            return Err(InvalidState);
+        }
+        self.state.s0 = read_u32_be(state.slice(0, 4));
+        self.state.s1 = read_u32_be(state.slice(4, 8));
+        self.state.s2 = read_u32_be(state.slice(8, 12));
+        self.state.s3 = read_u32_be(state.slice(12, 16));
+        self.length_bytes = read_u64_be(state.slice(16, 24));
+        self.finished = finished;
+        return Ok(());
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
-    use cryptoutil::test::test_digest_1million_random;
+    use cryptoutil::test::{test_digest_1million_random, test_digest_state,
+        test_digest_clone};
     use digest::Digest;
     use md5::Md5;
 
@@ -283,6 +324,16 @@ mod tests {
             64,
             "7707d6ae4e027c70eea2a935c2296f21");
     }
+
+    #[test]
+    fn test_state_md5() {
+        test_digest_state(&mut Md5::new(), &mut Md5::new());
+    }
+
+    #[test]
+    fn test_clone_md5() {
+        test_digest_clone(&mut Md5::new());
+    }
 }
 
 
@@ -0,0 +1,417 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * Constant time modular exponentiation using Montgomery multiplication.
+ *
+ * The running time of BigUint arithmetic depends on the values involved, so prime_rng's
+ * modular_exp leaks the exponent through timing. The Montgomery type instead works on fixed length
+ * arrays of 32 bit limbs: a multiplication performs the same operations whatever its inputs are,
+ * the exponent is processed in windows of 4 bits over its full length and the precomputed powers of
+ * the base are selected by reading every entry of the table. Only odd moduli are supported.
+ *
+ * The field operations are also public, working on values in Montgomery form (x * R mod n, stored
+ * as little endian limbs), so that other constant time code, such as the elliptic curve
+ * arithmetic, can be built on them.
+ */
+
+use std::cmp;
+use std::num::One;
+
+use num::bigint::BigUint;
+use num::integer::Integer;
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be, read_u32v_be, write_u32_be};
+
+static WINDOW_BITS: uint = 4;
+
+/// Precomputed values for arithmetic modulo an odd number in Montgomery form
+#[deriving(Clone, PartialEq)]
+pub struct Montgomery {
+    n: BigUint,
+    // The modulus as little endian limbs
+    limbs: Vec<u32>,
+    // -n^-1 mod 2^32
+    n0_inv: u32,
+    // R^2 mod n, where R = 2^(32 * limbs.len())
+    r2: Vec<u32>
+}
+
+// Returns 0xffffffff if a == b and 0 otherwise.
+fn ct_eq(a: u32, b: u32) -> u32 {
+    let x = (a ^ b) as u64;
+    return ((x - 1) >> 32) as u32;
+}
+
+fn to_limbs(x: &BigUint, len: uint) -> Vec<u32> {
+    let bytes = biguint_to_bytes_be(x, len * 4);
+    let mut limbs = Vec::from_elem(len, 0u32);
+    read_u32v_be(limbs.as_mut_slice(), bytes.as_slice());
+    limbs.reverse();
+    return limbs;
+}
+
+fn from_limbs(limbs: &[u32]) -> BigUint {
+    let mut bytes = Vec::from_elem(limbs.len() * 4, 0u8);
+    for (chunk, x) in bytes.as_mut_slice().mut_chunks(4).zip(limbs.iter().rev()) {
+        write_u32_be(chunk, *x);
+    }
+    return biguint_from_bytes_be(bytes.as_slice());
+}
+
+impl Montgomery {
+    /// Set up arithmetic modulo n, which must be odd and greater than 1
+    pub fn new(n: &BigUint) -> Montgomery {
+        let one: BigUint = One::one();
+        assert!(n.is_odd() && *n > one);
+
+        let len = (n.bits() + 31) / 32;
+        let limbs = to_limbs(n, len);
+
+        // Every odd number is its own inverse modulo 8 and each Newton iteration doubles the
+        // number of correct bits.
+        let n0 = limbs[0];
+        let mut inv = n0;
+        for _ in range(0u, 4) {
+            inv = inv * (2 - n0 * inv);
+        }
+
+        let r2 = (one << (64 * len)) % *n;
+        Montgomery {
+            n: n.clone(),
+            limbs: limbs,
+            n0_inv: 0 - inv,
+            r2: to_limbs(&r2, len)
+        }
+    }
+
+    /// The modulus
+    pub fn modulus<'a>(&'a self) -> &'a BigUint { &self.n }
+
+    /// The number of 32 bit limbs in a value
+    pub fn len(&self) -> uint { self.limbs.len() }
+
+    /// Convert x, which may be larger than the modulus, to Montgomery form
+    pub fn to_montgomery(&self, x: &BigUint) -> Vec<u32> {
+        let x = to_limbs(&(*x % self.n), self.limbs.len());
+        return self.mul(x.as_slice(), self.r2.as_slice());
+    }
+
+    /// Convert a value in Montgomery form back to an integer
+    pub fn from_montgomery(&self, a: &[u32]) -> BigUint {
+        let mut one = Vec::from_elem(self.limbs.len(), 0u32);
+        *one.get_mut(0) = 1;
+        return from_limbs(self.mul(a, one.as_slice()).as_slice());
+    }
+
+    /// Compute a * b / R mod n, where a and b are less than n
+    pub fn mul(&self, a: &[u32], b: &[u32]) -> Vec<u32> {
+        let n = self.limbs.as_slice();
+        let len = n.len();
+        let mut t_vec = Vec::from_elem(len + 2, 0u32);
+        let t = t_vec.as_mut_slice();
+
+        for i in range(0, len) {
+            // t += a[i] * b
+            let mut c = 0u64;
+            for j in range(0, len) {
+                let x = t[j] as u64 + a[i] as u64 * b[j] as u64 + c;
+                t[j] = x as u32;
+                c = x >> 32;
+            }
+            let x = t[len] as u64 + c;
+            t[len] = x as u32;
+            t[len + 1] = (x >> 32) as u32;
+
+            // t = (t + m * n) / 2^32, where m is chosen so that the division is exact
+            let m = t[0] * self.n0_inv;
+            let x = t[0] as u64 + m as u64 * n[0] as u64;
+            let mut c = x >> 32;
+            for j in range(1, len) {
+                let x = t[j] as u64 + m as u64 * n[j] as u64 + c;
+                t[j - 1] = x as u32;
+                c = x >> 32;
+            }
+            let x = t[len] as u64 + c;
+            t[len - 1] = x as u32;
+            t[len] = t[len + 1] + (x >> 32) as u32;
+        }
+
+        // t < 2n, so at most one subtraction of n is needed. t - n is negative if the borrow out of
+        // the low limbs isn't covered by the top limb of t, which is 0 or 1.
+        let mut out = Vec::from_elem(len, 0u32);
+        let mut borrow = 0u64;
+        for (j, o) in out.mut_iter().enumerate() {
+            let x = (t[j] as u64) - (n[j] as u64) - borrow;
+            *o = x as u32;
+            borrow = (x >> 32) & 1;
+        }
+        let keep = 0 - ((borrow as u32) & (t[len] ^ 1));
+        for (j, o) in out.mut_iter().enumerate() {
+            *o = (t[j] & keep) | (*o & !keep);
+        }
+        return out;
+    }
+
+    /// Compute a + b mod n, where a and b are less than n
+    pub fn add(&self, a: &[u32], b: &[u32]) -> Vec<u32> {
+        let n = self.limbs.as_slice();
+        let mut sum = Vec::from_elem(n.len(), 0u32);
+        let mut carry = 0u64;
+        for (j, s) in sum.mut_iter().enumerate() {
+            let x = a[j] as u64 + b[j] as u64 + carry;
+            *s = x as u32;
+            carry = x >> 32;
+        }
+
+        // Subtract n unless the sum is less than n, which is when there is no carry out of the
+        // addition but there is a borrow out of the subtraction.
+        let mut out = Vec::from_elem(n.len(), 0u32);
+        let mut borrow = 0u64;
+        for (j, o) in out.mut_iter().enumerate() {
+            let x = (sum[j] as u64) - (n[j] as u64) - borrow;
+            *o = x as u32;
+            borrow = (x >> 32) & 1;
+        }
+        let keep = 0 - ((borrow as u32) & ((carry as u32) ^ 1));
+        for (j, o) in out.mut_iter().enumerate() {
+            *o = (sum[j] & keep) | (*o & !keep);
+        }
+        return out;
+    }
+
+    /// Compute a - b mod n, where a and b are less than n
+    pub fn sub(&self, a: &[u32], b: &[u32]) -> Vec<u32> {
+        let n = self.limbs.as_slice();
+        let mut out = Vec::from_elem(n.len(), 0u32);
+        let mut borrow = 0u64;
+        for (j, o) in out.mut_iter().enumerate() {
+            let x = (a[j] as u64) - (b[j] as u64) - borrow;
+            *o = x as u32;
+            borrow = (x >> 32) & 1;
+        }
+
+        // Add n back if the subtraction borrowed
+        let mask = 0 - (borrow as u32);
+        let mut carry = 0u64;
+        for (j, o) in out.mut_iter().enumerate() {
+            let x = *o as u64 + (n[j] & mask) as u64 + carry;
+            *o = x as u32;
+            carry = x >> 32;
+        }
+        return out;
+    }
+
+    // Copy table[index] into out, reading every entry of the table.
+    fn select(table: &[Vec<u32>], index: u32, out: &mut [u32]) {
+        for x in out.mut_iter() {
+            *x = 0;
+        }
+        for (i, entry) in table.iter().enumerate() {
+            let mask = ct_eq(i as u32, index);
+            for (x, y) in out.mut_iter().zip(entry.iter()) {
+                *x |= *y & mask;
+            }
+        }
+    }
+
+    /**
+     * Compute a^exponent in Montgomery form, where a is in Montgomery form. The time taken depends
+     * only on the size of n, as long as the exponent doesn't have more 32 bit limbs than n, and not
+     * on the values of a or the exponent.
+     */
+    pub fn exp(&self, a: &[u32], exponent: &BigUint) -> Vec<u32> {
+        let len = self.limbs.len();
+        let exp_len = cmp::max(len, (exponent.bits() + 31) / 32);
+        let exp = to_limbs(exponent, exp_len);
+
+        let mut one = Vec::from_elem(len, 0u32);
+        *one.get_mut(0) = 1;
+
+        // table[i] = a^i in Montgomery form
+        let table_len = 1u << WINDOW_BITS;
+        let mut table = Vec::with_capacity(table_len);
+        table.push(self.mul(one.as_slice(), self.r2.as_slice()));
+        table.push(Vec::from_slice(a));
+        for i in range(2, table_len) {
+            let x = self.mul(table[i - 1].as_slice(), table[1].as_slice());
+            table.push(x);
+        }
+
+        let mut acc = table[0].clone();
+        let mut entry = Vec::from_elem(len, 0u32);
+        for i in range(0, exp_len * 32 / WINDOW_BITS).rev() {
+            for _ in range(0, WINDOW_BITS) {
+                acc = self.mul(acc.as_slice(), acc.as_slice());
+            }
+            let bit = i * WINDOW_BITS;
+            let window = (exp[bit / 32] >> (bit % 32)) & (table_len as u32 - 1);
+            Montgomery::select(table.as_slice(), window, entry.as_mut_slice());
+            acc = self.mul(acc.as_slice(), entry.as_slice());
+        }
+        return acc;
+    }
+
+    /**
+     * Compute base^exponent mod n. The time taken depends only on the size of n, as long as the
+     * exponent doesn't have more 32 bit limbs than n, and not on the values of the base or the
+     * exponent.
+     */
+    pub fn modular_exp(&self, base: &BigUint, exponent: &BigUint) -> BigUint {
+        let base = self.to_montgomery(base);
+        let result = self.exp(base.as_slice(), exponent);
+        return self.from_montgomery(result.as_slice());
+    }
+}
+
+/// Compute base^exponent mod modulus in constant time. The modulus must be odd.
+pub fn modular_exp(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+    Montgomery::new(modulus).modular_exp(base, exponent)
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::IsaacRng;
+
+    use num::bigint::{BigUint, ToBigUint, RandBigInt};
+
+    use montgomery::{Montgomery, modular_exp};
+    use prime_rng;
+
+    fn big(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    #[test]
+    fn test_modular_exp_known() {
+        // (base, exponent, modulus, result)
+        let tests = [
+            ("4", "d", "1f1", "1bd"),
+            ("2", "0", "3", "1"),
+            ("0", "5", "7", "0"),
+            ("1234567890abcdef", "ffffffffffffffff", "ffffffffffffffc5", "dff31662a6e4c81d"),
+            (
+                "110d71df2231aa7916fa7ac9a6c103b2ec30503308c584567d1cf87097aa8d44",
+                "234b790ab5751ef85c4ab0c735839da2248c91dffeed7d7f90976dee7808cedd",
+                "ff3822a1b16abe903c2a2f2139ef2a242c952a4fc7779d9a5bcef505054e9423",
+                "4644a6066bd642ee1937fac01a9eb5176ffd0e8ea997db19bd1e7556015be10d"),
+            (
+                "1b835fd421feb7c06a5aa9cc474ac3b3c9253890dabe1ffa6e209fd60b58bad501f12d2886fb87d6d1a19f05d20bad47d0a8cded2c1ceafe0ddf7521186a6ff091583ef970d739f69ddcc11cc8d4eba374ee4ab264c12121ab61d9323db47ffd055643c13d6e08533cedae612513468010b6fb75321564c434b89456ec4120028c8b222df73da43a5e384ff96faab8a9695099f9623a93bac028d2aeeeb0b5f259514ddda5e6a9315f181bff6e617aff24891b7247d72448aac441c47b9ffe49",
+                "faf0a59ffac7b060cbe18ccfc00dc301112fe0d67bab3c7506da11b4a34c146b78ece9030656413d49c878ac2684822021426ae85d8c694604e239c2ce2126ba2613c0f2f36a625c6fcf12ca1fe2306ba3b8d5eaa2bb834c3ed50deddca460ecd758594e1e02a143596f2b640f6076ad95b14b065d32e42785ede3a4eb68eb29b0512cdd2114f60d745855ca819de2e00c1bef7cbf5b44e795c05e5f578282fec769bdd26a2bbdc8e7e053d05fd5e88fa91f07c0c588b7f6b1f8defa3c56612f",
+                "a6e72757b5c2625385d1934193a462e8fe7ad2f1ee306b36b7f821717204c2361ad4022954f478426678d6bbddf688cf1c32c993db65ad50c57fec86cda0fca3d03c39a31b1b9ce1cc1adb8c126689d5307c64a984db8bc6d1d6e34944583b620acada4a4dd4d5a9b27b4ab2b8f3b05d014b7769cc10e995e889c64ab06e1d5134c571253dab0850b8160826326f4891e32120eb76375854876ef9a707a2eca279a516986235c503109a1fc9fbb190bf593a0a89bd81fcb6e4c5c13347b1231f",
+                "ab3a5763d40e4d2688addb19cbf6f55e4228a71c10c61056f79c6a3544b58310314d3b3b45d2e727191aaeeec8d50207dd9ea4099df66335eabbeec7e6aac5b3711968adcb16e9097425f2ffbcdaf93fc35bccfe312540a1b5675763564b8c131185fc4ce2faa086a587236ab18a8d3308f48f1cfdaad0446422b6797e51680660e0f709e26b94b79caa45ea19be362d9b4545d954c6af73bb519596260b20a623a198a87a436a48f8c71f12704d200a22c9d5debbb4d9034d27304fbd1c738")
+        ];
+        for &(b, e, m, r) in tests.iter() {
+            assert!(modular_exp(&big(b), &big(e), &big(m)) == big(r));
+        }
+    }
+
+    #[test]
+    fn test_modular_exp_random() {
+        let mut rng = IsaacRng::new_unseeded();
+        let one = 1u.to_biguint().unwrap();
+        for bits in [31u, 32, 33, 64, 100, 512, 1000].iter() {
+            let n = (rng.gen_biguint(*bits) | (one << (*bits - 1))) | one;
+            let mont = Montgomery::new(&n);
+            for _ in range(0u, 5) {
+                // The base may be larger than the modulus
+                let base = rng.gen_biguint(*bits + 10);
+                let exp = rng.gen_biguint(*bits);
+                assert!(mont.modular_exp(&base, &exp) == prime_rng::modular_exp(&base, &exp, &n));
+            }
+            // An exponent longer than the modulus
+            let base = rng.gen_biguint(*bits);
+            let exp = rng.gen_biguint(*bits * 2 + 7);
+            assert!(mont.modular_exp(&base, &exp) == prime_rng::modular_exp(&base, &exp, &n));
+        }
+    }
+
+    #[test]
+    fn test_field_operations() {
+        let mut rng = IsaacRng::new_unseeded();
+        let one = 1u.to_biguint().unwrap();
+        for bits in [32u, 255, 256, 384, 521].iter() {
+            let n = (rng.gen_biguint(*bits) | (one << (*bits - 1))) | one;
+            let mont = Montgomery::new(&n);
+            for _ in range(0u, 10) {
+                let x = rng.gen_biguint_below(&n);
+                let y = rng.gen_biguint_below(&n);
+                let a = mont.to_montgomery(&x);
+                let b = mont.to_montgomery(&y);
+                assert!(mont.from_montgomery(a.as_slice()) == x);
+                assert!(mont.from_montgomery(mont.add(a.as_slice(), b.as_slice()).as_slice()) ==
+                        (x + y) % n);
+                assert!(mont.from_montgomery(mont.sub(a.as_slice(), b.as_slice()).as_slice()) ==
+                        (x + n - y) % n);
+                assert!(mont.from_montgomery(mont.mul(a.as_slice(), b.as_slice()).as_slice()) ==
+                        (x * y) % n);
+                let e = rng.gen_biguint(*bits);
+                assert!(mont.from_montgomery(mont.exp(a.as_slice(), &e).as_slice()) ==
+                        prime_rng::modular_exp(&x, &e, &n));
+            }
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_even_modulus() {
+        Montgomery::new(&0x10u.to_biguint().unwrap());
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use std::rand::IsaacRng;
+    use test::Bencher;
+
+    use num::bigint::{BigUint, ToBigUint, RandBigInt};
+
+    use montgomery::Montgomery;
+    use prime_rng;
+
+    fn bench_values(bits: uint) -> (BigUint, BigUint, BigUint) {
+        let mut rng = IsaacRng::new_unseeded();
+        let one = 1u.to_biguint().unwrap();
+        let n = (rng.gen_biguint(bits) | (one << (bits - 1))) | one;
+        let base = rng.gen_biguint_below(&n);
+        let exp = rng.gen_biguint_below(&n);
+        (base, exp, n)
+    }
+
+    #[bench]
+    pub fn montgomery_exp_2048(bh: &mut Bencher) {
+        let (base, exp, n) = bench_values(2048);
+        let mont = Montgomery::new(&n);
+        bh.iter( || {
+            mont.modular_exp(&base, &exp)
+        });
+    }
+
+    #[bench]
+    pub fn modular_exp_2048(bh: &mut Bencher) {
+        let (base, exp, n) = bench_values(2048);
+        bh.iter( || {
+            prime_rng::modular_exp(&base, &exp, &n)
+        });
+    }
+
+    #[bench]
+    pub fn montgomery_exp_4096(bh: &mut Bencher) {
+        let (base, exp, n) = bench_values(4096);
+        let mont = Montgomery::new(&n);
+        bh.iter( || {
+            mont.modular_exp(&base, &exp)
+        });
+    }
+
+    #[bench]
+    pub fn modular_exp_4096(bh: &mut Bencher) {
+        let (base, exp, n) = bench_values(4096);
+        bh.iter( || {
+            prime_rng::modular_exp(&base, &exp, &n)
+        });
+    }
+}
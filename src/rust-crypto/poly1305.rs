@@ -21,6 +21,10 @@ pub struct Poly1305 {
     final    : bool,
 }
 
+impl Clone for Poly1305 {
+    fn clone(&self) -> Poly1305 { *self }
+}
+
 impl Poly1305 {
     pub fn new(key: &[u8]) -> Poly1305 {
         assert!(key.len() == 32);
@@ -353,6 +357,29 @@ mod test {
         poly1305(key, msg, mac.as_mut_slice());
         assert_eq!(mac.as_slice(), expected.as_slice());
     }
+
+    #[test]
+    fn test_clone() {
+        let key = b"this is 32-byte key for Poly1305";
+        let msg = Vec::from_fn(100, |i| i as u8);
+        let mut total_mac = [0u8, ..16];
+        poly1305(key, msg.as_slice(), total_mac.as_mut_slice());
+
+        // Split at points that leave a partial block buffered as well as at block boundaries
+        for split in range(0u, msg.len() + 1) {
+            let mut poly = Poly1305::new(key);
+            poly.input(msg.slice_to(split));
+            let mut copy = poly.clone();
+            poly.input(msg.slice_from(split));
+            copy.input(msg.slice_from(split));
+
+            let mut mac = [0u8, ..16];
+            poly.raw_result(mac.as_mut_slice());
+            assert_eq!(mac.as_slice(), total_mac.as_slice());
+            copy.raw_result(mac.as_mut_slice());
+            assert_eq!(mac.as_slice(), total_mac.as_slice());
+        }
+    }
 }
 
 #[cfg(test)]
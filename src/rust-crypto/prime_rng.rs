@@ -1,12 +1,13 @@
-/// The bulk of the algorithms implemented here can be found in chapter 4 
-/// section 4 of the Handbook of Applied Cryptography. 
-///
-
-
-extern crate num;
-
-use std::rand::task_rng;
-use num::bigint::{ToBigUint, BigUint, RandBigInt};
+//! The bulk of the algorithms implemented here can be found in chapter 4
+//! section 4 of the Handbook of Applied Cryptography.
+//!
+//! All of the functions that need randomness take it from a caller supplied
+//! Rng. Primes that are used as key material must be generated with a
+//! cryptographically secure Rng, such as OsRng.
+
+use std::num::{One, Zero};
+use std::rand::Rng;
+use num::bigint::{ToBigUint, ToBigInt, BigUint, BigInt, RandBigInt};
 use num::integer::Integer;
 
 /// Odd primes less than 256
@@ -28,15 +29,15 @@ static primes: [u8, ..53] = [
 /// This is a probablistic method meaning it may generate
 /// false primes.
 ///
-pub fn generate_weak_prime(bit_size: uint, security: uint) -> BigUint {
+pub fn generate_weak_prime<R: Rng>(rng: &mut R, bit_size: uint, security: uint) -> BigUint {
     loop {
-        let n = task_rng().gen_biguint(bit_size);
+        let n = rng.gen_biguint(bit_size);
  
         if n.is_even() {
             continue;
         }
 
-        if probably_prime_faster(&n, security) {
+        if probably_prime_faster(rng, &n, security) {
             return n;
         }
     }
@@ -47,13 +48,16 @@ pub fn generate_weak_prime(bit_size: uint, security: uint) -> BigUint {
 /// Note: this is a probablistic method meaning it may yield
 /// false positives.
 #[inline]
-pub fn probably_prime_faster(n: &BigUint, security: uint) -> bool { 
+pub fn probably_prime_faster<R: Rng>(rng: &mut R, n: &BigUint, security: uint) -> bool {
  
         /*
          * Trial division
          */
         for i in primes.iter() {
             let big = i.to_biguint().unwrap();
+            if *n == big {
+                return true;
+            }
             if n.is_multiple_of(&big) {
                 return false;
             }
@@ -62,7 +66,7 @@ pub fn probably_prime_faster(n: &BigUint, security: uint) -> bool {
         /*
          * Use a more expensive primality test
          */
-        if probably_prime(n, security) {
+        if probably_prime(rng, n, security) {
            return true;
         }
 
@@ -76,25 +80,58 @@ pub fn probably_prime_faster(n: &BigUint, security: uint) -> bool {
 /// p + 1 has a large prime factor s
 /// r - 1 has a large prime factor t
 /// 
+/// The result is exactly bit_size bits long.
+///
+/// Fails if bit_size is less than 128, since that doesn't leave
+/// room for large enough r, s and t.
+///
 /// Even though this is "strong" it is still probablistic
 /// thus it may generate composite numbers (false primes)
 ///
-pub fn generate_strong_prime(bit_size: uint, security: uint) -> BigUint {
-    
+pub fn generate_strong_prime<R: Rng>(rng: &mut R, bit_size: uint, security: uint) -> BigUint {
+    let min = 1u.to_biguint().unwrap() << (bit_size - 1);
+    return generate_strong_prime_min(rng, &min, bit_size, security);
+}
+
+/// Generates a strong prime number p, as above, with
+/// min <= p < 2^bit_size. min must be at least 2^(bit_size - 1).
+///
+/// Fails if bit_size is less than 128 or min is out of range.
+///
+pub fn generate_strong_prime_min<R: Rng>(
+        rng: &mut R,
+        min: &BigUint,
+        bit_size: uint,
+        security: uint) -> BigUint {
+    let (p, _, _, _) = gordon(rng, min, bit_size, security);
+    return p;
+}
+
+/// Gordon's algorithm. Returns the strong prime p along with the primes
+/// r, s and t described above.
+///
+/// s is 3/8 of the final size and r is slightly larger, which leaves
+/// about a quarter of the bits for the final search for p.
+fn gordon<R: Rng>(rng: &mut R, min: &BigUint, bit_size: uint, security: uint)
+        -> (BigUint, BigUint, BigUint, BigUint) {
+    assert!(bit_size >= 128);
+
     let two = 2u.to_biguint().unwrap();
     let one = 1u.to_biguint().unwrap();
+    let limit = one << bit_size;
+    assert!(*min >= one << (bit_size - 1) && *min < limit);
 
-    let half = bit_size.div_floor(&2u);
-    let s = generate_weak_prime(half, security);
-    let t = generate_weak_prime(half + 1, security);
+    let s_size = bit_size * 3 / 8;
+    let s = generate_weak_prime(rng, s_size, security);
+    let t = generate_weak_prime(rng, s_size - 32, security);
     
-    let mut i = task_rng().gen_biguint(32u);
+    let mut i = rng.gen_biguint(32u);
 
     let r: BigUint;
     
     loop {
         let candidate = (two * i * t) + one;
-        if probably_prime_faster(&candidate, security) {
+        if probably_prime_faster(rng, &candidate, security) {
             r = candidate;
             break;
         }
@@ -103,25 +140,60 @@ pub fn generate_strong_prime(bit_size: uint, security: uint) -> BigUint {
 
     let p0 = (two * modular_exp(&s, &(r - two), &r)) * s - one;
 
-
-    let mut j = task_rng().gen_biguint(32u);
+    // p0 < 2rs, so the candidates are in range for j_min <= j < j_max.
+    let step = two * r * s;
+    let j_min = (*min - p0 + step - one) / step;
+    let j_max = (limit - p0) / step;
+    let mut j = rng.gen_biguint_range(&j_min, &j_max);
     
     loop {
-        let candidate = p0 + (two * j * r * s);
-        if probably_prime_faster(&candidate, security) {
-            return candidate;
+        let candidate = p0 + (j * step);
+        if candidate >= limit {
+            j = j_min.clone();
+            continue;
+        }
+        if probably_prime_faster(rng, &candidate, security) {
+            return (candidate, r, s, t);
         }
         j = j + one;
     }
+}
 
+/// Generates a safe prime p = 2q + 1, where q is also prime.
+/// The result is exactly bit_size bits long.
+///
+/// This is probablistic too, so either p or q may be composite.
+pub fn generate_safe_prime<R: Rng>(rng: &mut R, bit_size: uint, security: uint) -> BigUint {
+    assert!(bit_size >= 3);
 
+    let one = 1u.to_biguint().unwrap();
+    let top = one << (bit_size - 2);
 
+    loop {
+        let q = rng.gen_biguint(bit_size - 1) | top | one;
+        let p = (q << 1) + one;
+
+        if probably_prime_faster(rng, &q, security) && probably_prime_faster(rng, &p, security) {
+            return p;
+        }
+    }
+}
+
+/// Tests whether p is a safe prime, that is whether both p and
+/// (p - 1) / 2 are prime.
+pub fn is_safe_prime<R: Rng>(rng: &mut R, p: &BigUint, security: uint) -> bool {
+    if p.is_even() {
+        return false;
+    }
+
+    let q = (*p - 1u.to_biguint().unwrap()) >> 1;
+    return probably_prime_faster(rng, &q, security) && probably_prime_faster(rng, p, security);
 }
 
 /// Implements the Miller-Rabin primality test.
 /// This test is probablistic, this it may yield false
 /// positives.
-pub fn probably_prime(candidate: &BigUint, security: uint) -> bool {
+pub fn probably_prime<R: Rng>(rng: &mut R, candidate: &BigUint, security: uint) -> bool {
     let one = 1u.to_biguint().unwrap(); 
 
     if *candidate <= one {
@@ -129,8 +201,10 @@ pub fn probably_prime(candidate: &BigUint, security: uint) -> bool {
     }
 
     let two = 2u.to_biguint().unwrap();
+    let three = 3u.to_biguint().unwrap();
 
-    if *candidate == two {
+    // There are no witnesses to choose from for 2 and 3.
+    if *candidate == two || *candidate == three {
         return true;
     }
 
@@ -142,8 +216,8 @@ pub fn probably_prime(candidate: &BigUint, security: uint) -> bool {
 
     let (power, remainder) = factor_powers_of_two(&bound);
 
-    'witness: for i in range(1, security) {
-        let a: BigUint = task_rng().gen_biguint_range(&two, &bound);
+    'witness: for _ in range(0, security) {
+        let a: BigUint = rng.gen_biguint_range(&two, &bound);
     
         let mut x = modular_exp(&a, &remainder, candidate);
 
@@ -151,7 +225,7 @@ pub fn probably_prime(candidate: &BigUint, security: uint) -> bool {
             continue 'witness;
         }
 
-        for j in range(1, power) {
+        for _ in range(1, power) {
             x = modular_exp(&x, &two, candidate);
             if x == one { 
                 return false;
@@ -190,6 +264,9 @@ fn factor_powers_of_two(number: &BigUint) -> (uint, BigUint){
 /// Raises a number `base` to the exponent `exponent` modulo `modulus` and
 /// returns the result. 
 ///
+/// The time taken depends on the exponent, so secret exponents should use
+/// `montgomery::Montgomery` instead.
+///
 /// Equivalent to:
 /// ```rust
 /// num::pow(base, exponent) % modulus
@@ -219,20 +296,194 @@ pub fn modular_exp(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> Big
     result
 }
 
+/// Computes the inverse of `a` modulo `modulus` using the extended
+/// Euclidean algorithm. Returns None if `a` and `modulus` aren't coprime.
+pub fn modular_inverse(a: &BigUint, modulus: &BigUint) -> Option<BigUint> {
+    let zero: BigInt = Zero::zero();
+    let one: BigInt = One::one();
+    let m = modulus.to_bigint().unwrap();
+
+    let mut r0 = m.clone();
+    let mut r1 = (*a % *modulus).to_bigint().unwrap();
+    let mut t0 = zero.clone();
+    let mut t1 = one.clone();
+
+    while !r1.is_zero() {
+        let q = r0 / r1;
+        let r2 = r0 - q * r1;
+        r0 = r1;
+        r1 = r2;
+        let t2 = t0 - q * t1;
+        t0 = t1;
+        t1 = t2;
+    }
+
+    if r0 != one {
+        return None;
+    }
+    if t0 < zero {
+        t0 = t0 + m;
+    }
+    return t0.to_biguint();
+}
+
 #[cfg(test)]
 mod test {
 
-    use super::{modular_exp, probably_prime};
-    use num::bigint::{ToBigUint};
+    use std::rand::IsaacRng;
+
+    use super::{modular_exp, modular_inverse, probably_prime, probably_prime_faster,
+        generate_weak_prime, generate_strong_prime, generate_strong_prime_min, gordon,
+        generate_safe_prime, is_safe_prime};
+    use num::bigint::{ToBigUint, BigUint};
+    use num::integer::Integer;
 
 
     #[test]
     fn test_probably_prime() {
+        let mut rng = IsaacRng::new_unseeded();
+
         let seventeen = 17u.to_biguint().unwrap();
-        assert!(probably_prime(&seventeen, 1));
+        assert!(probably_prime(&mut rng, &seventeen, 1));
         
         let eighteen = 18u.to_biguint().unwrap();
-        assert!(!probably_prime(&eighteen, 1));
+        assert!(!probably_prime(&mut rng, &eighteen, 1));
+    }
+
+    fn known_primes() -> Vec<BigUint> {
+        let mut p: Vec<BigUint> = [2u, 3, 5, 7, 251, 257, 65537, 2147483647].iter()
+            .map(|x| x.to_biguint().unwrap()).collect();
+        // 2^89 - 1 and 2^127 - 1
+        p.push(from_str("618970019642690137449562111").unwrap());
+        p.push(from_str("170141183460469231731687303715884105727").unwrap());
+        return p;
+    }
+
+    fn known_composites() -> Vec<BigUint> {
+        let mut c: Vec<BigUint> = [0u, 1, 4, 9, 15, 253, 65535].iter()
+            .map(|x| x.to_biguint().unwrap()).collect();
+        // (2^61 - 1) * (2^89 - 1)
+        c.push(from_str("1427247692705959880439315947500961989719490561").unwrap());
+        return c;
+    }
+
+    // Carmichael numbers pass the Fermat test for every base coprime to them. The last three
+    // have no factors small enough to be found by trial division.
+    fn carmichael_numbers() -> Vec<BigUint> {
+        [561u, 1105, 1729, 2465, 2821, 6601, 8911, 41041, 118901521, 172947529, 216821881].iter()
+            .map(|x| x.to_biguint().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_known_primes() {
+        let mut rng = IsaacRng::new_unseeded();
+        for p in known_primes().iter() {
+            assert!(probably_prime(&mut rng, p, 20));
+            assert!(probably_prime_faster(&mut rng, p, 20));
+        }
+        for c in known_composites().iter() {
+            assert!(!probably_prime(&mut rng, c, 20));
+            assert!(!probably_prime_faster(&mut rng, c, 20));
+        }
+    }
+
+    #[test]
+    fn test_carmichael_numbers() {
+        let mut rng = IsaacRng::new_unseeded();
+        for c in carmichael_numbers().iter() {
+            assert!(!probably_prime(&mut rng, c, 20));
+            assert!(!probably_prime_faster(&mut rng, c, 20));
+        }
+    }
+
+    #[test]
+    fn test_generate_weak_prime() {
+        let mut rng = IsaacRng::new_unseeded();
+        for _ in range(0u, 10) {
+            let p = generate_weak_prime(&mut rng, 128, 20);
+            assert!(p.bits() <= 128);
+            assert!(probably_prime(&mut rng, &p, 20));
+        }
+    }
+
+    #[test]
+    fn test_deterministic_rng() {
+        let mut rng1 = IsaacRng::new_unseeded();
+        let mut rng2 = IsaacRng::new_unseeded();
+        assert!(generate_weak_prime(&mut rng1, 128, 20) == generate_weak_prime(&mut rng2, 128, 20));
+        assert!(generate_strong_prime(&mut rng1, 128, 20) ==
+            generate_strong_prime(&mut rng2, 128, 20));
+    }
+
+    #[test]
+    fn test_is_safe_prime() {
+        let mut rng = IsaacRng::new_unseeded();
+        for p in [5u, 7, 11, 23, 47, 59, 83, 107, 1019, 2147483783].iter() {
+            assert!(is_safe_prime(&mut rng, &p.to_biguint().unwrap(), 20));
+        }
+        for p in [0u, 1, 2, 3, 13, 17, 29, 31, 561, 1021, 2147483647].iter() {
+            assert!(!is_safe_prime(&mut rng, &p.to_biguint().unwrap(), 20));
+        }
+    }
+
+    #[test]
+    fn test_generate_safe_prime() {
+        let mut rng = IsaacRng::new_unseeded();
+        for bit_size in [3u, 10, 64, 128].iter() {
+            let p = generate_safe_prime(&mut rng, *bit_size, 20);
+            assert_eq!(p.bits(), *bit_size);
+            assert!(is_safe_prime(&mut rng, &p, 20));
+        }
+    }
+
+    #[test]
+    fn test_strong_prime_structure() {
+        let mut rng = IsaacRng::new_unseeded();
+        let one = 1u.to_biguint().unwrap();
+        let min = 1u.to_biguint().unwrap() << 127;
+        let (p, r, s, t) = gordon(&mut rng, &min, 128, 20);
+        for x in [&p, &r, &s, &t].iter() {
+            assert!(probably_prime(&mut rng, *x, 20));
+        }
+        assert!((p - one).is_multiple_of(&r));
+        assert!((p + one).is_multiple_of(&s));
+        assert!((r - one).is_multiple_of(&t));
+        assert_eq!(p.bits(), 128);
+    }
+
+    #[test]
+    fn test_generate_strong_prime() {
+        let mut rng = IsaacRng::new_unseeded();
+        for bit_size in [128u, 200, 512].iter() {
+            let p = generate_strong_prime(&mut rng, *bit_size, 20);
+            assert_eq!(p.bits(), *bit_size);
+            assert!(probably_prime(&mut rng, &p, 20));
+        }
+
+        // Just above sqrt(2) * 2^127, as used for RSA primes
+        let min = 0xb505u.to_biguint().unwrap() << 112;
+        for _ in range(0u, 5) {
+            let p = generate_strong_prime_min(&mut rng, &min, 128, 20);
+            assert!(p >= min);
+            assert_eq!(p.bits(), 128);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_generate_strong_prime_too_small() {
+        let mut rng = IsaacRng::new_unseeded();
+        generate_strong_prime(&mut rng, 127, 20);
+    }
+
+    #[test]
+    fn test_modular_inverse() {
+        let tests = [(3u, 11u, Some(4u)), (10, 17, Some(12)), (1, 2, Some(1)),
+            (17, 3120, Some(2753)), (6, 9, None), (0, 7, None), (14, 7, None)];
+        for &(a, m, expected) in tests.iter() {
+            let result = modular_inverse(&a.to_biguint().unwrap(), &m.to_biguint().unwrap());
+            assert!(result == expected.map(|x| x.to_biguint().unwrap()));
+        }
     }
 
     #[test]
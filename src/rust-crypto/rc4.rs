@@ -19,6 +19,10 @@ pub struct Rc4 {
     state: [u8, ..256]
 }
 
+impl Clone for Rc4 {
+    fn clone(&self) -> Rc4 { *self }
+}
+
 impl Rc4 {
     pub fn new(key: &[u8]) -> Rc4 {
         assert!(key.len() >= 1 && key.len() <= 256);
@@ -107,6 +111,31 @@ mod test {
             assert!(result == t.output);
         }
     }
+
+    #[test]
+    fn test_clone() {
+        // The copy and the original must keep independent permutations once they diverge.
+        let key = "Key".as_bytes();
+        let input1 = Vec::from_fn(300, |i| i as u8);
+        let input2 = Vec::from_fn(300, |i| (i * 5 + 3) as u8);
+        let split = 37;
+
+        let mut rc4 = Rc4::new(key);
+        let mut out1 = Vec::from_elem(input1.len(), 0u8);
+        rc4.process(input1.slice_to(split), out1.mut_slice_to(split));
+        let mut copy = rc4.clone();
+        let mut out2 = out1.clone();
+        rc4.process(input1.slice_from(split), out1.mut_slice_from(split));
+        copy.process(input2.slice_from(split), out2.mut_slice_from(split));
+
+        let mut expected = Vec::from_elem(input1.len(), 0u8);
+        Rc4::new(key).process(input1.as_slice(), expected.as_mut_slice());
+        assert!(out1 == expected);
+        let mut input = Vec::from_slice(input1.slice_to(split));
+        input.push_all(input2.slice_from(split));
+        Rc4::new(key).process(input.as_slice(), expected.as_mut_slice());
+        assert!(out2 == expected);
+    }
 }
 
 #[cfg(test)]
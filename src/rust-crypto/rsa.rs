@@ -0,0 +1,1038 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * An implementation of RSA as specified in PKCS #1 v2.2 (RFC 8017): key pair generation,
+ * RSAES-OAEP and RSAES-PKCS1-v1_5 encryption and RSASSA-PSS and RSASSA-PKCS1-v1_5 signatures.
+ *
+ * Private key operations use the Chinese Remainder Theorem and are blinded with a fresh random
+ * value each time, so they need a random number generator, which should be cryptographically
+ * secure.
+ */
+
+use std::num::One;
+use std::rand::Rng;
+
+use num::bigint::{BigUint, ToBigUint, RandBigInt};
+use num::integer::Integer;
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be, write_u32_be};
+use digest::Digest;
+use md5::Md5;
+use montgomery::Montgomery;
+use prime_rng::{generate_strong_prime_min, modular_exp, modular_inverse};
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512Trunc224, Sha512Trunc256};
+use util::fixed_time_eq;
+
+/// The errors that can occur during RSA operations
+#[deriving(PartialEq, Show)]
+pub enum RsaError {
+    /// The message is too long to be encrypted with the key
+    MessageTooLong,
+    /// The key is too short for the digest and salt length used for a signature
+    KeyTooShort,
+    /// The ciphertext could not be decrypted. The reason is deliberately not reported, since that
+    /// would help an attacker.
+    DecryptionError,
+    /// The components of a private key are not consistent with each other
+    InvalidKey
+}
+
+/// An RSA public key
+#[deriving(Clone, PartialEq)]
+pub struct RsaPublicKey {
+    n: BigUint,
+    e: BigUint
+}
+
+/// An RSA private key. The CRT values are stored along with the private exponent.
+#[deriving(Clone, PartialEq)]
+pub struct RsaPrivateKey {
+    n: BigUint,
+    e: BigUint,
+    d: BigUint,
+    p: BigUint,
+    q: BigUint,
+    dp: BigUint,
+    dq: BigUint,
+    qinv: BigUint,
+    // Used for the exponentiations with the secret exponents, which must run in constant time
+    mont_p: Montgomery,
+    mont_q: Montgomery
+}
+
+/**
+ * Digests that can be used for RSASSA-PKCS1-v1_5 signatures, which identify the digest algorithm
+ * in the signature.
+ */
+pub trait Pkcs1v15Digest: Digest {
+    /// The DER encoding of the DigestInfo structure that precedes the hash in the signature
+    fn digest_info_prefix(&self) -> &'static [u8];
+}
+
+// The DigestInfo prefixes from RFC 8017 section 9.2.
+static MD5_PREFIX: [u8, ..18] = [
+    0x30, 0x20, 0x30, 0x0c, 0x06, 0x08, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x05, 0x05, 0x00,
+    0x04, 0x10 ];
+static SHA1_PREFIX: [u8, ..15] = [
+    0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14 ];
+static SHA224_PREFIX: [u8, ..19] = [
+    0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x04, 0x05,
+    0x00, 0x04, 0x1c ];
+static SHA256_PREFIX: [u8, ..19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05,
+    0x00, 0x04, 0x20 ];
+static SHA384_PREFIX: [u8, ..19] = [
+    0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05,
+    0x00, 0x04, 0x30 ];
+static SHA512_PREFIX: [u8, ..19] = [
+    0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05,
+    0x00, 0x04, 0x40 ];
+static SHA512_TRUNC_224_PREFIX: [u8, ..19] = [
+    0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x05, 0x05,
+    0x00, 0x04, 0x1c ];
+static SHA512_TRUNC_256_PREFIX: [u8, ..19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x06, 0x05,
+    0x00, 0x04, 0x20 ];
+
+macro_rules! impl_pkcs1v15_digest(
+    ($digest:ident, $prefix:ident) => (
+        impl Pkcs1v15Digest for $digest {
+            fn digest_info_prefix(&self) -> &'static [u8] { $prefix.as_slice() }
+        }
+    )
+)
+
+impl_pkcs1v15_digest!(Md5, MD5_PREFIX)
+impl_pkcs1v15_digest!(Sha1, SHA1_PREFIX)
+impl_pkcs1v15_digest!(Sha224, SHA224_PREFIX)
+impl_pkcs1v15_digest!(Sha256, SHA256_PREFIX)
+impl_pkcs1v15_digest!(Sha384, SHA384_PREFIX)
+impl_pkcs1v15_digest!(Sha512, SHA512_PREFIX)
+impl_pkcs1v15_digest!(Sha512Trunc224, SHA512_TRUNC_224_PREFIX)
+impl_pkcs1v15_digest!(Sha512Trunc256, SHA512_TRUNC_256_PREFIX)
+
+// The number of Miller-Rabin rounds used when generating primes
+static PRIME_SECURITY: uint = 40;
+
+// The following functions are used to check the padding of decrypted messages without branching on
+// secret data. Masks are 0xff for true and 0 for false.
+
+fn ct_eq(a: u8, b: u8) -> u8 {
+    let x = (a ^ b) as u32;
+    return ((x - 1) >> 8) as u8;
+}
+
+fn ct_select(mask: u8, a: uint, b: uint) -> uint {
+    let m = 0u - (mask & 1) as uint;
+    return (a & m) | (b & !m);
+}
+
+fn bool_mask(x: bool) -> u8 {
+    return 0u8 - (x as u8);
+}
+
+fn hash<D: Digest>(digest: &mut D, input: &[u8], out: &mut [u8]) {
+    digest.reset();
+    digest.input(input);
+    digest.result(out);
+}
+
+// Xor the output of the MGF1 mask generation function (RFC 8017 appendix B.2.1) into out.
+fn mgf1_xor<D: Digest>(digest: &mut D, seed: &[u8], out: &mut [u8]) {
+    let mut h = Vec::from_elem(digest.output_bytes(), 0u8);
+    let mut counter = [0u8, ..4];
+    for (i, chunk) in out.mut_chunks(h.len()).enumerate() {
+        write_u32_be(counter, i as u32);
+        digest.reset();
+        digest.input(seed);
+        digest.input(counter);
+        digest.result(h.as_mut_slice());
+        for (x, y) in chunk.mut_iter().zip(h.iter()) {
+            *x ^= *y;
+        }
+    }
+}
+
+// Generate a prime p of bits bits, where gcd(e, p - 1) = 1. p is at least sqrt(2) * 2^(bits - 1),
+// so that the product of two such primes is exactly 2 * bits long.
+fn generate_prime<R: Rng>(rng: &mut R, bits: uint, e: &BigUint) -> BigUint {
+    let one: BigUint = One::one();
+    let min = 0xb505u.to_biguint().unwrap() << (bits - 16);
+    loop {
+        let p = generate_strong_prime_min(rng, &min, bits, PRIME_SECURITY);
+        if e.gcd(&(p - one)) == one {
+            return p;
+        }
+    }
+}
+
+impl RsaPublicKey {
+    /// Create a public key from the modulus and public exponent
+    pub fn new(n: BigUint, e: BigUint) -> RsaPublicKey {
+        RsaPublicKey {
+            n: n,
+            e: e
+        }
+    }
+
+    /// The modulus
+    pub fn n<'a>(&'a self) -> &'a BigUint { &self.n }
+
+    /// The public exponent
+    pub fn e<'a>(&'a self) -> &'a BigUint { &self.e }
+
+    /// The size of the modulus, and so of ciphertexts and signatures, in bytes
+    pub fn size(&self) -> uint { (self.n.bits() + 7) / 8 }
+
+    fn public_op(&self, m: &BigUint) -> BigUint {
+        return modular_exp(m, &self.e, &self.n);
+    }
+
+    /**
+     * Encrypt a message using RSAES-PKCS1-v1_5. The message may be at most size() - 11 bytes long.
+     * New protocols should use OAEP instead.
+     */
+    pub fn encrypt_pkcs1v15<R: Rng>(&self, rng: &mut R, msg: &[u8])
+            -> Result<Vec<u8>, RsaError> {
+        let k = self.size();
+        if msg.len() + 11 > k {
+            return Err(MessageTooLong);
+        }
+
+        // EM = 0x00 || 0x02 || PS || 0x00 || M, where PS consists of non-zero random bytes
+        let mut em = Vec::from_elem(k, 0u8);
+        *em.get_mut(1) = 2;
+        for x in em.mut_slice(2, k - msg.len() - 1).mut_iter() {
+            while *x == 0 {
+                *x = rng.gen();
+            }
+        }
+        em.mut_slice_from(k - msg.len()).copy_from(msg);
+
+        let c = self.public_op(&biguint_from_bytes_be(em.as_slice()));
+        return Ok(biguint_to_bytes_be(&c, k));
+    }
+
+    /**
+     * Encrypt a message using RSAES-OAEP with MGF1. The message may be at most
+     * size() - 2 * digest.output_bytes() - 2 bytes long.
+     *
+     * # Arguments
+     *
+     * * digest - The digest used for hashing the label and by MGF1
+     * * rng - The random number generator used to generate the seed
+     * * msg - The message
+     * * label - The label associated with the message, which is usually empty
+     */
+    pub fn encrypt_oaep<D: Digest, R: Rng>(
+            &self,
+            digest: &mut D,
+            rng: &mut R,
+            msg: &[u8],
+            label: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let k = self.size();
+        let hlen = digest.output_bytes();
+        if msg.len() + 2 * hlen + 2 > k {
+            return Err(MessageTooLong);
+        }
+
+        // DB = lHash || PS || 0x01 || M, where PS consists of zeros
+        let mut db = Vec::from_elem(k - hlen - 1, 0u8);
+        hash(digest, label, db.mut_slice_to(hlen));
+        let db_len = db.len();
+        *db.get_mut(db_len - msg.len() - 1) = 1;
+        db.mut_slice_from(db_len - msg.len()).copy_from(msg);
+
+        let mut seed = Vec::from_elem(hlen, 0u8);
+        rng.fill_bytes(seed.as_mut_slice());
+        mgf1_xor(digest, seed.as_slice(), db.as_mut_slice());
+        mgf1_xor(digest, db.as_slice(), seed.as_mut_slice());
+
+        // EM = 0x00 || maskedSeed || maskedDB
+        let mut em = Vec::with_capacity(k);
+        em.push(0);
+        em.push_all(seed.as_slice());
+        em.push_all(db.as_slice());
+
+        let c = self.public_op(&biguint_from_bytes_be(em.as_slice()));
+        return Ok(biguint_to_bytes_be(&c, k));
+    }
+
+    // Apply the public key to a signature, returning None if the signature isn't valid input.
+    fn open_signature(&self, sig: &[u8], len: uint) -> Option<Vec<u8>> {
+        if sig.len() != self.size() {
+            return None;
+        }
+        let s = biguint_from_bytes_be(sig);
+        if s >= self.n {
+            return None;
+        }
+        let m = self.public_op(&s);
+        if m.bits() > len * 8 {
+            return None;
+        }
+        return Some(biguint_to_bytes_be(&m, len));
+    }
+
+    /// Verify an RSASSA-PKCS1-v1_5 signature of a message
+    pub fn verify_pkcs1v15<D: Pkcs1v15Digest>(&self, digest: &mut D, msg: &[u8], sig: &[u8])
+            -> bool {
+        let em = match self.open_signature(sig, self.size()) {
+            Some(x) => x,
+            None => return false
+        };
+        return match emsa_pkcs1v15_encode(digest, msg, self.size()) {
+            Ok(expected) => fixed_time_eq(em.as_slice(), expected.as_slice()),
+            Err(_) => false
+        };
+    }
+
+    /**
+     * Verify an RSASSA-PSS signature of a message, which uses MGF1 with the same digest as the
+     * message.
+     *
+     * # Arguments
+     *
+     * * digest - The digest used to hash the message and by MGF1
+     * * msg - The message
+     * * sig - The signature
+     * * salt_len - The length of the salt, which has to be agreed on beforehand
+     */
+    pub fn verify_pss<D: Digest>(&self, digest: &mut D, msg: &[u8], sig: &[u8], salt_len: uint)
+            -> bool {
+        let em_bits = self.n.bits() - 1;
+        let em_len = (em_bits + 7) / 8;
+        let hlen = digest.output_bytes();
+        if em_len < hlen + salt_len + 2 {
+            return false;
+        }
+
+        let mut em = match self.open_signature(sig, em_len) {
+            Some(x) => x,
+            None => return false
+        };
+
+        if em[em_len - 1] != 0xbc {
+            return false;
+        }
+        let top_mask = 0xffu8 >> (8 * em_len - em_bits);
+        if em[0] & !top_mask != 0 {
+            return false;
+        }
+
+        let db_len = em_len - hlen - 1;
+        let h = Vec::from_slice(em.slice(db_len, db_len + hlen));
+        mgf1_xor(digest, h.as_slice(), em.mut_slice_to(db_len));
+        *em.get_mut(0) &= top_mask;
+
+        // DB = PS || 0x01 || salt, where PS consists of zeros
+        let ps_len = db_len - salt_len - 1;
+        if em.slice_to(ps_len).iter().any(|x| *x != 0) || em[ps_len] != 1 {
+            return false;
+        }
+
+        let mut expected = Vec::from_elem(hlen, 0u8);
+        pss_hash(digest, msg, em.slice(db_len - salt_len, db_len), expected.as_mut_slice());
+        return fixed_time_eq(h.as_slice(), expected.as_slice());
+    }
+}
+
+// Build the EMSA-PKCS1-v1_5 encoding of the hash of msg:
+// EM = 0x00 || 0x01 || PS || 0x00 || DigestInfo, where PS consists of 0xff bytes
+fn emsa_pkcs1v15_encode<D: Pkcs1v15Digest>(digest: &mut D, msg: &[u8], em_len: uint)
+        -> Result<Vec<u8>, RsaError> {
+    let prefix = digest.digest_info_prefix();
+    let t_len = prefix.len() + digest.output_bytes();
+    if em_len < t_len + 11 {
+        return Err(KeyTooShort);
+    }
+
+    let mut em = Vec::from_elem(em_len, 0xffu8);
+    *em.get_mut(0) = 0;
+    *em.get_mut(1) = 1;
+    *em.get_mut(em_len - t_len - 1) = 0;
+    em.mut_slice(em_len - t_len, em_len - t_len + prefix.len()).copy_from(prefix);
+    hash(digest, msg, em.mut_slice_from(em_len - t_len + prefix.len()));
+    return Ok(em);
+}
+
+// Compute H = Hash(0x00 x 8 || Hash(msg) || salt) for PSS
+fn pss_hash<D: Digest>(digest: &mut D, msg: &[u8], salt: &[u8], out: &mut [u8]) {
+    let hlen = digest.output_bytes();
+    let mut m = Vec::from_elem(8 + hlen, 0u8);
+    hash(digest, msg, m.mut_slice_from(8));
+    m.push_all(salt);
+    hash(digest, m.as_slice(), out);
+}
+
+impl RsaPrivateKey {
+    /**
+     * Generate a new key pair with a modulus of exactly the given number of bits, which must be
+     * even and at least 256. The primes are strong primes generated with Gordon's algorithm. The
+     * public exponent must be odd and at least 3 - 65537 is the usual choice.
+     */
+    pub fn generate<R: Rng>(rng: &mut R, bits: uint, e: uint) -> RsaPrivateKey {
+        assert!(bits >= 256 && bits % 2 == 0);
+        assert!(e >= 3 && e % 2 == 1);
+
+        let one: BigUint = One::one();
+        let e = e.to_biguint().unwrap();
+        loop {
+            let p = generate_prime(rng, bits / 2, &e);
+            let q = generate_prime(rng, bits / 2, &e);
+            if p == q {
+                continue;
+            }
+
+            // d is computed modulo lcm(p - 1, q - 1), as FIPS 186-4 requires.
+            let lambda = (p - one).lcm(&(q - one));
+            let d = modular_inverse(&e, &lambda).unwrap();
+            return RsaPrivateKey::from_primes(p, q, e, d);
+        }
+    }
+
+    fn from_primes(p: BigUint, q: BigUint, e: BigUint, d: BigUint) -> RsaPrivateKey {
+        let one: BigUint = One::one();
+        let dp = d % (p - one);
+        let dq = d % (q - one);
+        let qinv = modular_inverse(&q, &p).unwrap();
+        let mont_p = Montgomery::new(&p);
+        let mont_q = Montgomery::new(&q);
+        RsaPrivateKey {
+            n: p * q,
+            e: e,
+            d: d,
+            p: p,
+            q: q,
+            dp: dp,
+            dq: dq,
+            qinv: qinv,
+            mont_p: mont_p,
+            mont_q: mont_q
+        }
+    }
+
+    /**
+     * Create a private key from its components. Returns InvalidKey if n is not the product of the
+     * distinct primes p and q or if d isn't the inverse of e.
+     */
+    pub fn from_components(n: BigUint, e: BigUint, d: BigUint, p: BigUint, q: BigUint)
+            -> Result<RsaPrivateKey, RsaError> {
+        let one: BigUint = One::one();
+        if p <= one || q <= one || p.is_even() || q.is_even() || p == q || p * q != n {
+            return Err(InvalidKey);
+        }
+        if (e * d) % (p - one) != one || (e * d) % (q - one) != one {
+            return Err(InvalidKey);
+        }
+        if modular_inverse(&q, &p).is_none() {
+            return Err(InvalidKey);
+        }
+        return Ok(RsaPrivateKey::from_primes(p, q, e, d));
+    }
+
+    /// Get the public key for this private key
+    pub fn public_key(&self) -> RsaPublicKey {
+        RsaPublicKey::new(self.n.clone(), self.e.clone())
+    }
+
+    /// The size of the modulus in bytes
+    pub fn size(&self) -> uint { (self.n.bits() + 7) / 8 }
+
+    // Pick a random r that is invertible modulo n and return r and its inverse.
+    fn blinding_factor<R: Rng>(&self, rng: &mut R) -> (BigUint, BigUint) {
+        let two = 2u.to_biguint().unwrap();
+        loop {
+            let r = rng.gen_biguint_range(&two, &self.n);
+            match modular_inverse(&r, &self.n) {
+                Some(r_inv) => return (r, r_inv),
+                None => {}
+            }
+        }
+    }
+
+    // Compute c^d mod n using the CRT. The input is blinded with a random value r by multiplying it
+    // with r^e first, so the time taken doesn't depend on values an attacker can choose.
+    fn private_op<R: Rng>(&self, rng: &mut R, c: &BigUint) -> BigUint {
+        let (r, r_inv) = self.blinding_factor(rng);
+        let blinded = (*c * modular_exp(&r, &self.e, &self.n)) % self.n;
+
+        let m1 = self.mont_p.modular_exp(&blinded, &self.dp);
+        let m2 = self.mont_q.modular_exp(&blinded, &self.dq);
+        let h = (self.qinv * (m1 + self.p - m2 % self.p)) % self.p;
+        let m = ((m2 + h * self.q) * r_inv) % self.n;
+
+        // A fault during the CRT computation would leak the factorization of n in the result, so
+        // the result is checked.
+        if modular_exp(&m, &self.e, &self.n) != *c {
+            fail!("RSA private key operation produced an incorrect result.");
+        }
+        return m;
+    }
+
+    // Apply the private key to a ciphertext, returning the encoded message.
+    fn decrypt_raw<R: Rng>(&self, rng: &mut R, ct: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let k = self.size();
+        if ct.len() != k {
+            return Err(DecryptionError);
+        }
+        let c = biguint_from_bytes_be(ct);
+        if c >= self.n {
+            return Err(DecryptionError);
+        }
+        let m = self.private_op(rng, &c);
+        return Ok(biguint_to_bytes_be(&m, k));
+    }
+
+    /**
+     * Decrypt a message that was encrypted with RSAES-PKCS1-v1_5. Note that this padding is
+     * vulnerable to chosen ciphertext attacks if the caller reveals whether decryption succeeded.
+     */
+    pub fn decrypt_pkcs1v15<R: Rng>(&self, rng: &mut R, ct: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let em = try!(self.decrypt_raw(rng, ct));
+
+        // EM = 0x00 || 0x02 || PS || 0x00 || M, where PS is at least 8 bytes long. The padding is
+        // checked without branching on its contents.
+        let mut good = ct_eq(em[0], 0) & ct_eq(em[1], 2);
+        let mut looking = 0xffu8;
+        let mut index = 0u;
+        for i in range(2, em.len()) {
+            let zero = ct_eq(em[i], 0);
+            index = ct_select(looking & zero, i, index);
+            looking &= !zero;
+        }
+        good &= !looking;
+        good &= bool_mask(index >= 10);
+
+        if good == 0 {
+            return Err(DecryptionError);
+        }
+        return Ok(Vec::from_slice(em.slice_from(index + 1)));
+    }
+
+    /**
+     * Decrypt a message that was encrypted with RSAES-OAEP. The digest and label must be the same
+     * as the ones used for encryption.
+     */
+    pub fn decrypt_oaep<D: Digest, R: Rng>(
+            &self,
+            digest: &mut D,
+            rng: &mut R,
+            ct: &[u8],
+            label: &[u8]) -> Result<Vec<u8>, RsaError> {
+        let k = self.size();
+        let hlen = digest.output_bytes();
+        if k < 2 * hlen + 2 {
+            return Err(DecryptionError);
+        }
+        let em = try!(self.decrypt_raw(rng, ct));
+
+        // EM = 0x00 || maskedSeed || maskedDB
+        let mut seed = Vec::from_slice(em.slice(1, hlen + 1));
+        let mut db = Vec::from_slice(em.slice_from(hlen + 1));
+        mgf1_xor(digest, db.as_slice(), seed.as_mut_slice());
+        mgf1_xor(digest, seed.as_slice(), db.as_mut_slice());
+
+        // DB = lHash || PS || 0x01 || M, where PS consists of zeros. The padding is checked
+        // without branching on its contents.
+        let mut lhash = Vec::from_elem(hlen, 0u8);
+        hash(digest, label, lhash.as_mut_slice());
+        let mut good = ct_eq(em[0], 0);
+        good &= bool_mask(fixed_time_eq(db.slice_to(hlen), lhash.as_slice()));
+        let mut looking = 0xffu8;
+        let mut index = 0u;
+        for i in range(hlen, db.len()) {
+            let one = ct_eq(db[i], 1);
+            let zero = ct_eq(db[i], 0);
+            index = ct_select(looking & one, i, index);
+            good &= !(looking & !one & !zero);
+            looking &= !one;
+        }
+        good &= !looking;
+
+        if good == 0 {
+            return Err(DecryptionError);
+        }
+        return Ok(Vec::from_slice(db.slice_from(index + 1)));
+    }
+
+    /// Sign a message using RSASSA-PKCS1-v1_5
+    pub fn sign_pkcs1v15<D: Pkcs1v15Digest, R: Rng>(&self, digest: &mut D, rng: &mut R, msg: &[u8])
+            -> Result<Vec<u8>, RsaError> {
+        let k = self.size();
+        let em = try!(emsa_pkcs1v15_encode(digest, msg, k));
+        let s = self.private_op(rng, &biguint_from_bytes_be(em.as_slice()));
+        return Ok(biguint_to_bytes_be(&s, k));
+    }
+
+    /**
+     * Sign a message using RSASSA-PSS, with MGF1 using the same digest as the message.
+     *
+     * # Arguments
+     *
+     * * digest - The digest used to hash the message and by MGF1
+     * * rng - The random number generator used for the salt and for blinding
+     * * msg - The message
+     * * salt_len - The length of the salt, which is usually the output size of the digest
+     */
+    pub fn sign_pss<D: Digest, R: Rng>(
+            &self,
+            digest: &mut D,
+            rng: &mut R,
+            msg: &[u8],
+            salt_len: uint) -> Result<Vec<u8>, RsaError> {
+        let em_bits = self.n.bits() - 1;
+        let em_len = (em_bits + 7) / 8;
+        let hlen = digest.output_bytes();
+        if em_len < hlen + salt_len + 2 {
+            return Err(KeyTooShort);
+        }
+
+        let mut salt = Vec::from_elem(salt_len, 0u8);
+        rng.fill_bytes(salt.as_mut_slice());
+
+        // EM = maskedDB || H || 0xbc, where DB = PS || 0x01 || salt and PS consists of zeros
+        let db_len = em_len - hlen - 1;
+        let mut em = Vec::from_elem(em_len, 0u8);
+        *em.get_mut(db_len - salt_len - 1) = 1;
+        em.mut_slice(db_len - salt_len, db_len).copy_from(salt.as_slice());
+        pss_hash(digest, msg, salt.as_slice(), em.mut_slice(db_len, db_len + hlen));
+        *em.get_mut(em_len - 1) = 0xbc;
+
+        let h = Vec::from_slice(em.slice(db_len, db_len + hlen));
+        mgf1_xor(digest, h.as_slice(), em.mut_slice_to(db_len));
+        *em.get_mut(0) &= 0xffu8 >> (8 * em_len - em_bits);
+
+        let k = self.size();
+        let s = self.private_op(rng, &biguint_from_bytes_be(em.as_slice()));
+        return Ok(biguint_to_bytes_be(&s, k));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rand::IsaacRng;
+
+    use num::bigint::BigUint;
+    use serialize::hex::FromHex;
+
+    use md5::Md5;
+    use rsa::{RsaPrivateKey, RsaPublicKey, Pkcs1v15Digest, MessageTooLong, KeyTooShort,
+        DecryptionError, InvalidKey};
+    use sha1::Sha1;
+    use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512Trunc256};
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        s.from_hex().unwrap()
+    }
+
+    fn big(s: &str) -> BigUint {
+        BigUint::parse_bytes(s.as_bytes(), 16).unwrap()
+    }
+
+    // The key and the vectors that use it were generated with OpenSSL. The vectors with invalid
+    // padding were built by applying the raw RSA operation to hand crafted encoded messages.
+    fn test_key() -> RsaPrivateKey {
+        RsaPrivateKey::from_components(
+            big(N),
+            big("10001"),
+            big(D),
+            big(P),
+            big(Q)).unwrap()
+    }
+
+    static N: &'static str = "be520fedc604c32ef2e80f173d4e961f37afa54153d63cba83dd68ab8f50d4cd8225ae3cceb63ffd54317bafb39fa504fe6eab0c0b2b4bf766b53b7b47e5c9ee970a72f67a9015b0212a0a8203e6836b22caafdbea7aee7045efb5effc87a6649c1ecf38ca8cc08d9289c780186227c480ed32bac1ebc69255cbaf943450b5c1b28fdcc5152bc93335230cabb1c9d9ce13844d2642325d86d3c3b2fa6a31ef05e82bc635c57198eabda5e411ec4e27a788a76869b7b11b2b44c1edfa6b77a50473ba0709415841174822d02a91b5d4a3ea76e51d486bfe4c5af2ab81d686cd2ae05f2a27d28ec2934ecee0944e7b091a215b8b712a0a8c846eabc1f91d18b83d";
+    static D: &'static str = "ff18ba0657d22fac26245580837414febd8278b11a264b8fd45fae2f0611af5525dfa103702ebd910336058b23eabfcd49c61c63858a55c5c3acf78fee76af8b5522c9a505f29ff4a1a9dac66f63f1e7bae28cda40183605d6e27902b5813cce18a9a87cd3ebda7692b68a3e6fc5b6229fe1f9dfc93d6068e9b14778bc067d16504a9c38ae3ec0a9445c8dc7596956342f42b65419bbb4895193c7e0fceeb7cf87a9b23f2c12098cad9e1ca18b695d6e6922c4fef696eed5488fd763bcfcd54320562e8db24b6ba4d82ade0cca19dc5722deeee7d0c7f92fe34f8f0364b5c594b07a348bbe8d4096f7817c28ad4fcb20e030de626e76a6ea867019b7bd1f283";
+    static P: &'static str = "fe9a50b0a461b1f23928005ffba14e5e8fe65c4ee12c97b6b1ea0d58cfc6aa0e6d1d48284d5b9e54760f9c5e8d66eaff957fe3a2c4a299615881969e4ac03f668960f66d50e31519914c9cac5e0cc200acded1b5b11a674415ec915db5b0c6b10e4736bb46ea8c45c61f74ddcf69853ff5812031707bde61d0c1f56a70507f53";
+    static Q: &'static str = "bf5d70478bddeb4668e17f397372322e020de15b32d05f48effd367844ad60102c1450c00bfd2be697a9f56ad6e6de8558fefcf537d521453612b90d67d4da1e7c60b71a016f635ab0513a8ceba7680f0a7c9935af01bb7d19bf027be36e25288173c58e13ff2c02485213209a8e034179bdd6f1b162e941d3b71cf22536482f";
+
+    static MSG: &'static str = "54657374206d65737361676520666f7220525341";
+
+    #[test]
+    fn test_from_components() {
+        let q_plus_one = big(Q) + big("1");
+        assert!(RsaPrivateKey::from_components(big(N), big("10001"), big(D), big(P), q_plus_one) ==
+            Err(InvalidKey));
+        assert!(RsaPrivateKey::from_components(big(N), big("10003"), big(D), big(P), big(Q)) ==
+            Err(InvalidKey));
+        assert!(RsaPrivateKey::from_components(big(N), big("10001"), big(D), big(P), big(P)) ==
+            Err(InvalidKey));
+        assert!(test_key().public_key() == RsaPublicKey::new(big(N), big("10001")));
+    }
+
+    fn check_pkcs1v15_sig<H: Pkcs1v15Digest>(digest: &mut H, expected: &str) {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = test_key();
+        let msg = from_hex(MSG);
+        let expected = from_hex(expected);
+
+        // The signatures are deterministic, so they must match exactly
+        let sig = key.sign_pkcs1v15(digest, &mut rng, msg.as_slice()).unwrap();
+        assert!(sig == expected);
+        assert!(key.public_key().verify_pkcs1v15(digest, msg.as_slice(), sig.as_slice()));
+
+        let mut bad_msg = msg.clone();
+        *bad_msg.get_mut(0) ^= 1;
+        assert!(!key.public_key().verify_pkcs1v15(digest, bad_msg.as_slice(), sig.as_slice()));
+        let mut bad_sig = sig.clone();
+        *bad_sig.get_mut(100) ^= 1;
+        assert!(!key.public_key().verify_pkcs1v15(digest, msg.as_slice(), bad_sig.as_slice()));
+        assert!(!key.public_key().verify_pkcs1v15(digest, msg.as_slice(), sig.slice_from(1)));
+    }
+
+    #[test]
+    fn test_pkcs1v15_sign() {
+        check_pkcs1v15_sig(&mut Md5::new(), "4c5349ae8010a1b7ccb43d1c64ed43889932a294e77078819b50fd23ea443d30f32bfa89e30eae5b3393c65049d5821afbd8063d0055e13855f0295218e39720d00b5b526e4e7783685f1b8f4854d298253d05fda8847c52b5d8a032a0f865bc30db0d03db229f4c5cc3b22852d482b8b7ff8eec7cf6d6ccffa3cac12e76d3e6eef70c77a10d682d7b15f5fadd1630a405a75b04f6d6784a5db388f0180c145b240dbf677bad4d98812b3af36afd9bec3b171cad25b8b70d41abd4b6f1aef6b03fb80fcd952a18bb90a9754fd67c89a63a677cd3654697bc869dc9a2b9d0d82bde2a74fa91ee324da0cb899592eda7de22c33add138ffff8570a70802782e01c");
+        check_pkcs1v15_sig(&mut Sha1::new(), "1e18d967ae823940bd8a87f2c978ce150a9ceff390e9e008ee2c6bd031f78b6cd6da15ae3ba58ec164bd3e12ee1273b730a014b43762d1117cd716025b694ff4663d7272c92984e5257d05b456de063beb0eb1047e6ce2e7a3a7d1fca809a5af1fd88c11d1fa0312641867972f4a9199c93a65bd3805bde6b3024505db3706b6bd3734c4054c5938ec636b833b5f2f4728c736bc33a7658bff1c0b04ba4f711452e7c72833e7de4309580bc8ba0f1dadd4361381da5f5048bda32eee062b52aa1169329e6c615f2bc0b2fc4717bf313e8a18fc433c6a9b217c42106dd2d6f60993d4192eb2d6d8a9fa35932147824787835c379e278d5c9d681108f01ee271a4");
+        check_pkcs1v15_sig(&mut Sha224::new(), "0a3089e246fd5b8e6607c5f543d539f828b8c9c9e7ef579c4dae9fe7c58a9c6e0581a9e46fa05dcea08bb8610f4c121a1c74355dd88c5df52fb6714306475a2b5b9951f173db2589d04249cb766d75f3a86da97e21251bd21721ab2cd43389719c4ff71bd29c38e7eba234d06c5aa77338a0138a0c6ebad6e8bba5ea5de1b1060dc4e290e1160f9a605495788eb26a08676db54f6a5c6403f779e2dfb8c890e8e81b83dd79c4d10b626e1227e79beb8846cfa94878bb65401499fe62f21be3d5c87faf028d909d07a6ce124a91330401174f430712955366ac78f1237f1a6d45c38317ed32485e039b50cb53217ae2aa6d665c5a186f72b734acf74962b0e52b");
+        check_pkcs1v15_sig(&mut Sha256::new(), "13e68e61739c65b3915288b98cca21c4ded8d5a638497b917c7e75a6d3ec603a050dbfb253c95a27b9f2929f87fa347f622cc867a6e46f9b487affd0034bffac56f1124ea6a4dc899181e81da3d2696f1f756c8f87385aafe4a47ebc58ace2405c9a11b28392d15ba12e393316ccf72d3043daa25f4e25dbb4b5216b8ab50169542882f8b2e7262b82107250ec88d3f36f6d02455c9b7a1a3d9a44dfa89210b76e5fc8cec4f40654abaa15034b0de290f6d3e704cc5266ca6584f6d517d4d517224f0486609d6d7565a8665cdcefe8a7195b57f7dfb2063d9a0f6f8cf81007ff2cb5f46d280c5a0c1759e7854d73fcd2094610010dc457da6b20179ff844af87");
+        check_pkcs1v15_sig(&mut Sha384::new(), "6818f359fa3b10ac5f973733a12c5fc0b7d89ea3c86e4267e5ed0004e8e28ba0e9fa1c5009a9a2c978278d15182272652aa06687c6e9a81ff3ea2b0a5a3b064902f844a91d96464f209e2fffb0852e051b58af8382695b4f02355e9d300e2103f7681473a5c918dd0729c8b20db13da2f8049f28a08b30c997ff678dbfad5fbf0d643ea870dced502547211d6b5c25154f7f51e6720096218d26b69f0b929a18eeba7f69e5f15d46171587a7682e78e2d87f67fc870f612ebd17f0cb35b9060cccbe8394ba2c90c13c31c74d092edb626c35fed0282a4afa1359422eaa6b274edf9b852a2fb1274480fe63d5b0a14b28d1d3cbc6563c4b296ef0cb45044e36bb");
+        check_pkcs1v15_sig(&mut Sha512::new(), "65e0a3b78da17d95a6079e7497434c3ace6a3938b8207a6f5301cf3aa3952773ee299a197d63ce5c53a0286571bd7900772250e33178dc4efbb94835008b60beb6cbc6844e50de15e40b57c1aac5d26f991727061c3e8666cb4860e395361b732a93aa3e719b37ee7f616118851549bd62893197ae1dcc09869400e2789e3abe20356eee67ecf00d3a338a2c40966fdc892cff3d6e2d7664f0569355bc5f18b1e0a74c7fb87ee05574b970e7403cee79e8cbdc04aa5be44255fe360c21bcdccf10f18c844e0c1c095fbfc31fd944f87ee74b2a99e940964475be1494c57bb5965c398bf66c201bfb01867eb5bf597a76892850901b784d2fc7e8a2633b18a7a3");
+        check_pkcs1v15_sig(&mut Sha512Trunc256::new(), "9d734e479b945267b6ead5ebe853cffe145bde1031a705f7497708f4690c8dc3f178bf88ba49c60ea45fdb7fe9eafff77b63193c2151e7d45c7d9794d0c6afe41e491abffe9ab11b08a77114b71cd46151d20f156af485c477549937dc7f3f2042f2cee6dd1be94bb736c3be9b63adaa9615289d1e9f4a33f7940ee8b190702a0fdf64de7996f07aa3471b2dfa18e14c2bf7632de9e1c7baee97bd9c80868b5adb06db2b2d4480b2f15e45978287b01699e039a09424100fea490995210416b603b356ccab70d864b43b7f3e5a32c19689b1e930bc2927cdfa4e00aada3eef8cfd6db402192624e7c2d83018f6f64a50e721b03a6bf1a382ce8ed7517aa3dcf8");
+    }
+
+    #[test]
+    fn test_pkcs1v15_verify_invalid() {
+        let key = test_key().public_key();
+        let msg = from_hex(MSG);
+        let invalid = [
+            // A padding byte that isn't 0xff
+            "a8ee17f28ef5ea9d42d8a0a6e10874c65747e76342b0ea1c2ff6244b16067446cd78c9aa36731547b954194efe2c202b33922670c03e9bc7c7572b1602cc3dd858340fc7409c01cd1bdbee889ee5aa1330c15346023fcdfec9b6d9f40aecc2405fc944aeb891031437051c8dd363bc981f97f543e2a746842c7ba56e856535f105a451aa926ea1c1f7f799b45297a12e5a9cf6a7dbe2bf6dd87cbbf349bd5bfdbf16af76df737bbba9e646d782859196e07e4569b3eb829632a2049efa74b104f02de3f2548cb7c8faefa56f400613922b8ad059312c28c0e2a651dddce38f79bae5a922dd822e76765bedf7efdf98b6fa32944e08ffdd78ceb0aa631b3668c2",
+            // The DigestInfo without the NULL parameters
+            "1bb859a30dd340b9a55d3cedbc40914fbf3ad29b0e4da4f30929a64c4017bcecb9504a595fb082767965d193286e19101a8044c024f6e0ffa5e75a7975e13c3c570249a07188487a9cb84f44cb06e40f3fa29b6ef4105c05b7ef4bcce52385df7cfcaef3d6894f5f87e70611487df5da6ee1a6f0194552f4811071bd4e2800730cc22e7663f6637901cf23727529057c74cc6d2856afd48885896987e136daa9d228b68ed298b4222db74187ab509519a53c0cf851edcbe7ee0254875ddcd15217c1915b32813933c104e3aa9e542565c85cca871e54ed557824cc956d49526a5382be1d5203cdaa00e945180fd271106dbbb82c302ef785a2d7ee6de7010740",
+            // A trailing zero byte after the hash
+            "9af5fdc17c197063aab54b73edccc45de5988b5b8f62ca55d50f9f00d866890669cc6c4ddc095f60db8fd83accb6acb10f73fcf388149423c713e351e5f6af8313581527b224e7eb10ad0508a825a75c5bb3b8c6401a004c8ef5bd8bdaa82f27e15fb9b4bb25f181946cf1c3b8ce290df6ff1d586f377e9a1513f0acbdfeb3a617cbabaf4c746d400f399eb509c0e5b86417f567facea68d720cf410e2b97270e32142c58cc518746d9f79355d0af724a3977fb50d3eb6bad7883cb8d7a63df76f731643414d9a3efc42b3b2999f7297234e14cefb6444193fd7922417ab7f123d1f5eea82b08ceee3386754a3a84a2a8c073dc205114a08614f8db5b749f012",
+            // Block type 2 instead of 1
+            "4f9832d0062613535eb8707377c221051d9b9ea2b70c71771c8a7a7f314e6fcc18834899b8461dfdd710b40d7374cf1d016751a1ca8f2cb1edc2c91dc7a39e0bb1e1842a40bbee2188e3c92aab8f984a9d7075b1af5e8030e078cf2197720ac0380bf8f0571db60880d352882cfd9c8900d227fed3d8210e42916cf21c09e61de6253db5820c039afac905e7e0f712bed0423b3a2fe2feb0b5ef10c4b7468d6d7fd485a41a1aeed8b6e005163cbec60860ff7f657f27fb9ef9b474af9be1f22f58ef1d4f7ec2d5dbbab0794d637beef9b15e0231566714b397b3fdc85616864cddca8ad402dd76acff913dce19b58065ae683a4e4603a77bce11ef771e004e5f"
+        ];
+        for sig in invalid.iter() {
+            let sig = from_hex(*sig);
+            assert!(!key.verify_pkcs1v15(&mut Sha256::new(), msg.as_slice(), sig.as_slice()));
+        }
+
+        // The signature must be less than n
+        let n = from_hex(N);
+        assert!(!key.verify_pkcs1v15(&mut Sha256::new(), msg.as_slice(), n.as_slice()));
+
+        // A signature made with a different digest
+        let sig = from_hex("1e18d967ae823940bd8a87f2c978ce150a9ceff390e9e008ee2c6bd031f78b6cd6da15ae3ba58ec164bd3e12ee1273b730a014b43762d1117cd716025b694ff4663d7272c92984e5257d05b456de063beb0eb1047e6ce2e7a3a7d1fca809a5af1fd88c11d1fa0312641867972f4a9199c93a65bd3805bde6b3024505db3706b6bd3734c4054c5938ec636b833b5f2f4728c736bc33a7658bff1c0b04ba4f711452e7c72833e7de4309580bc8ba0f1dadd4361381da5f5048bda32eee062b52aa1169329e6c615f2bc0b2fc4717bf313e8a18fc433c6a9b217c42106dd2d6f60993d4192eb2d6d8a9fa35932147824787835c379e278d5c9d681108f01ee271a4");
+        assert!(!key.verify_pkcs1v15(&mut Sha256::new(), msg.as_slice(), sig.as_slice()));
+    }
+
+    #[test]
+    fn test_pss() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = test_key();
+        let public = key.public_key();
+        let msg = from_hex(MSG);
+
+        // Without a salt the signature is deterministic
+        let sig = key.sign_pss(&mut Sha256::new(), &mut rng, msg.as_slice(), 0).unwrap();
+        assert!(sig == from_hex("115584551415361494e8cab0507fbc3f0d305a033849f995bb487993e40e68deec4d9de426a5a809975f42a5cf198f33f688259d677ef2f71606e62372f2074968251a0a1f8218b7de51ee0ce57c2d068924b482f7a1f85cdc7511db850b2c6496e37ed63bed7ed07ce8bbd48b4b0d86c4812bb87040d4125d03f92f4c1b74440eb4987d9a1920a6221fc911138f09bf396b3ca7e365993944db83647f6ca0e9a12f636542c5f939a064228ed5a0b2e254a8388301e3856a610d0f6bf23db258961f3e72411fd3245102194445e83090b7effc52d24588bcc4074d5ea308bafade39d3dd5c88fcb49c45d29a54c98e444eb62098b7b4fe30de63f73a35617fc5"));
+
+        let tests = [
+            ("115584551415361494e8cab0507fbc3f0d305a033849f995bb487993e40e68deec4d9de426a5a809975f42a5cf198f33f688259d677ef2f71606e62372f2074968251a0a1f8218b7de51ee0ce57c2d068924b482f7a1f85cdc7511db850b2c6496e37ed63bed7ed07ce8bbd48b4b0d86c4812bb87040d4125d03f92f4c1b74440eb4987d9a1920a6221fc911138f09bf396b3ca7e365993944db83647f6ca0e9a12f636542c5f939a064228ed5a0b2e254a8388301e3856a610d0f6bf23db258961f3e72411fd3245102194445e83090b7effc52d24588bcc4074d5ea308bafade39d3dd5c88fcb49c45d29a54c98e444eb62098b7b4fe30de63f73a35617fc5", 0u),
+            ("4cc960fbcb5961baf948336bd87172cdd649e656824ba40b032842a5de73ac86f23cc30c7a1465bad4d4e39d0d36990695d66fdce9cf86adc649b4158de8556fa9074b67db44e58553cc99f726ea8f29997a603812097d4ff74e0f6ba16561018f159d35dea416839b7e04e1669b3971496914db3a3c1e5f869a1668e7ae9cfd1c95e62fb062bcbb9c4ffb041e74fa1be0e9501f15c122149f2f3500fd67e15573122d20e0203638d757e087493533a595210a9ef69ef5d16874fd1b51ee68ba939624d4b5a57e39b1555a7691bc1d26165e46511f412813257150743b30bda420a178c836a2e8464d0db47e44047c455e35a45818b4cec2433656c6f45b11d5", 32u)
+        ];
+        for &(sig, salt_len) in tests.iter() {
+            let sig = from_hex(sig);
+            assert!(public.verify_pss(
+                &mut Sha256::new(), msg.as_slice(), sig.as_slice(), salt_len));
+            assert!(!public.verify_pss(
+                &mut Sha256::new(), msg.as_slice(), sig.as_slice(), salt_len + 1));
+            assert!(!public.verify_pss(&mut Sha1::new(), msg.as_slice(), sig.as_slice(), salt_len));
+            let mut bad_sig = sig.clone();
+            *bad_sig.get_mut(10) ^= 0x80;
+            assert!(!public.verify_pss(
+                &mut Sha256::new(), msg.as_slice(), bad_sig.as_slice(), salt_len));
+        }
+
+        let sig = from_hex("9b9f9ab7e7cf4f4c4f7b265a66a20130745af5e5f4fcca6f1e4b83e7cf0eab3d0b5fd88823fdd575ee880dcb9d9e64144f855a22787aedad45ae9a2c2629ee381d7633425e750b69134610a50de19dd43c61b588ed1a34725efffcfdeed6752fd2dece13847579d002e5422b32b50b1772cf164be280ebb8ff4034d8d9a01df84f24da5514fdb3e535be51b7252f3e3e515564c00b98d690c4bdffb82b0e3a956c4fb8d040d99ed1b7a185a0269a9396760a7ef8c40e34a0c3a4570f81d28acb05e674b6281f2337f12822abfade457ff2259066d94a24a1c9d5cd1b501c4502e224351571991d2a39653f6685812cb6f488207a86554a138a277f14d79c080a");
+        assert!(public.verify_pss(&mut Sha1::new(), msg.as_slice(), sig.as_slice(), 20));
+
+        for salt_len in [0u, 20, 32, 64].iter() {
+            let salt_len = *salt_len;
+            let sig = key.sign_pss(&mut Sha256::new(), &mut rng, msg.as_slice(), salt_len).unwrap();
+            assert!(public.verify_pss(
+                &mut Sha256::new(), msg.as_slice(), sig.as_slice(), salt_len));
+            assert!(!public.verify_pss(
+                &mut Sha256::new(), msg.slice_from(1), sig.as_slice(), salt_len));
+        }
+
+        // The salt doesn't fit
+        assert_eq!(key.sign_pss(&mut Sha512::new(), &mut rng, msg.as_slice(), 191),
+            Err(KeyTooShort));
+    }
+
+    // Excerpts from SigGen15_186-3.rsp, SigVer15_186-3.rsp and SigVerPSS_186-3.rsp of the NIST
+    // CAVP FIPS 186-3 RSA test vectors:
+    // http://csrc.nist.gov/groups/STM/cavp/documents/dss/186-3rsatestvectors.zip
+    // The excerpts use SHA-256, SHA-384 and SHA-512. The other digests are only checked against the
+    // OpenSSL vectors above.
+
+    static CAVP_SIGGEN_N: &'static str = "cea80475324c1dc8347827818da58bac069d3419c614a6ea1ac6a3b510dcd72cc516954905e9fef908d45e13006adf27d467a7d83c111d1a5df15ef293771aefb920032a5bb989f8e4f5e1b05093d3f130f984c07a772a3683f4dc6fb28a96815b32123ccdd13954f19d5b8b24a103e771a34c328755c65ed64e1924ffd04d30b2142cc262f6e0048fef6dbc652f21479ea1c4b1d66d28f4d46ef7185e390cbfa2e02380582f3188bb94ebbf05d31487a09aff01fcbb4cd4bfd1f0a833b38c11813c84360bb53c7d4481031c40bad8713bb6b835cb08098ed15ba31ee4ba728a8c8e10f7294e1b4163b7aee57277bfd881a6f9d43e02c6925aa3a043fb7fb78d";
+    static CAVP_SIGGEN_E: &'static str = "260445";
+    static CAVP_SIGGEN_D: &'static str = "997634c477c1a039d44c810b2aaa3c7862b0b88d3708272e1e15f66fc9389709f8a11f3ea6a5af7effa2d01c189c50f0d5bcbe3fa272e56cfc4a4e1d388a9dcd65df8628902556c8b6bb6a641709b5a35dd2622c73d4640bfa1359d0e76e1f219f8e33eb9bd0b59ec198eb2fccaae0346bd8b401e12e3c67cb629569c185a2e0f35a2f741644c1cca5ebb139d77a89a2953fc5e30048c0e619f07c8d21d1e56b8af07193d0fdf3f49cd49f2ef3138b5138862f1470bd2d16e34a2b9e7777a6c8c8d4cb94b4e8b5d616cd5393753e7b0f31cc7da559ba8e98d888914e334773baf498ad88d9631eb5fe32e53a4145bf0ba548bf2b0a50c63f67b14e398a34b0d";
+    static CAVP_SIGGEN_P: &'static str = "f364e16ef12017ec95b192308c01e087cee619ab50a5d537cc01841dc92b30bcef0d9f2c6bbd5dc10bdf5b9f6c354a4f9f210520caa72b4f5c36b8d33f10324c55956141891e45b84b49f59ea5bfac6ffa38900aca5099afcd02f6a8257c41ce5bb2e4153832b5c22f91eb389fa2035c3cf9b3374531c483cb30ceb007259b1d";
+    static CAVP_SIGGEN_Q: &'static str = "d95c0995fabdfcbccfe63e0f3262f806869ab571e1793e97234cbb9bd4b6872a7695389955cf6ce7245345a5df8021f7d9519563afbc2667f5311fad093de2c02cd069109b630d68e3bf767f8a788a6add7ab199f2d8f6a40b7c1910d9dab52ac80d0d333aacab321a9309dc884ddd4db637a0c1115ae3c08efa683f99eb7331";
+
+    fn check_cavp_sig<H: Pkcs1v15Digest>(key: &RsaPrivateKey, digest: &mut H, msg: &str,
+            sig: &str) {
+        let mut rng = IsaacRng::new_unseeded();
+        let msg = from_hex(msg);
+        let sig = from_hex(sig);
+        assert!(key.sign_pkcs1v15(digest, &mut rng, msg.as_slice()).unwrap() == sig);
+        assert!(key.public_key().verify_pkcs1v15(digest, msg.as_slice(), sig.as_slice()));
+    }
+
+    #[test]
+    fn test_pkcs1v15_sign_cavp() {
+        let key = RsaPrivateKey::from_components(
+            big(CAVP_SIGGEN_N),
+            big(CAVP_SIGGEN_E),
+            big(CAVP_SIGGEN_D),
+            big(CAVP_SIGGEN_P),
+            big(CAVP_SIGGEN_Q)).unwrap();
+        check_cavp_sig(&key, &mut Sha256::new(),
+            "5af283b1b76ab2a695d794c23b35ca7371fc779e92ebf589e304c7f923d8cf976304c19818fcd89d6f07c8d8e08bf371068bdf28ae6ee83b2e02328af8c0e2f96e528e16f852f1fc5455e4772e288a68f159ca6bdcf902b858a1f94789b3163823e2d0717ff56689eec7d0e54d93f520d96e1eb04515abc70ae90578ff38d31b",
+            "6b8be97d9e518a2ede746ff4a7d91a84a1fc665b52f154a927650db6e7348c69f8c8881f7bcf9b1a6d3366eed30c3aed4e93c203c43f5528a45de791895747ade9c5fa5eee81427edee02082147aa311712a6ad5fb1732e93b3d6cd23ffd46a0b3caf62a8b69957cc68ae39f9993c1a779599cdda949bdaababb77f248fcfeaa44059be5459fb9b899278e929528ee130facd53372ecbc42f3e8de2998425860406440f248d817432de687112e504d734028e6c5620fa282ca07647006cf0a2ff83e19a916554cc61810c2e855305db4e5cf893a6a96767365794556ff033359084d7e38a8456e68e21155b76151314a29875feee09557161cbc654541e89e42");
+        check_cavp_sig(&key, &mut Sha384::new(),
+            "6cd59fdd3efd893d091afdc3155d354f10d6d88167427a2cf7246207e51791a6ca6200a914cd2834a9b3c79fcd59e26e457e0683bc33d49267edbdd6e5d90902696f1e7b1a4affc4ba371339868c28015ebbb73e262669866c35db974ba69e468f2583b9191d15d686cd66fb0b9e0ff0a3b4721a6dc342f14f2446b4e028595b",
+            "3974900bec3fcb081f0e5a299adf30d087aabaa633911410e87a4979bbe3fa80c3abcf221686399a49bc2f1e5ac40c35df1700e4b9cb7c805a896646573f4a570a9704d2a2e6baee4b43d916906884ad3cf283529ea265e8fcb5cc1bdf7b7dee85941e4b4fb25c1fc7b951fb129ab393cb069be271c1d954da3c43674309f1d212826fabb8e812de2d53d12597de040d32cb28c9f813159cb18c1b51f7a874cbf229cc222caeb98e35ec5e4bf5c5e22cc8528631f15117e8c2be6eac91f4070eecdd07ecc6db6c46eaa65f472f2006988efef0b51c538c6e04d7519c8e3da4b172b1e2761089ed3ad1197992ef37c168dc881c8b5f8bbfee919f7c7afd25b8fc");
+        check_cavp_sig(&key, &mut Sha512::new(),
+            "a7c309d44a57188bbd7b726b98b98ce12582228e1415864870a23961d2afb82cd5bc98bec922d5f2ac4168b056da176ef3ba91f6b699ba6acc4144868ff37f26fd06720868d12ad26ecb52572cf10416af68df03ab645a8b704857d2190ffc3f07eabe3a8e2abe34ed6159e884c4fae141d4333d5c3e0db044ff9cccd9cbd67f",
+            "148af61ed5ea8a87a08b3f403929bf8031db4fd3999b64409ba489f97a3ee5208ea4202d2ec18734f615003a51f77441085be6ac0f11810ffa2dad58f0e186d5520ac2b8a5d3966e8d2abb8074e13b50a4e7de83be10a66fdc7ca18118c5774f781212de9efebc6376fcdddc65a3b1b8f1ab31492fe478259ce719b3db587498d879a01dec96e8eabeb07ff7073f3f3eb446084955ca26329a791315a2c259d225e26b2154b2047b21faba68115bfd962e5e24ec52d7c5d231e3044cbcd8c8804855703cbaa622b15b6ef78c7421a367166f1b02576c87360593da75b7189efafd1082bd59f6857f1701f646c24d70c95273c49d5b11e6afe258821b55c1680c");
+    }
+
+    #[test]
+    fn test_pkcs1v15_verify_cavp() {
+        let n = big("c47abacc2a84d56f3614d92fd62ed36ddde459664b9301dcd1d61781cfcc026bcb2399bee7e75681a80b7bf500e2d08ceae1c42ec0b707927f2b2fe92ae852087d25f1d260cc74905ee5f9b254ed05494a9fe06732c3680992dd6f0dc634568d11542a705f83ae96d2a49763d5fbb24398edf3702bc94bc168190166492b8671de874bb9cecb058c6c8344aa8c93754d6effcd44a41ed7de0a9dcd9144437f212b18881d042d331a4618a9e630ef9bb66305e4fdf8f0391b3b2313fe549f0189ff968b92f33c266a4bc2cffc897d1937eeb9e406f5d0eaa7a14782e76af3fce98f54ed237b4a04a4159a5f6250a296a902880204e61d891c4da29f2d65f34cbb");
+        let tests = [
+            ("95123c8d1b236540b86976a11cea31f8bd4e6c54c235147d20ce722b03a6ad756fbd918c27df8ea9ce3104444c0bbe877305bc02e35535a02a58dcda306e632ad30b3dc3ce0ba97fdf46ec192965dd9cd7f4a71b02b8cba3d442646eeec4af590824ca98d74fbca934d0b6867aa1991f3040b707e806de6e66b5934f05509bea",
+             "49d2a1",
+             "51265d96f11ab338762891cb29bf3f1d2b3305107063f5f3245af376dfcc7027d39365de70a31db05e9e10eb6148cb7f6425f0c93c4fb0e2291adbd22c77656afc196858a11e1c670d9eeb592613e69eb4f3aa501730743ac4464486c7ae68fd509e896f63884e9424f69c1c5397959f1e52a368667a598a1fc90125273d9341295d2f8e1cc4969bf228c860e07a3546be2eeda1cde48ee94d062801fe666e4a7ae8cb9cd79262c017b081af874ff00453ca43e34efdb43fffb0bb42a4e2d32a5e5cc9e8546a221fe930250e5f5333e0efe58ffebf19369a3b8ae5a67f6a048bc9ef915bda25160729b508667ada84a0c27e7e26cf2abca413e5e4693f4a9405",
+             true),
+            ("f89fd2f6c45a8b5066a651410b8e534bfec0d9a36f3e2b887457afd44dd651d1ec79274db5a455f182572fceea5e9e39c3c7c5d9e599e4fe31c37c34d253b419c3e8fb6b916aef6563f87d4c37224a456e5952698ba3d01b38945d998a795bd285d69478e3131f55117284e27b441f16095dca7ce9c5b68890b09a2bfbb010a5",
+             "49d2a1",
+             "ba48538708512d45c0edcac57a9b4fb637e9721f72003c60f13f5c9a36c968cef9be8f54665418141c3d9ecc02a5bf952cfc055fb51e18705e9d8850f4e1f5a344af550de84ffd0805e27e557f6aa50d2645314c64c1c71aa6bb44faf8f29ca6578e2441d4510e36052f46551df341b2dcf43f761f08b946ca0b7081dadbb88e955e820fd7f657c4dd9f4554d167dd7c9a487ed41ced2b40068098deedc951060faf7e15b1f0f80ae67ff2ee28a238d80bf72dd71c8d95c79bc156114ece8ec837573a4b66898d45b45a5eacd0b0e41447d8fa08a367f437645e50c9920b88a16bc0880147acfb9a79de9e351b3fa00b3f4e9f182f45553dffca55e393c5eab6",
+             false),
+            ("915c5e4c16acfa0f49de43d6491f0060a944034475ba518572c08366a8d36c7f1e6afc11e5e4649757bf7b9da10a61d57f1d626847871d8a2948e551b54167c79de88d3ebd40a3e35809b996a53348f98a9918c7a7ec606896ed30c271e00c51953dd97aa6a8fe1cd423c3695c83fcf45120ec0a9cd1644642182b60e599a246",
+             "49d2a1",
+             "3d57ea5961db8fc144301ca4278f799911229d865ea3e992c7fbc4d03c6551729e26034e95dd71da312340e4051c9dd9b12f7700a821fe3b7c37785d5106350b667ac255a57c13da5842d90bcadea9e6b1f720c607d6893a2caa3c5f3c4074e914451a45380a767c291a67cac3f1cab1fbd05adc37036856a8404e7cea3654019466de449ad6e92b27254f3d25949b1b860065406455a13db7c5fe25d1af7a84cddf7792c64e16260c950d60bd86d005924148ad097c126b84947ab6e89d48f61e711d62522b6e48f16186d1339e6ab3f58c359eb24cb68043737591cd7d9390a468c0022b3b253be52f1a7fc408f84e9ffb4c34fa9e01605851d6583aa13032",
+             false),
+            ("03d2f0693517cffb2b724c1f30502c5359c051c1bcd88dc1dd54b89e6981009d275a813b2bf016b74d0f6ed0d91e62d0884785c9afd8fd1fb7e99246cd4005cdda71a39cb649197a996d8ad2d23fdfb6bb015f24ec3d7f88af64fb83b4b525eb06607d133eec834cf7d6c9ab817b4c0dda370459d9cfba05ad0c1adc86a909fe",
+             "7485b",
+             "511abd82218cab344979b2887b02600d2427f1eb12ac01d97684c2a443a9272834c3f79cded07a39dbee3770dde827a74dc994b17bfd8a26d07b239d26d58c42f79d560264c31b7e1c3dddef6d7556f228c394414f4cec561c3da2686a8eebec7702f32850809a93deeb84b2a02fcdba224d2fd9efb8e056e796f49b57d56e9f3e90d0b49b08bdee93a2e12e676fb4d4fa838c5bd88eda008f1b592a72465587be0ae17d9b156b904f44a7e04d3b58d24ad67b71b0f4c699fa51639546b62b9f83597ff03d465f1bb396ae15e92d0e92e85647d5df113e2c7518d0e3ad2e7aa7dac720c98347aa151e4f37fea081dbed350cc9c93f606b38f21a3e5de6d140d2",
+             false),
+            ("dffe42bfda886e1a73fe8a8dfcf71c9fb44deb054588a9bb9199d554aecce08f2ff88f2aa6f8a0fb675fb03c8e685c27432ca7c33c189bfd849d34fa7b2979ac1f57eca389632426bae0b98398ad60a3342557e14e96041c1bf4d90b46cf7ad1348322d28caf43c4f7e86c0924ae703c109ec50a84ea2a43df078c3015a52b28",
+             "49d2a1",
+             "8f4dd479239f2d08dc05d7d40539288b67c4d77210ecb16be76f0b1925e8b088570831e361a1ca57893135f8af64b8e2996b8d635899da4e04c68acb9b1b3813697d57da90c57f18509e0ab6705c704feb448cca5c07d258ecd884ab93f508cefdb25f2bc3061c4006099e2e33b27972c3edb0a0a33114d381c82ab506d041ff680af595ef3400a8bb6774030d2e38dd304272092bd32a553017f7bda4b998b27aa8aca12def327b1f11063a5342b0d55738183417d321c5682fc4ab64e79174216feebb989521e1e3d827647068003be34fe1d093964d28f4877c49b4065672448597a89b91919cfb55ca13836e7e6f3b3fd04f417cf1c16d9872538bf4e87a",
+             false),
+            ("cfe99788f55ec6944942bd0a187d51b80fd8bd4051bd4f07c73e614eb75a8b9f997b176b2642b5f1b1877061ba9ce142c1d2a311583f072b7cbe08ed253681191c209d7b0d438fcdddc284d93d59d6dd80e48333a921dd31c9b6834f88768f8701e01102d3e8bdf074fbe0b8c93d9951f41545ef6eeb3be35530babc079f1fb3",
+             "49d2a1",
+             "9fd6f6107e838107f906c26cb2910704599f175b6a84db485fbc30776eb7fd53bfe20c38c537b154a3e519b662bd9fdc8e3045e21f6e5ae97d0ff6a9d8632825544525d84f99f80e3ed4e69dc5e219d59ccfbb37c23c84fe3b3e6fb22f402f94e5225c6387fdf8bcdb3508f8832908fe05771521e92234348004e8fe19a8f24bebcab9f074327c88d066bc12081748d696be6135c6aea32220ea786ebd7800e6936365ff25831c28cb6c8a59237ff84f5cf89036cff188ee0f9a6195f2b1aca2e4442af8369f1b49322fa2f891b83a14a97b60c6aeafd6c2928047affda9c8d869ff5294bb5943ad14a6d64e784d126c469d51e292b9ce33e1d8371ba5f467b3",
+             false)
+        ];
+        for &(msg, e, sig, result) in tests.iter() {
+            let key = RsaPublicKey::new(n.clone(), big(e));
+            let msg = from_hex(msg);
+            let sig = from_hex(sig);
+            assert_eq!(
+                key.verify_pkcs1v15(&mut Sha256::new(), msg.as_slice(), sig.as_slice()), result);
+        }
+    }
+
+    #[test]
+    fn test_pss_verify_cavp() {
+        // The salt of these vectors is as long as the digest
+        let key = RsaPublicKey::new(
+            big("a47d04e7cacdba4ea26eca8a4c6e14563c2ce03b623b768c0d49868a57121301dbf783d82f4c055e73960e70550187d0af62ac3496f0a3d9103c2eb7919a72752fa7ce8c688d81e3aee99468887a15288afbb7acb845b7c522b5c64e678fcd3d22feb84b44272700be527d2b2025a3f83c2383bf6a39cf5b4e48b3cf2f56eef0dfff18555e31037b915248694876f3047814415164f2c660881e694b58c28038a032ad25634aad7b39171dee368e3d59bfb7299e4601d4587e68caaf8db457b75af42fc0cf1ae7caced286d77fac6cedb03ad94f1433d2c94d08e60bc1fdef0543cd2951e765b38230fdd18de5d2ca627ddc032fe05bbd2ff21e2db1c2f94d8b"),
+            big("10e43f"));
+        let tests = [
+            // Format of the EM is incorrect - hash moved to left
+            ("7518c85b67e7aef7f26bf006899faef76e076f0c6c946e5dc9c83521771a6d298a9cf5adefdb314b5a07a54d8054c22b879fff50ba552c218291033c918401fd611a7447dddad4815e0f56ded825bfe256557622a385de4b4a69e265c1efd259e2da6db19aac3fa0e5ca2d42fadb4e24c271fc078feb2be10b9afa256f228844",
+             "992d48b21bb3d2219b44e8fcc8633cf3aeb591de90f4386496ac7ecd284cb63d7dff81a50b8c4fed9f2ef737692ea6be05248ca138947b49b4e7f3cce6640e049ac2154c40f57e22fa14f97e7a9507e1dc98b206ce6ea0e180039199d1be0a15d1f5093a459e5101aaca2a23cb1f59cad2f1fb99dc956b9d4344bad2c1121d63b915004acbfc7ac60ac9a7b0b1c6812b30bfe087f7f0c7d1625f9c4f458515e11478e3604aa39d14d08bea30b01fcd6189e6f9b701d360e4714d45556b29815c8d8fa8e46e10749ba5e8d445a4c0f487e70ab5890b7ccc1651282a54e87e7db4bb2f7d4a671e71c43c55cf6486416f171d1955037474d06a71dd078767848e5d",
+             false),
+            ("e002377affb04f0fe4598de9d92d31d6c786040d5776976556a2cfc55e54a1dcb3cb1b126bd6a4bed2a184990ccea773fcc79d246553e6c64f686d21ad4152673cafec22aeb40f6a084e8a5b4991f4c64cf8a927effd0fd775e71e8329e41fdd4457b3911173187b4f09a817d79ea2397fc12dfe3d9c9a0290c8ead31b6690a6",
+             "4f9b425c2058460e4ab2f5c96384da2327fd29150f01955a76b4efe956af06dc08779a374ee4607eab61a93adc5608f4ec36e47f2a0f754e8ff839a8a19b1db1e884ea4cf348cd455069eb87afd53645b44e28a0a56808f5031da5ba9112768dfbfca44ebe63a0c0572b731d66122fb71609be1480faa4e4f75e43955159d70f081e2a32fbb19a48b9f162cf6b2fb445d2d6994bc58910a26b5943477803cdaaa1bd74b0da0a5d053d8b1dc593091db5388383c26079f344e2aea600d0e324164b450f7b9b465111b7265f3b1b063089ae7e2623fc0fda8052cf4bf3379102fbf71d7c98e8258664ceed637d20f95ff0111881e650ce61f251d9c3a629ef222d",
+             true),
+            // Message changed
+            ("a3bf44cae8aa8347fd07d84a33eec5dbbdd7b6431368887c988c4be779c5473dd8c33ec82a35f1d3dddfe55f3eed67179b87ce86a4a50088172538fe9d1b06c6ef6897eb3c8e3618cfc21353ed4343e7fceb09a2eb035441cd5c8829c79b81582dd5d69ae85c5a001bd8e98e069961342a2bee00ad2b8b91015ac5cfc1f0c2d9",
+             "877f20eed60f8ce286108a5dde9b6828b37e3fbdb08fe153e591513897440f21f81214598fba08ea077394ba8c2a44aa4f0d8f3a5fbaec3dc69b3bddfbe28397c90adf35d08ea771c7aaa31eb06413c1c62b77618af940f4c71859fa4384d29b48e5cfc941d69bf0a3804d2008e758742b8ed68754bc71d231623d181347c36833a7d7160f742a37ce7d432d748e514aa7d8156b50c532151390d086cdcf9d59f122c6d97f4ccb737289f7b00a237cb6b4aae6ba79d41ff73d019a26b59ade04c967356e2aad52f115357ffb7676f190db38dcfc98666e5b258559c8c85fa32942cbcb99d757e8847e56a1687b3302415698708191b136d923349b02fe38b6be",
+             false),
+            // Signature changed
+            ("e1c46c309b6366fb4d56ac08c9393cee9a7c95bbe7b7c0e79a3d9187c0f42bc33364c28a770da585e3fe7b4901a3ccd037dfc42aa65a3470521ddafa835ce2d16c92ac670bd4d086505e608781736dc4dd64cc5080ee19e586c8fd1d737dade5d378b32f1d5df1e8dda0e32a125024b2d53334943c18782d7e69825a580093e7",
+             "8ed1f28fd16d45d416a21554e104c006fd7868e5895e8b99831ae0938135b543610df64a8c3574d08118bfe396f9a5609a8dbda21b9a8530ff0ba90e629d6abe30d2c1b590600db971fcda80e6eaa84017e209b9bd3b641f3c81d5d27f842bec8019790ed99a0e5db4aedc1c070b047c19410cbc56e9a0ff12d8f6e5d7371b1011ecfecf7be7a74f94403590a52f95238dd69e0b5f4c1fcde97ecfdb1acc3803e59ad8b3088b2bc509e3dd12d40d875625dc8362c579176799c75e4fadcdb392c68f401f68d854e46377f084c081f9d83743039f6934722e30ef3f0226bc841d79a4eb68c5cccbb6ae0e9200444e50ff0d0953047ef955d2d39a70c3b837c5f4",
+             false),
+            // Format of the EM is incorrect - 00 on end of pad removed
+            ("925d59f953cb3ffb6d5a3a55c079cb1083997536e33d7c8aed50ed76aebcde459938f79229613200c70dde2ceddceae08c10608aab9e30ec51842f14a65e5f8f553471da3497881927ec400b4207ef3e2dfc2b7fcd318c9520b8b22f69dc8a1a8efaceb7be93cbad569e67db062362913005dcff902018ed22937fad405fe84e",
+             "62b07f6d1b8f13651d7f22ce2ce01061090029db5af7dcecfeafef20621dd9e254a0fb914f76a3d79662257489cb8122708583d30778791a77da83c7bea81140c61e4d0484806e20fb85f24d1bbf774ef2ed38809c9b14f2a58c6e8649b760baa901544522ed94bd405c77201d07c8d12864a8d1e97a4d322c29994b214fc83c2ecd5c955b9bdec424e7ca5a1325ec0aae4ab0c202b980a2187f096aaaaa5e85550ebd325799f4f30ef2ead07e79c7a475667f5965e6b50269513659ab5962a391c43cbc3a3da34c0fdd1546c40ea7e2eb5352ce6a06ce6a6385ca0ddd5d162c137836df9ea1f89cfa00c3eb1671a43bd625526f3b6ba8e48a7a2d56fb4f01c6",
+             false)
+        ];
+        for &(msg, sig, result) in tests.iter() {
+            let msg = from_hex(msg);
+            let sig = from_hex(sig);
+            assert_eq!(
+                key.verify_pss(&mut Sha256::new(), msg.as_slice(), sig.as_slice(), 32), result);
+        }
+    }
+
+    #[test]
+    fn test_pkcs1v15_decrypt() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = test_key();
+        let msg = from_hex(MSG);
+
+        let ct = from_hex("2dfa3462d6433b6364825f3c822bffbbcea52b3d536b8cdf5f0a96f54ed335efbe054fb87d25595a5d552e5b3f0f60375f2cd3dbe66719e86882326b2eda80bdd7a65f37aecb6abd3ea0cfb2ca03dc9a711fe301ed49acd2ea16f15841d6744cd6c44e855258e0408cf14db1a52c11d44b071aa4d7e5272e0d9580a9ae1a2da7bdca9ecb03fa9be7a6dbd784cd5aa82714b874145ccfec906e3c3f25fd0f6e977ae0207c0ce50458e33510049c1a7890ffc0b0d5ec7f34572ded259671a8a8dc3b8cf73c720dc3dc028b3f0c18f11a0913d5c0a39675ea66142b76a812748ee0b7ceaae1c6c86d9b9ca62a392280c2475a6739058283f033d9f0f9a906aa9042");
+        assert!(key.decrypt_pkcs1v15(&mut rng, ct.as_slice()).unwrap() == msg);
+
+        let ct = from_hex("41494810b3973db1094d6aa7b516821d4bd97379230aec9c5e4c70ea296d765180550e68b5a863e2d6f005262bb1d66cc45e10284d193ed9c335b7ba15967ca861f2ee2d514e4e571e948ad75b98ca47f0121e6195faa1f7de3827689db844f136dde821c0337765d4a70582184b9b652d4fbef5fb809c3b1178a5f3f06dc7497791df8971200b2a30c2d4bbc5f1787395297e83b7cd1d7a64beebdbdb0cbbdd3e3fd2048a3f59ee87b9d23590655bd01c92ba0f8cb4d1737336a485fdc9067d1ceffa9345de8f999f0ce94dc25aa3dd2fbf62b9e662980a3ed4edc605ac8bb520ae8155f1502425553b8ffaacabf6a35f53f0058fad367f142d843f4671befc");
+        let expected = from_hex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4a5a6a7a8a9aaabacadaeafb0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4");
+        assert!(key.decrypt_pkcs1v15(&mut rng, ct.as_slice()).unwrap() == expected);
+
+        // A padding string of exactly 8 bytes is the minimum
+        let ct = from_hex("34de65413308644041fc217ee605f88482fd327082eaf7e11b42909f57aded0f25b10709d01e38d63dc0f11b55a296137a60c7542cf5a57b8737cbee2a2c64e7460850698d719d3434310bdfca9af1293b5fca21f5c9ddc12b06ab32082def6c379abcd8e44fc36996ef3d19106584f54a1a5aceb3bb1aa924fc1ddb9ee36cf1761049a4d142f042cca685fe9aeec475b7ce69216921cbe00d0f78bd1b51bf1186803c54a7845a6f460da01d6c3fb263bec18baa97937eefb213c77faa11543b185cf0f143874ef49f5c5b39b3b43b7def8f5add4d7c52f026b4295bdb3c87131a35d97a43daf4a5032bfe31d534be68762faae7759c86fb9f82c7f9d98b0f58");
+        assert_eq!(key.decrypt_pkcs1v15(&mut rng, ct.as_slice()).unwrap().len(), 245);
+
+        let invalid = [
+            // Block type 1 instead of 2
+            "bace92abcd9e9b9fce6f2559b35d14340ba89e941a6f8b67dbace9af8c7cad40044dcbd8fb08a3c62f92b3293ec2fe5b45457d657a949e54a61661e0a44d53562cc49b155550edba17c0b6674a3658366a3182b07c8557c4e0b953fbf3dc6a675726e2b9659e2657bb2101d2194d5e5299ce4fd8146f15ff3347242fb6a9e53b70450813e90a6387d2f53cf4af575f76e1c5a77ea857a731e1185d250f15282e1454ce32398a5f2bcc251a4981a9b198d56ac028561d9d85bca499aa3e6b1f84c80b6da304966d6862e45c8de36051b554037da7e82eb91f0d496ebd0e2aabdd7ce17ae469c54a229d4428f4cfd5ff7337d98fab47b3fd1c3f84f8f7a3d11bfc",
+            // No zero byte after the padding string
+            "0899842825be449de8887e2cf26d0d9b2fef8a7ad9477e36d6ef2fa7b872c422139e95ad330189e76de36dcd46a9f8c89b5a7d7f5a649baccfae493f67d4550af3fb3442afcf2dd6b4bc7bd3334e30ce57b449c5bf12fc008d7e79440e8d9d18f3df7f4be5a789881b6d00887601dd44b97fadc04d099848ddd9222f11b657379434b6e493f5c11bdb4256680c29c09f9f729ce7b326c8621b957dd93e42aa47721f5bac5983260b8677189885e6158a0a9cafc54fb67123115e1ad04d1a2c288dcfa7dbbb158f69b60c05d2fbe24690e870945887def0d9ab5ba956c1d726c91eb2a8643d65f0e52f9c07113d1d4bf187d199910ef40f6d8e5d41c5391d18eb",
+            // A padding string of only 7 bytes
+            "83d645b1883e1878d79a62874146939e8cdfb39268c89b7851b1ea9ed569da565a8c17eb972ec8e865fbb4994a532a71efafebfeaa023e37e422e5935ca33071eefa6682a22cd9a4ec5787d4d8a0a3f4448c5f50c4627fa424a6608babef4358d58e5c11320021dc33fb57323b8e48b8b860f5760e4ec9b034662758cc27a9db26cc748bc7e4c932a06f127865e436e93008714e6a0f83c1ea2ceb70652e338f93196cce76a3b5a4ff76abe0fb8ba47b8f0bffe3128465d5eb08ecbc976c96a21c532ab1ecd5de63b67e99023f59d0a051151ccb064bd7c7fb7d74aa3ef32ee09adcae86dc370deaf18ff4898bb9bbe42897c8b9d76f48577a536415ef9f03c9",
+            // The first byte isn't zero
+            "13f954c6d9c6b1839610c704147cf46e4b45ee81807ed251c9c9010f19b4e887c5c03fcd62484363895125d266b07206683fc6b45d9d6b54fe1c562f6da53db82c8e8f2b94daa944b0679ab910f782278dd279f9119e2c7de10eac5ce41d80f6db67a1d3c2c237eb898ec176626bed8ddd52170b8ee670bd5e681201c98a96eed6370e3fa02cf06208a432c9b4c62cbe031bfc7d322ecf63c05778805ae3202954274f430e4f2cee09196cb8328177091b788ae7c130c322d9bd6d6c75b48a158e9317d51160054c55231299bf537c29d9cd07bca15d8972b0446fce95a1b4280ed2e07c33295c99e37a1d14f5ee8db64e62075129e96e9b71956c126ce06964"
+        ];
+        for ct in invalid.iter() {
+            let ct = from_hex(*ct);
+            assert_eq!(key.decrypt_pkcs1v15(&mut rng, ct.as_slice()), Err(DecryptionError));
+        }
+
+        let n = from_hex(N);
+        assert_eq!(key.decrypt_pkcs1v15(&mut rng, n.as_slice()), Err(DecryptionError));
+        assert_eq!(key.decrypt_pkcs1v15(&mut rng, n.slice_from(1)), Err(DecryptionError));
+    }
+
+    #[test]
+    fn test_pkcs1v15_encrypt() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = test_key();
+        let public = key.public_key();
+
+        for len in [0u, 1, 100, 245].iter() {
+            let msg = Vec::from_fn(*len, |i| i as u8);
+            let ct = public.encrypt_pkcs1v15(&mut rng, msg.as_slice()).unwrap();
+            assert_eq!(ct.len(), 256);
+            assert!(key.decrypt_pkcs1v15(&mut rng, ct.as_slice()).unwrap() == msg);
+        }
+
+        let msg = Vec::from_elem(246, 0u8);
+        assert_eq!(public.encrypt_pkcs1v15(&mut rng, msg.as_slice()), Err(MessageTooLong));
+    }
+
+    #[test]
+    fn test_oaep_decrypt() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = test_key();
+        let msg = from_hex(MSG);
+        let no_label = [0u8, ..0];
+
+        let ct = from_hex("8aca3994ef08957e8b72ad02b684f1138efc13538bd47a0f150c02fba8b49b4f465484b5c3f5ec617fe82896f2a4b31b743869b8ac74cc79788921b0e0e003c899bbecabb880cf093dd073e4d30481714f5c8b2af4c1055397e721c174ac0d98f5eb50a85e30eb7df6b2f6778e9e8c636be157396294f6ed0e320b737271e6ed9e0c69a76a9b78427836018325a8ea336791ae1505a390bfcb1ceaf84911ff13a18df00c2b2024ef2002f592d0c65726e07b6677a4aed450a3c9b5a3d3de201fce16b7ef7632e7783c9018423338798f0244a0f9ffa53f802fcd49c4c7a5c57b2a577f05f1c64d8a4ce7f2095d277f7ca6a328e02c754dae7c85cd485b2068bb");
+        assert!(key.decrypt_oaep(&mut Sha1::new(), &mut rng, ct.as_slice(), no_label).unwrap() ==
+            msg);
+        assert_eq!(key.decrypt_oaep(&mut Sha256::new(), &mut rng, ct.as_slice(), no_label),
+            Err(DecryptionError));
+        assert_eq!(key.decrypt_oaep(&mut Sha1::new(), &mut rng, ct.as_slice(), b"label"),
+            Err(DecryptionError));
+
+        let ct = from_hex("6dc9a6bc6794652b75ecbd922df1090c43ac2ac6e133a0a5e3312d8885e5a708f7e18618d335c1d7c4e5d7d9365dcbbe1a6a40a3ebebfcb69916a72fad40879ae6ab638dd0f4ac5baa8b27dcd4293ce761d166dc71d3491ac784bfdac3d0e7aae3f12a909266f04813e1058279c3e66f64d8ed30e469c7b99c0a7a8d6c02ab7ecf6deaa86008b58867ed03ddde6326045381ac1179fbcc2ae944bfa34c6420b977729893c0d104e63fb304df32f7bd4436f117e0fb16e377f835340a4c5f55c1041ab8906fdd487f706198cd050a7f3221e0f1125d6c22339fe2981204acb10170d108999d9a06355a8512b77638b3f0cfc1b1b52b9d2227ba72ffb27dbcdc0b");
+        assert!(key.decrypt_oaep(&mut Sha256::new(), &mut rng, ct.as_slice(), b"label").unwrap() ==
+            msg);
+        assert_eq!(key.decrypt_oaep(&mut Sha256::new(), &mut rng, ct.as_slice(), no_label),
+            Err(DecryptionError));
+
+        let ct = from_hex("0c454a66af298be848099421ded6473842171d12b3662db220f464fb36df8fe6e61217d9546468a429b044a9c21327c91fef34f27cacc5e5cd5af97a93967152b11f150130e1de1c4b867fab0c10aa54a6d3c600b0c0d3e3e6485befee5cd3565129b2cc972c1ce2fe9047bc639ede08b877d4d457fd3a913ad27aed0f8d82388ef3390565a48f5e9c838da6571651df42c3839f734cc1e4aa6fd4e814ba57e9cd0dc81a1af44d94061a3a75332491787570a2becb7452ee7d849a8c53f33c8ea7a218d327693e22089e2746a859bc54ad883f738c36dc0dea1126d16be47f4d0b90c90281a423cc61c24b1c8814dcb268daa4e73b9edf0e09f985b2fe165810");
+        assert!(key.decrypt_oaep(&mut Sha256::new(), &mut rng, ct.as_slice(), no_label).unwrap()
+            .len() == 0);
+
+        let mut bad_ct = ct.clone();
+        *bad_ct.get_mut(0) ^= 1;
+        assert_eq!(key.decrypt_oaep(&mut Sha256::new(), &mut rng, bad_ct.as_slice(), no_label),
+            Err(DecryptionError));
+    }
+
+    #[test]
+    fn test_oaep_decrypt_vectors() {
+        // Example 1 from oaep-vect.txt in the RSA Laboratories PKCS #1 v2.1 test vectors:
+        // ftp://ftp.rsasecurity.com/pub/pkcs/pkcs-1/pkcs-1v2-1-vec.zip
+        // These use SHA-1 and an empty label.
+        let mut rng = IsaacRng::new_unseeded();
+        let key = RsaPrivateKey::from_components(
+            big("a8b3b284af8eb50b387034a860f146c4919f318763cd6c5598c8ae4811a1e0abc4c7e0b082d693a5e7fced675cf4668512772c0cbc64a742c6c630f533c8cc72f62ae833c40bf25842e984bb78bdbf97c0107d55bdb662f5c4e0fab9845cb5148ef7392dd3aaff93ae1e6b667bb3d4247616d4f5ba10d4cfd226de88d39f16fb"),
+            big("10001"),
+            big("53339cfdb79fc8466a655c7316aca85c55fd8f6dd898fdaf119517ef4f52e8fd8e258df93fee180fa0e4ab29693cd83b152a553d4ac4d1812b8b9fa5af0e7f55fe7304df41570926f3311f15c4d65a732c483116ee3d3d2d0af3549ad9bf7cbfb78ad884f84d5beb04724dc7369b31def37d0cf539e9cfcdd3de653729ead5d1"),
+            big("d32737e7267ffe1341b2d5c0d150a81b586fb3132bed2f8d5262864a9cb9f30af38be448598d413a172efb802c21acf1c11c520c2f26a471dcad212eac7ca39d"),
+            big("cc8853d1d54da630fac004f471f281c7b8982d8224a490edbeb33d3e3d5cc93c4765703d1dd791642f1f116a0dd852be2419b2af72bfe9a030e860b0288b5d77")).unwrap();
+        let tests = [
+            ("354fe67b4a126d5d35fe36c777791a3f7ba13def484e2d3908aff722fad468fb21696de95d0be911c2d3174f8afcc201035f7b6d8e69402de5451618c21a535fa9d7bfc5b8dd9fc243f8cf927db31322d6e881eaa91a996170e657a05a266426d98c88003f8477c1227094a0d9fa1e8c4024309ce1ecccb5210035d47ac72e8a",
+             "6628194e12073db03ba94cda9ef9532397d50dba79b987004afefe34"),
+            ("640db1acc58e0568fe5407e5f9b701dff8c3c91e716c536fc7fcec6cb5b71c1165988d4a279e1577d730fc7a29932e3f00c81515236d8d8e31017a7a09df4352d904cdeb79aa583adcc31ea698a4c05283daba9089be5491f67c1a4ee48dc74bbbe6643aef846679b4cb395a352d5ed115912df696ffe0702932946d71492b44",
+             "750c4047f547e8e41411856523298ac9bae245efaf1397fbe56f9dd5"),
+            ("423736ed035f6026af276c35c0b3741b365e5f76ca091b4e8c29e2f0befee603595aa8322d602d2e625e95eb81b2f1c9724e822eca76db8618cf09c5343503a4360835b5903bc637e3879fb05e0ef32685d5aec5067cd7cc96fe4b2670b6eac3066b1fcf5686b68589aafb7d629b02d8f8625ca3833624d4800fb081b1cf94eb",
+             "d94ae0832e6445ce42331cb06d531a82b1db4baad30f746dc916df24d4e3c2451fff59a6423eb0e1d02d4fe646cf699dfd818c6e97b051"),
+            ("45ead4ca551e662c9800f1aca8283b0525e6abae30be4b4aba762fa40fd3d38e22abefc69794f6ebbbc05ddbb11216247d2f412fd0fba87c6e3acd888813646fd0e48e785204f9c3f73d6d8239562722dddd8771fec48b83a31ee6f592c4cfd4bc88174f3b13a112aae3b9f7b80e0fc6f7255ba880dc7d8021e22ad6a85f0755",
+             "52e650d98e7f2a048b4f86852153b97e01dd316f346a19f67a85"),
+            ("36f6e34d94a8d34daacba33a2139d00ad85a9345a86051e73071620056b920e219005855a213a0f23897cdcd731b45257c777fe908202befdd0b58386b1244ea0cf539a05d5d10329da44e13030fd760dcd644cfef2094d1910d3f433e1c7c6dd18bc1f2df7f643d662fb9dd37ead9059190f4fa66ca39e869c4eb449cbdc439",
+             "8da89fd9e5f974a29feffb462b49180f6cf9e802"),
+            ("42cee2617b1ecea4db3f4829386fbd61dafbf038e180d837c96366df24c097b4ab0fac6bdf590d821c9f10642e681ad05b8d78b378c0f46ce2fad63f74e0ad3df06b075d7eb5f5636f8d403b9059ca761b5c62bb52aa45002ea70baace08ded243b9d8cbd62a68ade265832b56564e43a6fa42ed199a099769742df1539e8255",
+             "26521050844271")
+        ];
+        for &(ct, msg) in tests.iter() {
+            let ct = from_hex(ct);
+            assert!(key.decrypt_oaep(&mut Sha1::new(), &mut rng, ct.as_slice(), b"").unwrap() ==
+                from_hex(msg));
+        }
+    }
+
+    #[test]
+    fn test_oaep_encrypt() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = test_key();
+        let public = key.public_key();
+
+        // The longest message for SHA-256 is 256 - 2 * 32 - 2 bytes
+        for len in [0u, 1, 100, 190].iter() {
+            let msg = Vec::from_fn(*len, |i| i as u8);
+            let ct = public.encrypt_oaep(&mut Sha256::new(), &mut rng, msg.as_slice(), b"label")
+                .unwrap();
+            assert!(key.decrypt_oaep(&mut Sha256::new(), &mut rng, ct.as_slice(), b"label")
+                .unwrap() == msg);
+        }
+
+        let msg = Vec::from_elem(191, 0u8);
+        assert_eq!(public.encrypt_oaep(&mut Sha256::new(), &mut rng, msg.as_slice(), b""),
+            Err(MessageTooLong));
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut rng = IsaacRng::new_unseeded();
+        for &(bits, e) in [(512u, 65537u), (768, 3)].iter() {
+            let key = RsaPrivateKey::generate(&mut rng, bits, e);
+            assert_eq!(key.public_key().n().bits(), bits);
+
+            let msg = b"generated key";
+            let sig = key.sign_pss(&mut Sha256::new(), &mut rng, msg, 20).unwrap();
+            assert!(key.public_key().verify_pss(&mut Sha256::new(), msg, sig.as_slice(), 20));
+            let ct = key.public_key().encrypt_oaep(&mut Sha1::new(), &mut rng, msg, b"").unwrap();
+            assert!(key.decrypt_oaep(&mut Sha1::new(), &mut rng, ct.as_slice(), b"").unwrap() ==
+                Vec::from_slice(msg));
+        }
+    }
+}
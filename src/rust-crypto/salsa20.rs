@@ -7,6 +7,7 @@
 use buffer::{BufferResult, RefReadBuffer, RefWriteBuffer};
 use symmetriccipher::{Encryptor, Decryptor, SynchronousStreamCipher, SymmetricCipherError};
 use cryptoutil::{read_u32v_le, symm_enc_or_dec, write_u32_le};
+use simd::u32x4;
 
 use std::slice::bytes::copy_memory;
 
@@ -17,46 +18,121 @@ pub struct Salsa20 {
     offset: uint,
 }
 
-fn doubleround(y: &mut [u32, ..16]) {
-    y[ 4] = y[ 4] ^ (y[ 0]+y[12]).rotate_left( 7);
-    y[ 8] = y[ 8] ^ (y[ 4]+y[ 0]).rotate_left( 9);
-    y[12] = y[12] ^ (y[ 8]+y[ 4]).rotate_left(13);
-    y[ 0] = y[ 0] ^ (y[12]+y[ 8]).rotate_left(18);
-    
-    y[ 9] = y[ 9] ^ (y[ 5]+y[ 1]).rotate_left( 7);
-    y[13] = y[13] ^ (y[ 9]+y[ 5]).rotate_left( 9);
-    y[ 1] = y[ 1] ^ (y[13]+y[ 9]).rotate_left(13);
-    y[ 5] = y[ 5] ^ (y[ 1]+y[13]).rotate_left(18);
-
-    y[14] = y[14] ^ (y[10]+y[ 6]).rotate_left( 7);
-    y[ 2] = y[ 2] ^ (y[14]+y[10]).rotate_left( 9);
-    y[ 6] = y[ 6] ^ (y[ 2]+y[14]).rotate_left(13);
-    y[10] = y[10] ^ (y[ 6]+y[ 2]).rotate_left(18);
-
-    y[ 3] = y[ 3] ^ (y[15]+y[11]).rotate_left( 7);
-    y[ 7] = y[ 7] ^ (y[ 3]+y[15]).rotate_left( 9);
-    y[11] = y[11] ^ (y[ 7]+y[ 3]).rotate_left(13);
-    y[15] = y[15] ^ (y[11]+y[ 7]).rotate_left(18);
-
-    y[1] = y[1] ^ (y[0]+y[3]).rotate_left( 7);
-    y[2] = y[2] ^ (y[1]+y[0]).rotate_left( 9);
-    y[3] = y[3] ^ (y[2]+y[1]).rotate_left(13);
-    y[0] = y[0] ^ (y[3]+y[2]).rotate_left(18);
-
-    y[6] = y[6] ^ (y[5]+y[4]).rotate_left( 7);
-    y[7] = y[7] ^ (y[6]+y[5]).rotate_left( 9);
-    y[4] = y[4] ^ (y[7]+y[6]).rotate_left(13);
-    y[5] = y[5] ^ (y[4]+y[7]).rotate_left(18);
-
-    y[11] = y[11] ^ (y[10]+y[ 9]).rotate_left( 7);
-    y[ 8] = y[ 8] ^ (y[11]+y[10]).rotate_left( 9);
-    y[ 9] = y[ 9] ^ (y[ 8]+y[11]).rotate_left(13);
-    y[10] = y[10] ^ (y[ 9]+y[ 8]).rotate_left(18);
-
-    y[12] = y[12] ^ (y[15]+y[14]).rotate_left( 7);
-    y[13] = y[13] ^ (y[12]+y[15]).rotate_left( 9);
-    y[14] = y[14] ^ (y[13]+y[12]).rotate_left(13);
-    y[15] = y[15] ^ (y[14]+y[13]).rotate_left(18);
+impl Clone for Salsa20 {
+    fn clone(&self) -> Salsa20 { *self }
+}
+
+// The double round works on arrays of u32s as well as arrays of u32x4s.
+macro_rules! doubleround(
+    ($y:expr) => ({
+        $y[ 4] = $y[ 4] ^ ($y[ 0]+$y[12]).rotate_left( 7);
+        $y[ 8] = $y[ 8] ^ ($y[ 4]+$y[ 0]).rotate_left( 9);
+        $y[12] = $y[12] ^ ($y[ 8]+$y[ 4]).rotate_left(13);
+        $y[ 0] = $y[ 0] ^ ($y[12]+$y[ 8]).rotate_left(18);
+
+        $y[ 9] = $y[ 9] ^ ($y[ 5]+$y[ 1]).rotate_left( 7);
+        $y[13] = $y[13] ^ ($y[ 9]+$y[ 5]).rotate_left( 9);
+        $y[ 1] = $y[ 1] ^ ($y[13]+$y[ 9]).rotate_left(13);
+        $y[ 5] = $y[ 5] ^ ($y[ 1]+$y[13]).rotate_left(18);
+
+        $y[14] = $y[14] ^ ($y[10]+$y[ 6]).rotate_left( 7);
+        $y[ 2] = $y[ 2] ^ ($y[14]+$y[10]).rotate_left( 9);
+        $y[ 6] = $y[ 6] ^ ($y[ 2]+$y[14]).rotate_left(13);
+        $y[10] = $y[10] ^ ($y[ 6]+$y[ 2]).rotate_left(18);
+
+        $y[ 3] = $y[ 3] ^ ($y[15]+$y[11]).rotate_left( 7);
+        $y[ 7] = $y[ 7] ^ ($y[ 3]+$y[15]).rotate_left( 9);
+        $y[11] = $y[11] ^ ($y[ 7]+$y[ 3]).rotate_left(13);
+        $y[15] = $y[15] ^ ($y[11]+$y[ 7]).rotate_left(18);
+
+        $y[1] = $y[1] ^ ($y[0]+$y[3]).rotate_left( 7);
+        $y[2] = $y[2] ^ ($y[1]+$y[0]).rotate_left( 9);
+        $y[3] = $y[3] ^ ($y[2]+$y[1]).rotate_left(13);
+        $y[0] = $y[0] ^ ($y[3]+$y[2]).rotate_left(18);
+
+        $y[6] = $y[6] ^ ($y[5]+$y[4]).rotate_left( 7);
+        $y[7] = $y[7] ^ ($y[6]+$y[5]).rotate_left( 9);
+        $y[4] = $y[4] ^ ($y[7]+$y[6]).rotate_left(13);
+        $y[5] = $y[5] ^ ($y[4]+$y[7]).rotate_left(18);
+
+        $y[11] = $y[11] ^ ($y[10]+$y[ 9]).rotate_left( 7);
+        $y[ 8] = $y[ 8] ^ ($y[11]+$y[10]).rotate_left( 9);
+        $y[ 9] = $y[ 9] ^ ($y[ 8]+$y[11]).rotate_left(13);
+        $y[10] = $y[10] ^ ($y[ 9]+$y[ 8]).rotate_left(18);
+
+        $y[12] = $y[12] ^ ($y[15]+$y[14]).rotate_left( 7);
+        $y[13] = $y[13] ^ ($y[12]+$y[15]).rotate_left( 9);
+        $y[14] = $y[14] ^ ($y[13]+$y[12]).rotate_left(13);
+        $y[15] = $y[15] ^ ($y[14]+$y[13]).rotate_left(18);
+    });
+)
+
+// Run the Salsa20 rounds over a single block. The state is held as four u32x4s, each holding one
+// of the diagonals of the state, so that the four column quarter rounds run at once. Before the
+// row rounds, the lanes are rotated so that the rows line up in the same way, and afterwards they
+// are rotated back.
+fn salsa20_rounds(y: &mut [u32, ..16]) {
+    let mut a = u32x4(y[ 0], y[ 5], y[10], y[15]);
+    let mut b = u32x4(y[ 4], y[ 9], y[14], y[ 3]);
+    let mut c = u32x4(y[ 8], y[13], y[ 2], y[ 7]);
+    let mut d = u32x4(y[12], y[ 1], y[ 6], y[11]);
+
+    for _ in range(0u, 10) {
+        b = b ^ (a + d).rotate_left( 7);
+        c = c ^ (b + a).rotate_left( 9);
+        d = d ^ (c + b).rotate_left(13);
+        a = a ^ (d + c).rotate_left(18);
+
+        b = b.rotate_lanes_3();
+        c = c.rotate_lanes_2();
+        d = d.rotate_lanes_1();
+
+        d = d ^ (a + b).rotate_left( 7);
+        c = c ^ (d + a).rotate_left( 9);
+        b = b ^ (c + d).rotate_left(13);
+        a = a ^ (b + c).rotate_left(18);
+
+        b = b.rotate_lanes_1();
+        c = c.rotate_lanes_2();
+        d = d.rotate_lanes_3();
+    }
+
+    let u32x4(y0, y5, y10, y15) = a;
+    let u32x4(y4, y9, y14, y3) = b;
+    let u32x4(y8, y13, y2, y7) = c;
+    let u32x4(y12, y1, y6, y11) = d;
+    *y = [y0, y1, y2, y3, y4, y5, y6, y7, y8, y9, y10, y11, y12, y13, y14, y15];
+}
+
+// Generate 4 consecutive blocks of keystream at once, starting at the given counter. Each word of
+// the state is held in a u32x4 with lane i belonging to the i-th block, so the rounds are exactly
+// the same as for a single block of u32s.
+fn salsa20_block_x4(state: &[u32, ..16], counter: u64, output: &mut [u8]) {
+    let mut s = [u32x4(0, 0, 0, 0), ..16];
+    for i in range(0u, 16) {
+        s[i] = u32x4(state[i], state[i], state[i], state[i]);
+    }
+
+    let ctrs = [counter, counter + 1, counter + 2, counter + 3];
+    s[8] = u32x4(ctrs[0] as u32, ctrs[1] as u32, ctrs[2] as u32, ctrs[3] as u32);
+    s[9] = u32x4(
+        (ctrs[0] >> 32) as u32,
+        (ctrs[1] >> 32) as u32,
+        (ctrs[2] >> 32) as u32,
+        (ctrs[3] >> 32) as u32);
+
+    let mut x = s;
+    for _ in range(0u, 10) {
+        doubleround!(x);
+    }
+
+    for i in range(0u, 16) {
+        let u32x4(w0, w1, w2, w3) = x[i] + s[i];
+        write_u32_le(output.mut_slice(      i * 4,       i * 4 + 4), w0);
+        write_u32_le(output.mut_slice( 64 + i * 4,  64 + i * 4 + 4), w1);
+        write_u32_le(output.mut_slice(128 + i * 4, 128 + i * 4 + 4), w2);
+        write_u32_le(output.mut_slice(192 + i * 4, 192 + i * 4 + 4), w3);
+    }
 }
 
 impl Salsa20 {
@@ -133,9 +209,7 @@ impl Salsa20 {
         let mut z = [0u32, ..16];
         read_u32v_le(x.as_mut_slice(), self.state);
         read_u32v_le(z.as_mut_slice(), self.state);
-        for _ in range(0u, 10) {
-            doubleround(&mut z);
-        }
+        salsa20_rounds(&mut z);
         for i in range(0u, 16) {
             write_u32_le(self.output.mut_slice(i*4, (i+1)*4), x[i] + z[i]);
         }
@@ -147,9 +221,7 @@ impl Salsa20 {
     fn hsalsa20_hash(&mut self) {
         let mut x = [0u32, ..16];
         read_u32v_le(x.as_mut_slice(), self.state);
-        for _ in range(0u, 10) {
-            doubleround(&mut x);
-        }
+        salsa20_rounds(&mut x);
         for i in range(0u, 16) {
             write_u32_le(self.output.mut_slice(i*4, (i+1)*4), x[i]);
         }
@@ -168,8 +240,27 @@ impl Salsa20 {
 impl SynchronousStreamCipher for Salsa20 {
     fn process(&mut self, input: &[u8], output: &mut [u8]) {
         assert!(input.len() == output.len());
-        for (x, y) in input.iter().zip(output.mut_iter()) {
-            *y = *x ^ self.next();
+        let len = input.len();
+        let mut i = 0;
+        while i < len {
+            // Once the buffered keystream is used up, generate 4 blocks at a time for as long as
+            // there is enough input left to use all of them.
+            if self.offset == 64 && len - i >= 256 {
+                let mut x = [0u32, ..16];
+                read_u32v_le(x.as_mut_slice(), self.state);
+                let mut keystream = [0u8, ..256];
+                salsa20_block_x4(&x, self.counter, keystream);
+                self.counter += 4;
+                for ((x, y), k) in input.slice(i, i + 256).iter()
+                        .zip(output.mut_slice(i, i + 256).mut_iter())
+                        .zip(keystream.iter()) {
+                    *y = *x ^ *k;
+                }
+                i += 256;
+            } else {
+                output[i] = input[i] ^ self.next();
+                i += 1;
+            }
         }
     }
 }
@@ -190,9 +281,103 @@ impl Decryptor for Salsa20 {
 
 #[cfg(test)]
 mod test {
-    use salsa20::Salsa20;
+    use std::rand::{Rng, IsaacRng};
+
+    use salsa20::{Salsa20, salsa20_rounds, salsa20_block_x4};
+    use cryptoutil::write_u32_le;
     use symmetriccipher::SynchronousStreamCipher;
 
+    // The original scalar implementation of the rounds
+    fn salsa20_rounds_scalar(y: &mut [u32, ..16]) {
+        for _ in range(0u, 10) {
+            doubleround!(y);
+        }
+    }
+
+    #[test]
+    fn test_simd_rounds_match_scalar() {
+        let mut rng = IsaacRng::new_unseeded();
+        for _ in range(0u, 100) {
+            let mut state = [0u32, ..16];
+            for w in state.mut_iter() {
+                *w = rng.gen();
+            }
+
+            let mut expected = state;
+            salsa20_rounds_scalar(&mut expected);
+            let mut out = state;
+            salsa20_rounds(&mut out);
+            assert!(out == expected);
+
+            // Make sure that the counter overflows into the next word in some of the blocks
+            let counter = 0xfffffffeu64;
+            let mut expected_x4 = [0u8, ..256];
+            for i in range(0u, 4) {
+                let ctr = counter + i as u64;
+                let mut x = state;
+                x[8] = ctr as u32;
+                x[9] = (ctr >> 32) as u32;
+                let mut z = x;
+                salsa20_rounds_scalar(&mut z);
+                for j in range(0u, 16) {
+                    write_u32_le(expected_x4.mut_slice(i * 64 + j * 4, i * 64 + j * 4 + 4),
+                        x[j] + z[j]);
+                }
+            }
+            let mut out_x4 = [0u8, ..256];
+            salsa20_block_x4(&state, counter, out_x4);
+            assert!(out_x4.as_slice() == expected_x4.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_salsa20_x4_process() {
+        // Processing a long input at once uses the 4 block path while processing it a byte at a
+        // time only uses the single block path - the results must be identical.
+        let key = [7u8, ..32];
+        let nonce = [9u8, ..8];
+        let input = Vec::from_fn(1000, |i| i as u8);
+
+        let mut c1 = Salsa20::new(key, nonce);
+        let mut out1 = Vec::from_elem(input.len(), 0u8);
+        c1.process(input.as_slice(), out1.as_mut_slice());
+
+        let mut c2 = Salsa20::new(key, nonce);
+        let mut out2 = Vec::from_elem(input.len(), 0u8);
+        for i in range(0u, input.len()) {
+            c2.process(input.slice(i, i + 1), out2.mut_slice(i, i + 1));
+        }
+
+        assert!(out1 == out2);
+    }
+
+    #[test]
+    fn test_clone() {
+        // After cloning partway through a block, the original and the copy are fed different
+        // data and each must match a cipher that processed the whole of its own input.
+        let key = [7u8, ..32];
+        let nonce = [9u8, ..8];
+        let input1 = Vec::from_fn(600, |i| i as u8);
+        let input2 = Vec::from_fn(600, |i| (i * 5 + 3) as u8);
+        let split = 100;
+
+        let mut c1 = Salsa20::new(key, nonce);
+        let mut out1 = Vec::from_elem(input1.len(), 0u8);
+        c1.process(input1.slice_to(split), out1.mut_slice_to(split));
+        let mut c2 = c1.clone();
+        let mut out2 = out1.clone();
+        c1.process(input1.slice_from(split), out1.mut_slice_from(split));
+        c2.process(input2.slice_from(split), out2.mut_slice_from(split));
+
+        let mut expected = Vec::from_elem(input1.len(), 0u8);
+        Salsa20::new(key, nonce).process(input1.as_slice(), expected.as_mut_slice());
+        assert!(out1 == expected);
+        let mut input = Vec::from_slice(input1.slice_to(split));
+        input.push_all(input2.slice_from(split));
+        Salsa20::new(key, nonce).process(input.as_slice(), expected.as_mut_slice());
+        assert!(out2 == expected);
+    }
+
     #[test]
     fn test_salsa20_128bit_ecrypt_set_1_vector_0() {
         let key = [128u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
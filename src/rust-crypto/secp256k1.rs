@@ -0,0 +1,286 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * ECDSA over secp256k1 with recoverable signatures, as used by Bitcoin and Ethereum.
+ *
+ * Signatures are deterministic, as in the ecdsa module, and always have a low s (s <= n / 2):
+ * since (r, n - s) is also a valid signature whenever (r, s) is, only the low form is produced and
+ * accepted, so that signatures can't be modified by third parties (BIP 62, EIP-2). A signature
+ * carries a recovery id v in the range [0, 3], which allows the signer's public key to be recovered
+ * from the message and the signature. Ethereum transactions encode v as 27 + v or with the chain
+ * id folded in; converting to and from those forms is left to the caller.
+ *
+ * Messages are hashed with any Digest, for example Sha256 or an implementation of Keccak-256.
+ */
+
+use num::bigint::BigUint;
+
+use cryptoutil::{biguint_from_bytes_be, biguint_to_bytes_be};
+use digest::Digest;
+use ec::{Curve, EcPrivateKey, EcPublicKey};
+use ecdsa;
+use ecdsa::EcdsaSignature;
+
+/// A signature (r, s) with the recovery id v
+#[deriving(Clone, PartialEq, Show)]
+pub struct RecoverableSignature {
+    pub r: BigUint,
+    pub s: BigUint,
+    pub v: u8
+}
+
+impl RecoverableSignature {
+    /**
+     * Decode a signature from 65 bytes: r and s as 32 byte big endian integers followed by v.
+     * Returns None if the length is wrong or v is greater than 3.
+     */
+    pub fn from_bytes(data: &[u8]) -> Option<RecoverableSignature> {
+        if data.len() != 65 || data[64] > 3 {
+            return None;
+        }
+        Some(RecoverableSignature {
+            r: biguint_from_bytes_be(data.slice_to(32)),
+            s: biguint_from_bytes_be(data.slice(32, 64)),
+            v: data[64]
+        })
+    }
+
+    /// Encode the signature as r, s and v in 65 bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = biguint_to_bytes_be(&self.r, 32);
+        out.push_all(biguint_to_bytes_be(&self.s, 32).as_slice());
+        out.push(self.v);
+        return out;
+    }
+
+    /// The signature without the recovery id
+    pub fn signature(&self) -> EcdsaSignature {
+        EcdsaSignature {
+            r: self.r.clone(),
+            s: self.s.clone()
+        }
+    }
+}
+
+/// Sign a message, which is hashed with the digest, with a private key on secp256k1
+pub fn sign<D: Digest + Clone>(key: &EcPrivateKey, digest: &mut D, msg: &[u8])
+        -> RecoverableSignature {
+    let curve = Curve::secp256k1();
+    assert!(*key.curve() == curve);
+    let (sig, mut v) = ecdsa::sign_recoverable(key, digest, msg);
+
+    // Negating s corresponds to negating k * G, which flips the parity of its y coordinate
+    if !sig.is_low_s(&curve) {
+        v ^= 1;
+    }
+    let sig = sig.normalize_s(&curve);
+    RecoverableSignature {
+        r: sig.r,
+        s: sig.s,
+        v: v
+    }
+}
+
+/**
+ * Verify a signature of a message, which is hashed with the digest, with a public key on
+ * secp256k1. Signatures with a high s and keys on other curves are rejected.
+ */
+pub fn verify<D: Digest>(key: &EcPublicKey, digest: &mut D, msg: &[u8], sig: &EcdsaSignature)
+        -> bool {
+    let curve = Curve::secp256k1();
+    *key.curve() == curve && sig.is_low_s(&curve) && ecdsa::verify(key, digest, msg, sig)
+}
+
+/**
+ * Recover the public key that signed a message, which is hashed with the digest. Returns None if
+ * the signature has a high s or there is no public key that it is valid for.
+ */
+pub fn recover<D: Digest>(digest: &mut D, msg: &[u8], sig: &RecoverableSignature)
+        -> Option<EcPublicKey> {
+    let curve = Curve::secp256k1();
+    let ecdsa_sig = sig.signature();
+    if !ecdsa_sig.is_low_s(&curve) {
+        return None;
+    }
+    ecdsa::recover_public_key(&curve, digest, msg, &ecdsa_sig, sig.v)
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::Zero;
+    use std::rand::IsaacRng;
+
+    use serialize::hex::{FromHex, ToHex};
+
+    use ec::{Curve, EcPrivateKey};
+    use ecdsa;
+    use secp256k1::{RecoverableSignature, sign, verify, recover};
+    use sha2::{Sha256, Sha512};
+
+    struct Test {
+        key: &'static str,
+        msg: &'static str,
+        sig: &'static str,
+        public_key: &'static str
+    }
+
+    // The signatures are deterministic RFC 6979 signatures using SHA-256, normalized to low S and
+    // followed by the recovery id.
+    static TESTS: &'static [Test] = &[
+        Test {
+            key: "0000000000000000000000000000000000000000000000000000000000000001",
+            msg: "Satoshi Nakamoto",
+            sig: "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d82442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e501",
+            public_key: "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+        },
+        Test {
+            key: "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+            msg: "Satoshi Nakamoto",
+            sig: "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d06b39cd0eb1bc8603e159ef5c20a5c8ad685a45b06ce9bebed3f153d10d93bed500",
+            public_key: "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798b7c52588d95c3b9aa25b0403f1eef75702e84bb7597aabe663b82f6f04ef2777"
+        },
+        Test {
+            key: "f8b8af8ce3c7cca5e300d33939540c10d45ce001b8f252bfbc57ba0342904181",
+            msg: "Alan Turing",
+            sig: "7063ae83e7f62bbb171798131b4a0564b956930092b33b07b395615d9ec7e15c58dfcc1e00a35e1572f366ffe34ba0fc47db1e7189759b9fb233c5b05ab388ea00",
+            public_key: "0492df7b245b81aa637ab4e867c8d511008f79161a97d64f2ac709600352f7acbce9bfdf1b13fa0cb1de4521e5386cde3a1cd26c5ab584989d07bbed58a5419f62"
+        },
+        Test {
+            key: "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+            msg: "Some data",
+            sig: "73a50d23d7524b3dd60f9a26f42e66cc6b89fe2cf2aab17cd97d17aa283e7d1915feb0683bfd36fee7c107fc8d3e56f4a27ae10d65e394eec0b11a4a282bf08301",
+            public_key: "044e3b81af9c2234cad09d679ce6035ed1392347ce64ce405f5dcd36228a25de6e47fd35c4215d1edf53e6f83de344615ce719bdb0fd878f6ed76f06dd277956de"
+        }
+    ];
+
+    fn private_key(t: &Test) -> EcPrivateKey {
+        let d = t.key.from_hex().unwrap();
+        EcPrivateKey::from_bytes(&Curve::secp256k1(), d.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_sign_known() {
+        for t in TESTS.iter() {
+            let key = private_key(t);
+            let public_key = key.public_key().to_bytes(false);
+            assert_eq!(public_key.as_slice().to_hex(), t.public_key.to_string());
+
+            let msg = t.msg.as_bytes();
+            let sig = sign(&key, &mut Sha256::new(), msg);
+            assert_eq!(sig.to_bytes().as_slice().to_hex(), t.sig.to_string());
+            assert!(verify(key.public_key(), &mut Sha256::new(), msg, &sig.signature()));
+
+            let recovered = recover(&mut Sha256::new(), msg, &sig).unwrap();
+            assert!(recovered == *key.public_key());
+        }
+    }
+
+    #[test]
+    fn test_high_s() {
+        let curve = Curve::secp256k1();
+        let t = &TESTS[0];
+        let key = private_key(t);
+        let msg = t.msg.as_bytes();
+        let sig = RecoverableSignature::from_bytes(t.sig.from_hex().unwrap().as_slice()).unwrap();
+
+        // The high form of the signature is valid ECDSA, but not accepted here
+        let high = RecoverableSignature {
+            r: sig.r.clone(),
+            s: *curve.n() - sig.s,
+            v: sig.v ^ 1
+        };
+        assert!(verify(key.public_key(), &mut Sha256::new(), msg, &sig.signature()));
+        assert!(!verify(key.public_key(), &mut Sha256::new(), msg, &high.signature()));
+        assert!(recover(&mut Sha256::new(), msg, &high).is_none());
+        assert!(high.signature().normalize_s(&curve) == sig.signature());
+    }
+
+    #[test]
+    fn test_recover_wrong() {
+        let curve = Curve::secp256k1();
+        let t = &TESTS[2];
+        let key = private_key(t);
+        let msg = t.msg.as_bytes();
+        let sig = RecoverableSignature::from_bytes(t.sig.from_hex().unwrap().as_slice()).unwrap();
+
+        // A different message or recovery id gives a different key
+        let other = recover(&mut Sha256::new(), b"Alan Turinh", &sig);
+        assert!(other.as_ref() != Some(key.public_key()));
+        let wrong_v = RecoverableSignature { v: 1, ..sig.clone() };
+        let other = recover(&mut Sha256::new(), msg, &wrong_v);
+        assert!(other.as_ref() != Some(key.public_key()));
+
+        // r + n is greater than p for almost every r, so there is no point for v = 2 or 3
+        let v2 = RecoverableSignature { v: 2, ..sig.clone() };
+        assert!(recover(&mut Sha256::new(), msg, &v2).is_none());
+
+        let zero = RecoverableSignature { r: Zero::zero(), ..sig.clone() };
+        assert!(recover(&mut Sha256::new(), msg, &zero).is_none());
+    }
+
+    #[test]
+    fn test_encoding() {
+        let data = TESTS[0].sig.from_hex().unwrap();
+        let sig = RecoverableSignature::from_bytes(data.as_slice()).unwrap();
+        assert_eq!(sig.to_bytes(), data);
+        assert_eq!(sig.v, 1);
+        assert!(RecoverableSignature::from_bytes(data.slice_to(64)).is_none());
+        let mut bad_v = data.clone();
+        *bad_v.get_mut(64) = 4;
+        assert!(RecoverableSignature::from_bytes(bad_v.as_slice()).is_none());
+    }
+
+    #[test]
+    fn test_sign_recover() {
+        let mut rng = IsaacRng::new_unseeded();
+        let curve = Curve::secp256k1();
+        for i in range(0u, 8) {
+            let key = EcPrivateKey::generate(&mut rng, &curve);
+            let msg = [i as u8, ..32];
+            let sig = sign(&key, &mut Sha256::new(), msg);
+            assert!(sig.signature().is_low_s(&curve));
+            assert!(verify(key.public_key(), &mut Sha256::new(), msg, &sig.signature()));
+            assert!(recover(&mut Sha256::new(), msg, &sig).unwrap() == *key.public_key());
+        }
+    }
+
+    #[test]
+    fn test_sign_recover_sha512() {
+        let mut rng = IsaacRng::new_unseeded();
+        let curve = Curve::secp256k1();
+        for i in range(0u, 4) {
+            let key = EcPrivateKey::generate(&mut rng, &curve);
+            let msg = [i as u8, ..32];
+            let sig = sign(&key, &mut Sha512::new(), msg);
+            assert!(sig.signature().is_low_s(&curve));
+            assert!(verify(key.public_key(), &mut Sha512::new(), msg, &sig.signature()));
+            assert!(recover(&mut Sha512::new(), msg, &sig).unwrap() == *key.public_key());
+
+            // The digest is part of what's signed
+            assert!(!verify(key.public_key(), &mut Sha256::new(), msg, &sig.signature()));
+            let other = recover(&mut Sha256::new(), msg, &sig);
+            assert!(other.as_ref() != Some(key.public_key()));
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_wrong_curve() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = EcPrivateKey::generate(&mut rng, &Curve::p256());
+        sign(&key, &mut Sha256::new(), b"message");
+    }
+
+    #[test]
+    fn test_verify_wrong_curve() {
+        let mut rng = IsaacRng::new_unseeded();
+        let key = EcPrivateKey::generate(&mut rng, &Curve::p256());
+        let msg = b"message";
+        let sig = ecdsa::sign(&key, &mut Sha256::new(), msg).normalize_s(key.curve());
+        assert!(!verify(key.public_key(), &mut Sha256::new(), msg, &sig));
+    }
+}
@@ -23,9 +23,14 @@
  */
 
 
-use cryptoutil::{write_u32_be, read_u32v_be, add_bytes_to_bits, FixedBuffer, FixedBuffer64,
+use cryptoutil::{write_u32_be, read_u32v_be, read_u32_be, read_u64_be, push_u32_be, push_u64_be,
+    push_fixed_buffer, read_fixed_buffer, read_bool, add_bytes_to_bits, FixedBuffer, FixedBuffer64,
     StandardPadding};
-use digest::Digest;
+use digest::{Digest, DigestBits, DigestState, DigestStateError, InvalidStateLength, InvalidState};
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+use util;
 
 /*
  * A SHA-1 implementation derived from Paul E. Jones's reference
@@ -47,6 +52,11 @@ pub struct Sha1 {
     length_bits: u64,
     buffer: FixedBuffer64,
     computed: bool,
+    use_sha_ni: bool,
+}
+
+impl Clone for Sha1 {
+    fn clone(&self) -> Sha1 { *self }
 }
 
 fn add_input(st: &mut Sha1, msg: &[u8]) {
@@ -54,7 +64,239 @@ fn add_input(st: &mut Sha1, msg: &[u8]) {
     // Assumes that msg.len() can be converted to u64 without overflow
     st.length_bits = add_bytes_to_bits(st.length_bits, msg.len() as u64);
     let st_h = &mut st.h;
-    st.buffer.input(msg, |d: &[u8]| {process_msg_block(d, &mut *st_h); });
+    let use_sha_ni = st.use_sha_ni;
+    st.buffer.input(msg, |d: &[u8]| {process_block(d, &mut *st_h, use_sha_ni); });
+}
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+fn process_block(data: &[u8], h: &mut [u32, ..DIGEST_BUF_LEN], use_sha_ni: bool) {
+    if use_sha_ni {
+        process_msg_block_sha_ni(data, h);
+    } else {
+        process_msg_block(data, h);
+    }
+}
+
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+fn process_block(data: &[u8], h: &mut [u32, ..DIGEST_BUF_LEN], _: bool) {
+    process_msg_block(data, h);
+}
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+fn supports_sha_ni() -> bool {
+    util::supports_sha_ni()
+}
+
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+fn supports_sha_ni() -> bool {
+    false
+}
+
+// Reverses the bytes of a 16 byte block so that the message words end up big-endian with the first
+// word in the highest dword, which is the order that the SHA-1 instructions expect.
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+static SHUFFLE_MASK: [u8, ..16] = [15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0];
+
+/// Process a single block using the SHA-1 instructions of the SHA extensions.
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+#[inline(never)]
+fn process_msg_block_sha_ni(data: &[u8], h: &mut [u32, ..DIGEST_BUF_LEN]) {
+    assert!(data.len() == 64);
+
+    unsafe {
+        let hp: *mut u32 = &mut h[0];
+        let dp: *const u8 = &data[0];
+        let mp: *const u8 = &SHUFFLE_MASK[0];
+
+        asm!(
+        "
+            /* Load the state with A in the highest dword of xmm0 and E in the highest dword of
+               xmm1 */
+            movdqu ($0), %xmm0
+            pshufd $$0x1B, %xmm0, %xmm0
+            movd 16($0), %xmm1
+            pslldq $$12, %xmm1
+            movdqu ($2), %xmm7
+
+            /* Rounds 0-3 */
+            movdqu 0($1), %xmm3
+            pshufb %xmm7, %xmm3
+            paddd %xmm3, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1rnds4 $$0, %xmm1, %xmm0
+
+            /* Rounds 4-7 */
+            movdqu 16($1), %xmm4
+            pshufb %xmm7, %xmm4
+            sha1nexte %xmm4, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1rnds4 $$0, %xmm2, %xmm0
+            sha1msg1 %xmm4, %xmm3
+
+            /* Rounds 8-11 */
+            movdqu 32($1), %xmm5
+            pshufb %xmm7, %xmm5
+            sha1nexte %xmm5, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1rnds4 $$0, %xmm1, %xmm0
+            sha1msg1 %xmm5, %xmm4
+            pxor %xmm5, %xmm3
+
+            /* Rounds 12-15 */
+            movdqu 48($1), %xmm6
+            pshufb %xmm7, %xmm6
+            sha1nexte %xmm6, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm6, %xmm3
+            sha1rnds4 $$0, %xmm2, %xmm0
+            sha1msg1 %xmm6, %xmm5
+            pxor %xmm6, %xmm4
+
+            /* Rounds 16-19 */
+            sha1nexte %xmm3, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm3, %xmm4
+            sha1rnds4 $$0, %xmm1, %xmm0
+            sha1msg1 %xmm3, %xmm6
+            pxor %xmm3, %xmm5
+
+            /* Rounds 20-23 */
+            sha1nexte %xmm4, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm4, %xmm5
+            sha1rnds4 $$1, %xmm2, %xmm0
+            sha1msg1 %xmm4, %xmm3
+            pxor %xmm4, %xmm6
+
+            /* Rounds 24-27 */
+            sha1nexte %xmm5, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm5, %xmm6
+            sha1rnds4 $$1, %xmm1, %xmm0
+            sha1msg1 %xmm5, %xmm4
+            pxor %xmm5, %xmm3
+
+            /* Rounds 28-31 */
+            sha1nexte %xmm6, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm6, %xmm3
+            sha1rnds4 $$1, %xmm2, %xmm0
+            sha1msg1 %xmm6, %xmm5
+            pxor %xmm6, %xmm4
+
+            /* Rounds 32-35 */
+            sha1nexte %xmm3, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm3, %xmm4
+            sha1rnds4 $$1, %xmm1, %xmm0
+            sha1msg1 %xmm3, %xmm6
+            pxor %xmm3, %xmm5
+
+            /* Rounds 36-39 */
+            sha1nexte %xmm4, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm4, %xmm5
+            sha1rnds4 $$1, %xmm2, %xmm0
+            sha1msg1 %xmm4, %xmm3
+            pxor %xmm4, %xmm6
+
+            /* Rounds 40-43 */
+            sha1nexte %xmm5, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm5, %xmm6
+            sha1rnds4 $$2, %xmm1, %xmm0
+            sha1msg1 %xmm5, %xmm4
+            pxor %xmm5, %xmm3
+
+            /* Rounds 44-47 */
+            sha1nexte %xmm6, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm6, %xmm3
+            sha1rnds4 $$2, %xmm2, %xmm0
+            sha1msg1 %xmm6, %xmm5
+            pxor %xmm6, %xmm4
+
+            /* Rounds 48-51 */
+            sha1nexte %xmm3, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm3, %xmm4
+            sha1rnds4 $$2, %xmm1, %xmm0
+            sha1msg1 %xmm3, %xmm6
+            pxor %xmm3, %xmm5
+
+            /* Rounds 52-55 */
+            sha1nexte %xmm4, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm4, %xmm5
+            sha1rnds4 $$2, %xmm2, %xmm0
+            sha1msg1 %xmm4, %xmm3
+            pxor %xmm4, %xmm6
+
+            /* Rounds 56-59 */
+            sha1nexte %xmm5, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm5, %xmm6
+            sha1rnds4 $$2, %xmm1, %xmm0
+            sha1msg1 %xmm5, %xmm4
+            pxor %xmm5, %xmm3
+
+            /* Rounds 60-63 */
+            sha1nexte %xmm6, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm6, %xmm3
+            sha1rnds4 $$3, %xmm2, %xmm0
+            sha1msg1 %xmm6, %xmm5
+            pxor %xmm6, %xmm4
+
+            /* Rounds 64-67 */
+            sha1nexte %xmm3, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm3, %xmm4
+            sha1rnds4 $$3, %xmm1, %xmm0
+            sha1msg1 %xmm3, %xmm6
+            pxor %xmm3, %xmm5
+
+            /* Rounds 68-71 */
+            sha1nexte %xmm4, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1msg2 %xmm4, %xmm5
+            sha1rnds4 $$3, %xmm2, %xmm0
+            pxor %xmm4, %xmm6
+
+            /* Rounds 72-75 */
+            sha1nexte %xmm5, %xmm1
+            movdqa %xmm0, %xmm2
+            sha1msg2 %xmm5, %xmm6
+            sha1rnds4 $$3, %xmm1, %xmm0
+
+            /* Rounds 76-79 */
+            sha1nexte %xmm6, %xmm2
+            movdqa %xmm0, %xmm1
+            sha1rnds4 $$3, %xmm2, %xmm0
+
+            /* Add the original state */
+            movd 16($0), %xmm7
+            pslldq $$12, %xmm7
+            sha1nexte %xmm7, %xmm1
+            movdqu ($0), %xmm7
+            pshufd $$0x1B, %xmm7, %xmm7
+            paddd %xmm7, %xmm0
+
+            pshufd $$0x1B, %xmm0, %xmm0
+            movdqu %xmm0, ($0)
+            psrldq $$12, %xmm1
+            movd %xmm1, 16($0)
+        "
+        : // outputs
+        : "r" (hp), "r" (dp), "r" (mp) // inputs
+        : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "memory" // clobbers
+        : "volatile" // options
+        );
+    }
 }
 
 fn process_msg_block(data: &[u8], h: &mut [u32, ..DIGEST_BUF_LEN]) {
@@ -128,16 +370,35 @@ fn circular_shift(bits: u32, word: u32) -> u32 {
     return word << bits as uint | word >> (32u32 - bits) as uint;
 }
 
-fn mk_result(st: &mut Sha1, rs: &mut [u8]) {
+fn add_input_bits(st: &mut Sha1, msg: &[u8], bits: uint) {
+    assert!(bits <= msg.len() * 8);
+    add_input(st, msg.slice_to(bits / 8));
+    let extra = bits % 8;
+    let last = if extra == 0 { 0 } else { msg[bits / 8] };
+    finish(st, last, extra);
+}
+
+// Pad and process the final block. The message may end with a partial byte containing the given
+// number of bits from the top of last.
+fn finish(st: &mut Sha1, last: u8, bits: uint) {
     if !st.computed {
+        // Only whole bytes have been counted so far, so adding the extra bits can't carry.
+        let length_bits = st.length_bits | bits as u64;
         let st_h = &mut st.h;
-        st.buffer.standard_padding(8, |d: &[u8]| { process_msg_block(d, &mut *st_h) });
-        write_u32_be(st.buffer.next(4), (st.length_bits >> 32) as u32 );
-        write_u32_be(st.buffer.next(4), st.length_bits as u32);
-        process_msg_block(st.buffer.full_buffer(), st_h);
+        let use_sha_ni = st.use_sha_ni;
+        st.buffer.standard_padding_bits(8, last, bits, |d: &[u8]| {
+            process_block(d, &mut *st_h, use_sha_ni)
+        });
+        write_u32_be(st.buffer.next(4), (length_bits >> 32) as u32 );
+        write_u32_be(st.buffer.next(4), length_bits as u32);
+        process_block(st.buffer.full_buffer(), st_h, use_sha_ni);
 
         st.computed = true;
     }
+}
+
+fn mk_result(st: &mut Sha1, rs: &mut [u8]) {
+    finish(st, 0, 0);
 
     write_u32_be(rs.mut_slice(0, 4), st.h[0]);
     write_u32_be(rs.mut_slice(4, 8), st.h[1]);
@@ -154,6 +415,7 @@ impl Sha1 {
             length_bits: 0u64,
             buffer: FixedBuffer64::new(),
             computed: false,
+            use_sha_ni: supports_sha_ni(),
         };
         st.reset();
         return st;
@@ -177,12 +439,59 @@ impl Digest for Sha1 {
     fn block_size(&self) -> uint { 64 }
 }
 
+impl DigestBits for Sha1 {
+    fn input_bits(&mut self, msg: &[u8], bits: uint) { add_input_bits(self, msg, bits); }
+}
+
+// The state is exported as the 5 state words, the length, the computed flag and the buffer.
+static STATE_LEN: uint = 20 + 8 + 1 + 65;
+
+impl DigestState for Sha1 {
+    fn export_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(STATE_LEN);
+        for h in self.h.iter() {
+            push_u32_be(&mut state, *h);
+        }
+        push_u64_be(&mut state, self.length_bits);
+        state.push(self.computed as u8);
+        push_fixed_buffer(&mut state, &self.buffer);
+        return state;
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        if state.len() != STATE_LEN {
+            return Err(InvalidStateLength);
+        }
+        let computed = match read_bool(state[28]) {
+            Some(x) => x,
+            None => return Err(InvalidState)
+        };
+        if !read_fixed_buffer(&mut self.buffer, state.slice_from(29)) {
+            return Err(InvalidState);
+        }
+        for i in range(0u, DIGEST_BUF_LEN) {
+            self.h[i] = read_u32_be(state.slice(i * 4, i * 4 + 4));
+        }
+        self.length_bits = read_u64_be(state.slice(20, 28));
+        self.computed = computed;
+        return Ok(());
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use cryptoutil::test::test_digest_1million_random;
-    use digest::Digest;
+    use cryptoutil::test::{test_digest_1million_random, test_digest_state,
+        test_digest_clone};
+    use digest::{Digest, DigestBits};
     use sha1::Sha1;
 
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use std::rand::{Rng, IsaacRng};
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use util;
+
     #[deriving(Clone)]
     struct Test {
         input: &'static str,
@@ -280,6 +589,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_portable() {
+        // Make sure that the portable implementation is checked even if the processor supports
+        // the SHA extensions.
+        let mut sh = Sha1::new();
+        sh.use_sha_ni = false;
+        sh.input_str("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+        assert!(sh.result_str().as_slice() == "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+
+        let mut sh = Sha1::new();
+        sh.use_sha_ni = false;
+        test_digest_1million_random(
+            &mut sh,
+            64,
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f");
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_sha_ni_matches_portable() {
+        if !util::supports_sha_ni() {
+            return;
+        }
+
+        let mut rng = IsaacRng::new_unseeded();
+        let mut out_portable = [0u8, ..20];
+        let mut out_sha_ni = [0u8, ..20];
+        for len in range(0u, 300) {
+            let data: Vec<u8> = rng.gen_iter::<u8>().take(len).collect();
+
+            let mut portable = Sha1::new();
+            portable.use_sha_ni = false;
+            portable.input(data.as_slice());
+            portable.result(out_portable);
+
+            let mut sha_ni = Sha1::new();
+            sha_ni.use_sha_ni = true;
+            sha_ni.input(data.as_slice());
+            sha_ni.result(out_sha_ni);
+
+            assert!(out_portable == out_sha_ni);
+        }
+    }
+
+    #[test]
+    fn test_state_sha1() {
+        test_digest_state(&mut Sha1::new(), &mut Sha1::new());
+    }
+
+    #[test]
+    fn test_bits() {
+        // The first vector is from RFC 6234 (NIST bit-oriented message). The others end with a
+        // partial byte just before and just after the point where the length no longer fits in
+        // the final block.
+        let data = Vec::from_fn(200, |i| i as u8);
+        let tests = [
+            (vec![0x98u8], 5u, "29826b003b906e660eff4027ce98af3531ac75ba"),
+            (data.clone(), 55 * 8 + 7, "11aa5ed10bf4815191a1f8ed9597c550b906447b"),
+            (data.clone(), 56 * 8 + 3, "984942b7c86b9f4428e146a514b1989dcab8ecb5"),
+            (data.clone(), 64 * 8, "c6138d514ffa2135bfce0ed0b8fac65669917ec7")
+        ];
+
+        let mut sh = Sha1::new();
+        for &(ref input, bits, expected) in tests.iter() {
+            sh.reset();
+            sh.input_bits(input.as_slice(), bits);
+            assert_eq!(sh.result_str().as_slice(), expected);
+        }
+    }
+
+    #[test]
+    fn test_clone_sha1() {
+        test_digest_clone(&mut Sha1::new());
+    }
+
     #[test]
     fn test_1million_random_sha1() {
         let mut sh = Sha1::new();
@@ -326,4 +711,15 @@ mod bench {
         bh.bytes = bytes.len() as u64;
     }
 
+    #[bench]
+    pub fn sha1_portable_64k(bh: & mut Bencher) {
+        let mut sh = Sha1::new();
+        sh.use_sha_ni = false;
+        let bytes = [1u8, ..65536];
+        bh.iter( || {
+            sh.input(bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
 }
@@ -9,13 +9,21 @@
 // except according to those terms.
 
 use std::iter::range_step;
+use std::slice::bytes::copy_memory;
 
-use cryptoutil::{write_u64_be, write_u32_be, read_u64v_be, read_u32v_be, add_bytes_to_bits,
+use cryptoutil::{write_u64_be, write_u32_be, read_u64v_be, read_u32v_be, read_u32_be, read_u64_be,
+    push_u32_be, push_u64_be, push_fixed_buffer, read_fixed_buffer, read_bool, add_bytes_to_bits,
     add_bytes_to_bits_tuple, FixedBuffer, FixedBuffer128, FixedBuffer64, StandardPadding};
-use digest::Digest;
+use digest::{Digest, DigestBits, DigestState, DigestStateError, InvalidStateLength, InvalidState};
+use simd::{u32x4, u32x8};
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+use util;
 
 // A structure that represents that state of a digest computation for the SHA-2 512 family
 // of digest functions
+#[deriving(Clone)]
 struct Engine512State {
     h0: u64,
     h1: u64,
@@ -180,6 +188,7 @@ static K64: [u64, ..80] = [
 
 // A structure that keeps track of the state of the Sha-512 operation and contains the logic
 // necessary to perform the final calculations.
+#[deriving(Clone)]
 struct Engine512 {
     length_bits: (u64, u64),
     buffer: FixedBuffer128,
@@ -212,27 +221,82 @@ impl Engine512 {
         self.buffer.input(input, |input: &[u8]| { self_state.process_block(input) });
     }
 
+    fn input_bits(&mut self, input: &[u8], bits: uint) {
+        assert!(bits <= input.len() * 8);
+        self.input(input.slice_to(bits / 8));
+        let extra = bits % 8;
+        let last = if extra == 0 { 0 } else { input[bits / 8] };
+        self.finish_bits(last, extra);
+    }
+
     fn finish(&mut self) {
+        self.finish_bits(0, 0);
+    }
+
+    // The message may end with a partial byte containing the given number of bits from the top of
+    // last.
+    fn finish_bits(&mut self, last: u8, bits: uint) {
         if self.finished {
             return;
         }
 
         let self_state = &mut self.state;
-        self.buffer.standard_padding(16, |input: &[u8]| { self_state.process_block(input) });
+        self.buffer.standard_padding_bits(16, last, bits, |input: &[u8]| {
+            self_state.process_block(input)
+        });
         match self.length_bits {
             (hi, low) => {
+                // Only whole bytes have been counted so far, so adding the extra bits can't carry.
                 write_u64_be(self.buffer.next(8), hi);
-                write_u64_be(self.buffer.next(8), low);
+                write_u64_be(self.buffer.next(8), low | bits as u64);
             }
         }
         self_state.process_block(self.buffer.full_buffer());
 
         self.finished = true;
     }
+
+    // The state is exported as the 8 state words, the 128 bit length, the finished flag and the
+    // buffer.
+    fn export_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(ENGINE512_STATE_LEN);
+        let s = &self.state;
+        for h in [s.h0, s.h1, s.h2, s.h3, s.h4, s.h5, s.h6, s.h7].iter() {
+            push_u64_be(&mut state, *h);
+        }
+        let (hi, low) = self.length_bits;
+        push_u64_be(&mut state, hi);
+        push_u64_be(&mut state, low);
+        state.push(self.finished as u8);
+        push_fixed_buffer(&mut state, &self.buffer);
+        return state;
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        if state.len() != ENGINE512_STATE_LEN {
+            return Err(InvalidStateLength);
+        }
+        let finished = match read_bool(state[80]) {
+            Some(x) => x,
+            None => return Err(InvalidState)
+        };
+        if !read_fixed_buffer(&mut self.buffer, state.slice_from(81)) {
+            return Err(InvalidState);
+        }
+        let mut h = [0u64, ..8];
+        read_u64v_be(h, state.slice(0, 64));
+        self.state.reset(&h);
+        self.length_bits = (read_u64_be(state.slice(64, 72)), read_u64_be(state.slice(72, 80)));
+        self.finished = finished;
+        return Ok(());
+    }
 }
 
+static ENGINE512_STATE_LEN: uint = 64 + 16 + 1 + 129;
+
 
 /// The SHA-512 hash algorithm
+#[deriving(Clone)]
 pub struct Sha512 {
     engine: Engine512
 }
@@ -275,6 +339,22 @@ impl Digest for Sha512 {
     fn block_size(&self) -> uint { 128 }
 }
 
+impl DigestBits for Sha512 {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha512 {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
 static H512: [u64, ..8] = [
     0x6a09e667f3bcc908,
     0xbb67ae8584caa73b,
@@ -288,6 +368,7 @@ static H512: [u64, ..8] = [
 
 
 /// The SHA-384 hash algorithm
+#[deriving(Clone)]
 pub struct Sha384 {
     engine: Engine512
 }
@@ -328,6 +409,22 @@ impl Digest for Sha384 {
     fn block_size(&self) -> uint { 128 }
 }
 
+impl DigestBits for Sha384 {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha384 {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
 static H384: [u64, ..8] = [
     0xcbbb9d5dc1059ed8,
     0x629a292a367cd507,
@@ -341,6 +438,7 @@ static H384: [u64, ..8] = [
 
 
 /// The SHA-512 hash algorithm with digest truncated to 256 bits
+#[deriving(Clone)]
 pub struct Sha512Trunc256 {
     engine: Engine512
 }
@@ -379,6 +477,22 @@ impl Digest for Sha512Trunc256 {
     fn block_size(&self) -> uint { 128 }
 }
 
+impl DigestBits for Sha512Trunc256 {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha512Trunc256 {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
 static H512_TRUNC_256: [u64, ..8] = [
     0x22312194fc2bf72c,
     0x9f555fa3c84c64c2,
@@ -392,6 +506,7 @@ static H512_TRUNC_256: [u64, ..8] = [
 
 
 /// The SHA-512 hash algorithm with digest truncated to 224 bits
+#[deriving(Clone)]
 pub struct Sha512Trunc224 {
     engine: Engine512
 }
@@ -430,6 +545,22 @@ impl Digest for Sha512Trunc224 {
     fn block_size(&self) -> uint { 128 }
 }
 
+impl DigestBits for Sha512Trunc224 {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha512Trunc224 {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
 static H512_TRUNC_224: [u64, ..8] = [
     0x8c3d37c819544da2,
     0x73e1996689dcd4d6,
@@ -442,8 +573,103 @@ static H512_TRUNC_224: [u64, ..8] = [
 ];
 
 
+/// The SHA-512/t hash algorithm - SHA-512 with a distinct initial value and the digest truncated to
+/// t bits. Sha512Trunc256 and Sha512Trunc224 are the same as SHA-512/256 and SHA-512/224 but use
+/// precomputed initial values.
+pub struct Sha512Trunc {
+    engine: Engine512,
+    h: [u64, ..8],
+    t: uint
+}
+
+impl Clone for Sha512Trunc {
+    fn clone(&self) -> Sha512Trunc { *self }
+}
+
+impl Sha512Trunc {
+    /**
+     * Construct an new instance of a SHA-512/t digest. t must be less than 512 and may not be 384.
+     */
+    pub fn new(t: uint) -> Sha512Trunc {
+        let h = sha512_trunc_iv(t);
+        Sha512Trunc {
+            engine: Engine512::new(&h),
+            h: h,
+            t: t
+        }
+    }
+}
+
+// Compute the initial value for SHA-512/t. This is the SHA-512 digest of the string "SHA-512/t",
+// using the SHA-512 initial value with each word xored with 0xa5a5a5a5a5a5a5a5 (FIPS 180-4,
+// section 5.3.6).
+fn sha512_trunc_iv(t: uint) -> [u64, ..8] {
+    assert!(t > 0 && t < 512 && t != 384);
+
+    let mut h = H512;
+    for x in h.mut_iter() {
+        *x = *x ^ 0xa5a5a5a5a5a5a5a5;
+    }
+    let mut engine = Engine512::new(&h);
+    engine.input(format!("SHA-512/{}", t).as_bytes());
+    engine.finish();
+
+    let s = &engine.state;
+    return [s.h0, s.h1, s.h2, s.h3, s.h4, s.h5, s.h6, s.h7];
+}
+
+impl Digest for Sha512Trunc {
+    fn input(&mut self, d: &[u8]) {
+        self.engine.input(d);
+    }
+
+    fn result(&mut self, out: &mut [u8]) {
+        self.engine.finish();
+
+        let s = &self.engine.state;
+        let mut full = [0u8, ..64];
+        let h = [s.h0, s.h1, s.h2, s.h3, s.h4, s.h5, s.h6, s.h7];
+        for (chunk, x) in full.mut_chunks(8).zip(h.iter()) {
+            write_u64_be(chunk, *x);
+        }
+
+        // If t isn't a multiple of 8, the unused bits of the final byte are cleared.
+        let len = self.output_bytes();
+        copy_memory(out.mut_slice_to(len), full.slice_to(len));
+        if self.t % 8 != 0 {
+            out[len - 1] &= !(0xffu8 >> (self.t % 8));
+        }
+    }
+
+    fn reset(&mut self) {
+        self.engine.reset(&self.h);
+    }
+
+    fn output_bits(&self) -> uint { self.t }
+
+    fn block_size(&self) -> uint { 128 }
+}
+
+impl DigestBits for Sha512Trunc {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha512Trunc {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
+
 // A structure that represents that state of a digest computation for the SHA-2 512 family of digest
 // functions
+#[deriving(Clone)]
 struct Engine256State {
     h0: u32,
     h1: u32,
@@ -579,6 +805,275 @@ impl Engine256State {
         self.h6 += g;
         self.h7 += h;
     }
+
+    /// Process a single block using the SHA-256 instructions of the SHA extensions.
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[inline(never)]
+    fn process_block_sha_ni(&mut self, data: &[u8]) {
+        assert!(data.len() == 64);
+
+        let mut h = [self.h0, self.h1, self.h2, self.h3, self.h4, self.h5, self.h6, self.h7];
+
+        unsafe {
+            let hp: *mut u32 = &mut h[0];
+            let dp: *const u8 = &data[0];
+            let mp: *const u8 = &SHUFFLE_MASK_256[0];
+            let kp: *const u32 = &K32[0];
+
+            asm!(
+            "
+                /* Rearrange the state into ABEF and CDGH */
+                movdqu ($0), %xmm1
+                movdqu 16($0), %xmm2
+                pshufd $$0xB1, %xmm1, %xmm1
+                pshufd $$0x1B, %xmm2, %xmm2
+                movdqa %xmm1, %xmm7
+                palignr $$8, %xmm2, %xmm1
+                pblendw $$0xF0, %xmm7, %xmm2
+                movdqu ($2), %xmm7
+
+                /* Rounds 0-3 */
+                movdqu 0($1), %xmm3
+                pshufb %xmm7, %xmm3
+                movdqu 0($3), %xmm0
+                paddd %xmm3, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+
+                /* Rounds 4-7 */
+                movdqu 16($1), %xmm4
+                pshufb %xmm7, %xmm4
+                movdqu 16($3), %xmm0
+                paddd %xmm4, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm4, %xmm3
+
+                /* Rounds 8-11 */
+                movdqu 32($1), %xmm5
+                pshufb %xmm7, %xmm5
+                movdqu 32($3), %xmm0
+                paddd %xmm5, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm5, %xmm4
+
+                /* Rounds 12-15 */
+                movdqu 48($1), %xmm6
+                pshufb %xmm7, %xmm6
+                movdqu 48($3), %xmm0
+                paddd %xmm6, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm6, %xmm7
+                palignr $$4, %xmm5, %xmm7
+                paddd %xmm7, %xmm3
+                sha256msg2 %xmm6, %xmm3
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm6, %xmm5
+
+                /* Rounds 16-19 */
+                movdqu 64($3), %xmm0
+                paddd %xmm3, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm3, %xmm7
+                palignr $$4, %xmm6, %xmm7
+                paddd %xmm7, %xmm4
+                sha256msg2 %xmm3, %xmm4
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm3, %xmm6
+
+                /* Rounds 20-23 */
+                movdqu 80($3), %xmm0
+                paddd %xmm4, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm4, %xmm7
+                palignr $$4, %xmm3, %xmm7
+                paddd %xmm7, %xmm5
+                sha256msg2 %xmm4, %xmm5
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm4, %xmm3
+
+                /* Rounds 24-27 */
+                movdqu 96($3), %xmm0
+                paddd %xmm5, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm5, %xmm7
+                palignr $$4, %xmm4, %xmm7
+                paddd %xmm7, %xmm6
+                sha256msg2 %xmm5, %xmm6
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm5, %xmm4
+
+                /* Rounds 28-31 */
+                movdqu 112($3), %xmm0
+                paddd %xmm6, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm6, %xmm7
+                palignr $$4, %xmm5, %xmm7
+                paddd %xmm7, %xmm3
+                sha256msg2 %xmm6, %xmm3
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm6, %xmm5
+
+                /* Rounds 32-35 */
+                movdqu 128($3), %xmm0
+                paddd %xmm3, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm3, %xmm7
+                palignr $$4, %xmm6, %xmm7
+                paddd %xmm7, %xmm4
+                sha256msg2 %xmm3, %xmm4
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm3, %xmm6
+
+                /* Rounds 36-39 */
+                movdqu 144($3), %xmm0
+                paddd %xmm4, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm4, %xmm7
+                palignr $$4, %xmm3, %xmm7
+                paddd %xmm7, %xmm5
+                sha256msg2 %xmm4, %xmm5
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm4, %xmm3
+
+                /* Rounds 40-43 */
+                movdqu 160($3), %xmm0
+                paddd %xmm5, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm5, %xmm7
+                palignr $$4, %xmm4, %xmm7
+                paddd %xmm7, %xmm6
+                sha256msg2 %xmm5, %xmm6
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm5, %xmm4
+
+                /* Rounds 44-47 */
+                movdqu 176($3), %xmm0
+                paddd %xmm6, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm6, %xmm7
+                palignr $$4, %xmm5, %xmm7
+                paddd %xmm7, %xmm3
+                sha256msg2 %xmm6, %xmm3
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm6, %xmm5
+
+                /* Rounds 48-51 */
+                movdqu 192($3), %xmm0
+                paddd %xmm3, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm3, %xmm7
+                palignr $$4, %xmm6, %xmm7
+                paddd %xmm7, %xmm4
+                sha256msg2 %xmm3, %xmm4
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+                sha256msg1 %xmm3, %xmm6
+
+                /* Rounds 52-55 */
+                movdqu 208($3), %xmm0
+                paddd %xmm4, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm4, %xmm7
+                palignr $$4, %xmm3, %xmm7
+                paddd %xmm7, %xmm5
+                sha256msg2 %xmm4, %xmm5
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+
+                /* Rounds 56-59 */
+                movdqu 224($3), %xmm0
+                paddd %xmm5, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                movdqa %xmm5, %xmm7
+                palignr $$4, %xmm4, %xmm7
+                paddd %xmm7, %xmm6
+                sha256msg2 %xmm5, %xmm6
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+
+                /* Rounds 60-63 */
+                movdqu 240($3), %xmm0
+                paddd %xmm6, %xmm0
+                sha256rnds2 %xmm0, %xmm1, %xmm2
+                pshufd $$0x0E, %xmm0, %xmm0
+                sha256rnds2 %xmm0, %xmm2, %xmm1
+
+                /* Add the original state */
+                movdqu ($0), %xmm3
+                movdqu 16($0), %xmm4
+                pshufd $$0xB1, %xmm3, %xmm3
+                pshufd $$0x1B, %xmm4, %xmm4
+                movdqa %xmm3, %xmm5
+                palignr $$8, %xmm4, %xmm3
+                pblendw $$0xF0, %xmm5, %xmm4
+                paddd %xmm3, %xmm1
+                paddd %xmm4, %xmm2
+
+                /* Rearrange the state back into its normal order */
+                pshufd $$0x1B, %xmm1, %xmm1
+                pshufd $$0xB1, %xmm2, %xmm2
+                movdqa %xmm1, %xmm7
+                pblendw $$0xF0, %xmm2, %xmm1
+                palignr $$8, %xmm7, %xmm2
+                movdqu %xmm1, ($0)
+                movdqu %xmm2, 16($0)
+            "
+            : // outputs
+            : "r" (hp), "r" (dp), "r" (mp), "r" (kp) // inputs
+            : "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "memory" // clobbers
+            : "volatile" // options
+            );
+        }
+
+        self.reset(&h);
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    fn process_block_dispatch(&mut self, data: &[u8], use_sha_ni: bool) {
+        if use_sha_ni {
+            self.process_block_sha_ni(data);
+        } else {
+            self.process_block(data);
+        }
+    }
+
+    #[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+    fn process_block_dispatch(&mut self, data: &[u8], _: bool) {
+        self.process_block(data);
+    }
+}
+
+// Byte swaps each of the 4 message words in a 16 byte block, which is the order that the SHA-256
+// instructions expect.
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+static SHUFFLE_MASK_256: [u8, ..16] = [3, 2, 1, 0, 7, 6, 5, 4, 11, 10, 9, 8, 15, 14, 13, 12];
+
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+fn supports_sha_ni() -> bool {
+    util::supports_sha_ni()
+}
+
+#[cfg(not(target_arch = "x86"), not(target_arch = "x86_64"))]
+fn supports_sha_ni() -> bool {
+    false
 }
 
 static K32: [u32, ..64] = [
@@ -603,11 +1098,13 @@ static K32: [u32, ..64] = [
 
 // A structure that keeps track of the state of the Sha-256 operation and contains the logic
 // necessary to perform the final calculations.
+#[deriving(Clone)]
 struct Engine256 {
     length_bits: u64,
     buffer: FixedBuffer64,
     state: Engine256State,
     finished: bool,
+    use_sha_ni: bool,
 }
 
 impl Engine256 {
@@ -616,7 +1113,8 @@ impl Engine256 {
             length_bits: 0,
             buffer: FixedBuffer64::new(),
             state: Engine256State::new(h),
-            finished: false
+            finished: false,
+            use_sha_ni: supports_sha_ni()
         }
     }
 
@@ -632,26 +1130,83 @@ impl Engine256 {
         // Assumes that input.len() can be converted to u64 without overflow
         self.length_bits = add_bytes_to_bits(self.length_bits, input.len() as u64);
         let self_state = &mut self.state;
-        self.buffer.input(input, |input: &[u8]| { self_state.process_block(input) });
+        let use_sha_ni = self.use_sha_ni;
+        self.buffer.input(input, |input: &[u8]| {
+            self_state.process_block_dispatch(input, use_sha_ni)
+        });
+    }
+
+    fn input_bits(&mut self, input: &[u8], bits: uint) {
+        assert!(bits <= input.len() * 8);
+        self.input(input.slice_to(bits / 8));
+        let extra = bits % 8;
+        let last = if extra == 0 { 0 } else { input[bits / 8] };
+        self.finish_bits(last, extra);
     }
 
     fn finish(&mut self) {
+        self.finish_bits(0, 0);
+    }
+
+    // The message may end with a partial byte containing the given number of bits from the top of
+    // last.
+    fn finish_bits(&mut self, last: u8, bits: uint) {
         if self.finished {
             return;
         }
 
+        // Only whole bytes have been counted so far, so adding the extra bits can't carry.
+        let length_bits = self.length_bits | bits as u64;
         let self_state = &mut self.state;
-        self.buffer.standard_padding(8, |input: &[u8]| { self_state.process_block(input) });
-        write_u32_be(self.buffer.next(4), (self.length_bits >> 32) as u32 );
-        write_u32_be(self.buffer.next(4), self.length_bits as u32);
-        self_state.process_block(self.buffer.full_buffer());
+        let use_sha_ni = self.use_sha_ni;
+        self.buffer.standard_padding_bits(8, last, bits, |input: &[u8]| {
+            self_state.process_block_dispatch(input, use_sha_ni)
+        });
+        write_u32_be(self.buffer.next(4), (length_bits >> 32) as u32 );
+        write_u32_be(self.buffer.next(4), length_bits as u32);
+        self_state.process_block_dispatch(self.buffer.full_buffer(), use_sha_ni);
 
         self.finished = true;
     }
+
+    // The state is exported as the 8 state words, the length, the finished flag and the buffer.
+    fn export_state(&self) -> Vec<u8> {
+        let mut state = Vec::with_capacity(ENGINE256_STATE_LEN);
+        let s = &self.state;
+        for h in [s.h0, s.h1, s.h2, s.h3, s.h4, s.h5, s.h6, s.h7].iter() {
+            push_u32_be(&mut state, *h);
+        }
+        push_u64_be(&mut state, self.length_bits);
+        state.push(self.finished as u8);
+        push_fixed_buffer(&mut state, &self.buffer);
+        return state;
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        if state.len() != ENGINE256_STATE_LEN {
+            return Err(InvalidStateLength);
+        }
+        let finished = match read_bool(state[40]) {
+            Some(x) => x,
+            None => return Err(InvalidState)
+        };
+        if !read_fixed_buffer(&mut self.buffer, state.slice_from(41)) {
+            return Err(InvalidState);
+        }
+        let mut h = [0u32, ..8];
+        read_u32v_be(h, state.slice(0, 32));
+        self.state.reset(&h);
+        self.length_bits = read_u64_be(state.slice(32, 40));
+        self.finished = finished;
+        return Ok(());
+    }
 }
 
+static ENGINE256_STATE_LEN: uint = 32 + 8 + 1 + 65;
+
 
 /// The SHA-256 hash algorithm
+#[deriving(Clone)]
 pub struct Sha256 {
     engine: Engine256
 }
@@ -694,6 +1249,22 @@ impl Digest for Sha256 {
     fn block_size(&self) -> uint { 64 }
 }
 
+impl DigestBits for Sha256 {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha256 {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
 static H256: [u32, ..8] = [
     0x6a09e667,
     0xbb67ae85,
@@ -707,6 +1278,7 @@ static H256: [u32, ..8] = [
 
 
 /// The SHA-224 hash algorithm
+#[deriving(Clone)]
 pub struct Sha224 {
     engine: Engine256
 }
@@ -747,6 +1319,22 @@ impl Digest for Sha224 {
     fn block_size(&self) -> uint { 64 }
 }
 
+impl DigestBits for Sha224 {
+    fn input_bits(&mut self, d: &[u8], bits: uint) {
+        self.engine.input_bits(d, bits);
+    }
+}
+
+impl DigestState for Sha224 {
+    fn export_state(&self) -> Vec<u8> {
+        self.engine.export_state()
+    }
+
+    fn import_state(&mut self, state: &[u8]) -> Result<(), DigestStateError> {
+        self.engine.import_state(state)
+    }
+}
+
 static H224: [u32, ..8] = [
     0xc1059ed8,
     0x367cd507,
@@ -759,11 +1347,156 @@ static H224: [u32, ..8] = [
 ];
 
 
+// Generates a function that computes the SHA-256 digests of several independent messages at once.
+// Each message is processed in its own lane of the vector type, so the messages don't need to be
+// the same length - once a message has run out of blocks, its lane keeps running but the result is
+// masked out of the state.
+macro_rules! sha256_multi_lane(
+    ($name:ident, $T:ident, $lanes:expr) => (
+        pub fn $name(inputs: [&[u8], ..$lanes]) -> [[u8, ..32], ..$lanes] {
+            fn ch(x: $T, y: $T, z: $T) -> $T {
+                z ^ (x & (y ^ z))
+            }
+
+            fn maj(x: $T, y: $T, z: $T) -> $T {
+                (x & y) ^ (x & z) ^ (y & z)
+            }
+
+            // rotr 2, 13 and 22
+            fn sum0(x: $T) -> $T {
+                x.rotate_left(30) ^ x.rotate_left(19) ^ x.rotate_left(10)
+            }
+
+            // rotr 6, 11 and 25
+            fn sum1(x: $T) -> $T {
+                x.rotate_left(26) ^ x.rotate_left(21) ^ x.rotate_left(7)
+            }
+
+            // rotr 7 and 18, shr 3
+            fn sigma0(x: $T) -> $T {
+                x.rotate_left(25) ^ x.rotate_left(14) ^ (x >> $T::splat(3))
+            }
+
+            // rotr 17 and 19, shr 10
+            fn sigma1(x: $T) -> $T {
+                x.rotate_left(15) ^ x.rotate_left(13) ^ (x >> $T::splat(10))
+            }
+
+            // Pad the end of each message into one or two final blocks
+            let mut tails = [[0u8, ..128], ..$lanes];
+            let mut full_blocks = [0u, ..$lanes];
+            let mut total_blocks = [0u, ..$lanes];
+            for i in range(0u, $lanes) {
+                let input = inputs[i];
+                full_blocks[i] = input.len() / 64;
+                let rest = input.slice_from(full_blocks[i] * 64);
+                copy_memory(tails[i].mut_slice_to(rest.len()), rest);
+                tails[i][rest.len()] = 0x80;
+                let tail_len = if rest.len() < 56 { 64 } else { 128 };
+                write_u64_be(tails[i].mut_slice(tail_len - 8, tail_len), (input.len() as u64) << 3);
+                total_blocks[i] = full_blocks[i] + tail_len / 64;
+            }
+            let max_blocks = *total_blocks.iter().max().unwrap();
+
+            let mut state = [$T::splat(0), ..8];
+            for i in range(0u, 8) {
+                state[i] = $T::splat(H256[i]);
+            }
+
+            let zero_block = [0u8, ..64];
+            let mut w = [$T::splat(0), ..64];
+            for n in range(0u, max_blocks) {
+                let mut blocks = [zero_block.as_slice(), ..$lanes];
+                let mut active = [0u32, ..$lanes];
+                for i in range(0u, $lanes) {
+                    if n < full_blocks[i] {
+                        blocks[i] = inputs[i].slice(n * 64, n * 64 + 64);
+                    } else if n < total_blocks[i] {
+                        let t = n - full_blocks[i];
+                        blocks[i] = tails[i].slice(t * 64, t * 64 + 64);
+                    }
+                    if n < total_blocks[i] {
+                        active[i] = 0xffffffff;
+                    }
+                }
+
+                for t in range(0u, 16) {
+                    let mut words = [0u32, ..$lanes];
+                    for i in range(0u, $lanes) {
+                        words[i] = read_u32_be(blocks[i].slice(t * 4, t * 4 + 4));
+                    }
+                    w[t] = $T::from_slice(words);
+                }
+                for t in range(16u, 64) {
+                    w[t] = sigma1(w[t - 2]) + w[t - 7] + sigma0(w[t - 15]) + w[t - 16];
+                }
+
+                let mut a = state[0];
+                let mut b = state[1];
+                let mut c = state[2];
+                let mut d = state[3];
+                let mut e = state[4];
+                let mut f = state[5];
+                let mut g = state[6];
+                let mut h = state[7];
+
+                for t in range(0u, 64) {
+                    let t1 = h + sum1(e) + ch(e, f, g) + $T::splat(K32[t]) + w[t];
+                    let t2 = sum0(a) + maj(a, b, c);
+                    h = g;
+                    g = f;
+                    f = e;
+                    e = d + t1;
+                    d = c;
+                    c = b;
+                    b = a;
+                    a = t1 + t2;
+                }
+
+                // Only update the lanes whose messages haven't been fully processed yet
+                let mask = $T::from_slice(active);
+                let words = [a, b, c, d, e, f, g, h];
+                for i in range(0u, 8) {
+                    let next = state[i] + words[i];
+                    state[i] = state[i] ^ ((next ^ state[i]) & mask);
+                }
+            }
+
+            let mut out = [[0u8, ..32], ..$lanes];
+            for i in range(0u, 8) {
+                let lanes = state[i].to_array();
+                for j in range(0u, $lanes) {
+                    write_u32_be(out[j].mut_slice(i * 4, i * 4 + 4), lanes[j]);
+                }
+            }
+            out
+        }
+    )
+)
+
+/// Compute the SHA-256 digests of 4 independent messages at once using 4 lane SIMD vectors. The
+/// messages may be of different lengths. Each digest is identical to the one computed by Sha256.
+sha256_multi_lane!(sha256_x4, u32x4, 4)
+
+/// Compute the SHA-256 digests of 8 independent messages at once using 8 lane SIMD vectors. The
+/// messages may be of different lengths. Each digest is identical to the one computed by Sha256.
+sha256_multi_lane!(sha256_x8, u32x8, 8)
+
+
 #[cfg(test)]
 mod tests {
-    use cryptoutil::test::test_digest_1million_random;
-    use digest::Digest;
-    use sha2::{Sha512, Sha384, Sha512Trunc256, Sha512Trunc224, Sha256, Sha224};
+    use cryptoutil::test::{test_digest_1million_random, test_digest_state,
+        test_digest_clone};
+    use digest::{Digest, DigestBits};
+    use sha2::{Sha512, Sha384, Sha512Trunc256, Sha512Trunc224, Sha512Trunc, Sha256, Sha224,
+        sha256_x4, sha256_x8, sha512_trunc_iv, H512_TRUNC_256, H512_TRUNC_224};
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use std::rand::{Rng, IsaacRng};
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    use util;
 
     struct Test {
         input: &'static str,
@@ -921,6 +1654,11 @@ mod tests {
         let mut sh = box Sha256::new();
 
         test_hash(&mut *sh, tests.as_slice());
+
+        // Make sure that the portable implementation is checked even if the processor supports
+        // the SHA extensions.
+        sh.engine.use_sha_ni = false;
+        test_hash(&mut *sh, tests.as_slice());
     }
 
     #[test]
@@ -946,6 +1684,172 @@ mod tests {
         let mut sh = box Sha224::new();
 
         test_hash(&mut *sh, tests.as_slice());
+
+        sh.engine.use_sha_ni = false;
+        test_hash(&mut *sh, tests.as_slice());
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_sha_ni_matches_portable() {
+        if !util::supports_sha_ni() {
+            return;
+        }
+
+        let mut rng = IsaacRng::new_unseeded();
+        let mut out_portable = [0u8, ..32];
+        let mut out_sha_ni = [0u8, ..32];
+        for len in range(0u, 300) {
+            let data: Vec<u8> = rng.gen_iter::<u8>().take(len).collect();
+
+            let mut portable = Sha256::new();
+            portable.engine.use_sha_ni = false;
+            portable.input(data.as_slice());
+            portable.result(out_portable);
+
+            let mut sha_ni = Sha256::new();
+            sha_ni.engine.use_sha_ni = true;
+            sha_ni.input(data.as_slice());
+            sha_ni.result(out_sha_ni);
+
+            assert!(out_portable == out_sha_ni);
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        test_digest_state(&mut Sha512::new(), &mut Sha512::new());
+        test_digest_state(&mut Sha384::new(), &mut Sha384::new());
+        test_digest_state(&mut Sha512Trunc256::new(), &mut Sha512Trunc256::new());
+        test_digest_state(&mut Sha512Trunc224::new(), &mut Sha512Trunc224::new());
+        test_digest_state(&mut Sha256::new(), &mut Sha256::new());
+        test_digest_state(&mut Sha224::new(), &mut Sha224::new());
+        test_digest_state(&mut Sha512Trunc::new(200), &mut Sha512Trunc::new(200));
+    }
+
+    fn test_bits_hash<D: Digest + DigestBits>(sh: &mut D, tests: &[(Vec<u8>, uint, &str)]) {
+        for &(ref input, bits, expected) in tests.iter() {
+            sh.reset();
+            sh.input_bits(input.as_slice(), bits);
+            assert_eq!(sh.result_str().as_slice(), expected);
+        }
+    }
+
+    #[test]
+    fn test_bits() {
+        // The 5 bit vectors are from RFC 6234 (NIST bit-oriented messages). The others end with a
+        // partial byte just before and just after the point where the length no longer fits in
+        // the final block.
+        let data = Vec::from_fn(200, |i| i as u8);
+
+        test_bits_hash(&mut Sha256::new(), &[
+            (vec![0x68u8], 5u,
+                "d6d3e02a31a84a8caa9718ed6c2057be09db45e7823eb5079ce7a573a3760f95"),
+            (data.clone(), 55 * 8 + 7,
+                "e2f8edd31496d8309bb06ffdbbf3636ea3ff32507f5744f9d5aa56ba7dfa3f56"),
+            (data.clone(), 56 * 8 + 3,
+                "af1408e3b696957b8f5a071b80c1c3ed9a9b95a400004f1e7a0419556b046c4d")]);
+
+        test_bits_hash(&mut Sha224::new(), &[
+            (vec![0x68u8], 5u, "e3b048552c3c387bcab37f6eb06bb79b96a4aee5ff27f51531a9551c")]);
+
+        test_bits_hash(&mut Sha512::new(), &[
+            (vec![0xb0u8], 5u,
+                "d4ee29a9e90985446b913cf1d1376c836f4be2c1cf3cada0720a6bf4857d886a7ecb3c4e4c0fa8c7f95214e41dc1b0d21b22a84cc03bf8ce4845f34dd5bdbad4"),
+            (data.clone(), 111 * 8 + 7,
+                "ec167442afabfb193980f62c64d36712922ca7cff6b82ff796444a1d4c1c9eef9971ce05f4ec8e2d7ccbb3eb0c9f2176d51f505e69e24c64e45b1d1267557663"),
+            (data.clone(), 112 * 8 + 3,
+                "0ad450b68250242999ff389d75880b820b30f510e352bc033eb9c5b1672535b1ddcbe48a91310f2471c605cdc3ae9408058f8daff911a1d754995df803927c5c"),
+            (data.clone(), 128 * 8,
+                "1dffd5e3adb71d45d2245939665521ae001a317a03720a45732ba1900ca3b8351fc5c9b4ca513eba6f80bc7b1d1fdad4abd13491cb824d61b08d8c0e1561b3f7")]);
+
+        test_bits_hash(&mut Sha384::new(), &[
+            (vec![0x10u8], 5u,
+                "8d17be79e32b6718e07d8a603eb84ba0478f7fcfd1bb93995f7d1149e09143ac1ffcfc56820e469f3878d957a15a3fe4")]);
+
+        test_bits_hash(&mut Sha512Trunc::new(256), &[
+            (vec![0xb0u8], 5u,
+                "e3a97de1c0eba3d55b4b93710674f586ae4c440ec47a2f6bfb64f516ea446ea9")]);
+    }
+
+    #[test]
+    fn test_sha512_trunc_iv() {
+        assert!(sha512_trunc_iv(256) == H512_TRUNC_256);
+        assert!(sha512_trunc_iv(224) == H512_TRUNC_224);
+    }
+
+    #[test]
+    fn test_sha512_trunc() {
+        // The values for 224 and 256 bits are the examples published by NIST.
+        let tests = [
+            (224u, "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa"),
+            (256u, "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23"),
+            (160u, "0a74fe1b43eecbea62182658da8a68b8acef25bf"),
+            (12u, "1ac0"),
+            (504u, "8c43e4bf1cad93067af1ad632ba38bba0b5673bf0129f01a469224c2d981b8ecaa301facf8e392f97efc5997885a1c90cefba70d81892f40267df4fd6fef9a")
+        ];
+
+        for &(t, expected) in tests.iter() {
+            let mut sh = Sha512Trunc::new(t);
+            assert_eq!(sh.output_bits(), t);
+            sh.input_str("abc");
+            assert_eq!(sh.result_str().as_slice(), expected);
+            sh.reset();
+            sh.input_str("abc");
+            assert_eq!(sh.result_str().as_slice(), expected);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_sha512_trunc_384() {
+        Sha512Trunc::new(384);
+    }
+
+    #[test]
+    fn test_clone() {
+        test_digest_clone(&mut Sha512Trunc::new(200));
+        test_digest_clone(&mut Sha512::new());
+        test_digest_clone(&mut Sha384::new());
+        test_digest_clone(&mut Sha512Trunc256::new());
+        test_digest_clone(&mut Sha512Trunc224::new());
+        test_digest_clone(&mut Sha256::new());
+        test_digest_clone(&mut Sha224::new());
+    }
+
+    fn sha256(input: &[u8]) -> [u8, ..32] {
+        let mut out = [0u8, ..32];
+        let mut sh = Sha256::new();
+        sh.input(input);
+        sh.result(out);
+        out
+    }
+
+    #[test]
+    fn test_sha256_multi_lane() {
+        // Lengths around the padding and block boundaries. Each lane gets a different length and
+        // offset into the data, so the lanes finish after different numbers of blocks.
+        let lens = [0u, 1, 55, 56, 63, 64, 65, 119, 120, 128, 200, 1000];
+        let data = Vec::from_fn(1008, |i| (i * 7 + 3) as u8);
+
+        for k in range(0u, lens.len()) {
+            let mut inputs = [data.slice_to(0), ..8];
+            for i in range(0u, 8) {
+                let len = lens[(k + i * 5) % lens.len()];
+                inputs[i] = data.slice(i, i + len);
+            }
+
+            let out = sha256_x8(inputs);
+            for i in range(0u, 8) {
+                assert!(out[i] == sha256(inputs[i]));
+            }
+
+            let out = sha256_x4([inputs[0], inputs[1], inputs[2], inputs[3]]);
+            for i in range(0u, 4) {
+                assert!(out[i] == sha256(inputs[i]));
+            }
+        }
     }
 
     #[test]
@@ -965,6 +1869,16 @@ mod tests {
             64,
             "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
     }
+
+    #[test]
+    fn test_1million_random_sha256_portable() {
+        let mut sh = Sha256::new();
+        sh.engine.use_sha_ni = false;
+        test_digest_1million_random(
+            &mut sh,
+            64,
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+    }
 }
 
 
@@ -973,7 +1887,7 @@ mod tests {
 mod bench {
     use test::Bencher;
     use digest::Digest;
-    use sha2::{Sha256,Sha512};
+    use sha2::{Sha256, Sha512, sha256_x4, sha256_x8};
 
     #[bench]
     pub fn sha256_10(bh: & mut Bencher) {
@@ -1005,6 +1919,49 @@ mod bench {
         bh.bytes = bytes.len() as u64;
     }
 
+    #[bench]
+    pub fn sha256_64b_x8_sequential(bh: & mut Bencher) {
+        let bytes = [1u8, ..64];
+        let mut out = [0u8, ..32];
+        bh.iter( || {
+            for _ in range(0u, 8) {
+                let mut sh = Sha256::new();
+                sh.input(bytes);
+                sh.result(out);
+            }
+        });
+        bh.bytes = 8 * bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha256_x4_64b(bh: & mut Bencher) {
+        let bytes = [1u8, ..64];
+        bh.iter( || {
+            sha256_x4([bytes.as_slice(), ..4])
+        });
+        bh.bytes = 4 * bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha256_x8_64b(bh: & mut Bencher) {
+        let bytes = [1u8, ..64];
+        bh.iter( || {
+            sha256_x8([bytes.as_slice(), ..8])
+        });
+        bh.bytes = 8 * bytes.len() as u64;
+    }
+
+    #[bench]
+    pub fn sha256_portable_64k(bh: & mut Bencher) {
+        let mut sh = Sha256::new();
+        sh.engine.use_sha_ni = false;
+        let bytes = [1u8, ..65536];
+        bh.iter( || {
+            sh.input(bytes);
+        });
+        bh.bytes = bytes.len() as u64;
+    }
+
 
 
     #[bench]
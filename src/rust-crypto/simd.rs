@@ -0,0 +1,130 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+ * SIMD vector types shared by the algorithms that are able to make use of them.
+ */
+
+use std::default::Default;
+
+// Using std::unstable::simd::u32x4 results in issues creating static arrays of u32x4 values.
+// Defining the type here avoids that problem. Additionally, we need to implement various trait from
+// libstd which wouldn't be possible if we used that type directly.
+#[simd]
+#[deriving(Clone, Eq, PartialEq)]
+#[allow(non_camel_case_types)]
+pub struct u32x4(pub u32, pub u32, pub u32, pub u32);
+
+impl u32x4 {
+    /// Create a vector with all 4 lanes set to x
+    pub fn splat(x: u32) -> u32x4 {
+        return u32x4(x, x, x, x);
+    }
+
+    /// Create a vector from the first 4 values of a slice
+    pub fn from_slice(s: &[u32]) -> u32x4 {
+        return u32x4(s[0], s[1], s[2], s[3]);
+    }
+
+    /// Get the values of the 4 lanes
+    pub fn to_array(&self) -> [u32, ..4] {
+        let u32x4(a0, a1, a2, a3) = *self;
+        return [a0, a1, a2, a3];
+    }
+
+    /// Shift the whole 128 bit value left by s bits
+    pub fn lsh(&self, s: uint) -> u32x4 {
+        let u32x4(a0, a1, a2, a3) = *self;
+        return u32x4(
+            a0 << s,
+            (a1 << s) | (a0 >> (32 - s)),
+            (a2 << s) | (a1 >> (32 - s)),
+            (a3 << s) | (a2 >> (32 - s)));
+    }
+
+    /// Shift the whole 128 bit value right by s bits
+    pub fn rsh(&self, s: uint) -> u32x4 {
+        let u32x4(a0, a1, a2, a3) = *self;
+        return u32x4(
+            (a0 >> s) | (a1 << (32 - s)),
+            (a1 >> s) | (a2 << (32 - s)),
+            (a2 >> s) | (a3 << (32 - s)),
+            a3 >> s);
+    }
+
+    /// Rotate each of the 4 u32 values left by s bits
+    pub fn rotate_left(&self, s: uint) -> u32x4 {
+        let s = s as u32;
+        let l = u32x4(s, s, s, s);
+        let r = u32x4(32 - s, 32 - s, 32 - s, 32 - s);
+        return (*self << l) | (*self >> r);
+    }
+
+    /// Rotate the lanes so that lane 1 becomes lane 0: (a0, a1, a2, a3) -> (a1, a2, a3, a0)
+    pub fn rotate_lanes_1(&self) -> u32x4 {
+        let u32x4(a0, a1, a2, a3) = *self;
+        return u32x4(a1, a2, a3, a0);
+    }
+
+    /// Rotate the lanes so that lane 2 becomes lane 0: (a0, a1, a2, a3) -> (a2, a3, a0, a1)
+    pub fn rotate_lanes_2(&self) -> u32x4 {
+        let u32x4(a0, a1, a2, a3) = *self;
+        return u32x4(a2, a3, a0, a1);
+    }
+
+    /// Rotate the lanes so that lane 3 becomes lane 0: (a0, a1, a2, a3) -> (a3, a0, a1, a2)
+    pub fn rotate_lanes_3(&self) -> u32x4 {
+        let u32x4(a0, a1, a2, a3) = *self;
+        return u32x4(a3, a0, a1, a2);
+    }
+}
+
+#[simd]
+#[deriving(Clone, Eq, PartialEq)]
+#[allow(non_camel_case_types)]
+pub struct u32x8(pub u32, pub u32, pub u32, pub u32, pub u32, pub u32, pub u32, pub u32);
+
+impl u32x8 {
+    /// Create a vector with all 8 lanes set to x
+    pub fn splat(x: u32) -> u32x8 {
+        return u32x8(x, x, x, x, x, x, x, x);
+    }
+
+    /// Create a vector from the first 8 values of a slice
+    pub fn from_slice(s: &[u32]) -> u32x8 {
+        return u32x8(s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]);
+    }
+
+    /// Get the values of the 8 lanes
+    pub fn to_array(&self) -> [u32, ..8] {
+        let u32x8(a0, a1, a2, a3, a4, a5, a6, a7) = *self;
+        return [a0, a1, a2, a3, a4, a5, a6, a7];
+    }
+
+    /// Rotate each of the 8 u32 values left by s bits
+    pub fn rotate_left(&self, s: uint) -> u32x8 {
+        let s = s as u32;
+        return (*self << u32x8::splat(s)) | (*self >> u32x8::splat(32 - s));
+    }
+}
+
+impl BitXor<u32x4, u32x4> for u32x4 {
+    fn bitxor(&self, rhs: &u32x4) -> u32x4 {
+        return *self ^ *rhs;
+    }
+}
+
+impl BitAnd<u32x4, u32x4> for u32x4 {
+    fn bitand(&self, rhs: &u32x4) -> u32x4 {
+        return *self & *rhs;
+    }
+}
+
+impl Default for u32x4 {
+    fn default() -> u32x4 {
+        return u32x4(0, 0, 0, 0);
+    }
+}
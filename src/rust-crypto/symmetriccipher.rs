@@ -66,3 +66,42 @@ impl Decryptor for Box<SynchronousStreamCipher> {
         symm_enc_or_dec(self, input, output)
     }
 }
+
+/// A SynchronousStreamCipher whose current state can be duplicated through a trait object. This
+/// is implemented for every cipher that is Clone, so that a boxed cipher returned by one of the
+/// aes functions can be cloned after processing a common prefix.
+pub trait CloneableStreamCipher: SynchronousStreamCipher {
+    fn box_clone(&self) -> Box<CloneableStreamCipher>;
+}
+
+impl <T: SynchronousStreamCipher + Clone + Send> CloneableStreamCipher for T {
+    fn box_clone(&self) -> Box<CloneableStreamCipher> {
+        box self.clone() as Box<CloneableStreamCipher>
+    }
+}
+
+impl Clone for Box<CloneableStreamCipher> {
+    fn clone(&self) -> Box<CloneableStreamCipher> {
+        (**self).box_clone()
+    }
+}
+
+impl SynchronousStreamCipher for Box<CloneableStreamCipher> {
+    fn process(&mut self, input: &[u8], output: &mut [u8]) {
+        (**self).process(input, output);
+    }
+}
+
+impl Encryptor for Box<CloneableStreamCipher> {
+    fn encrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
+
+impl Decryptor for Box<CloneableStreamCipher> {
+    fn decrypt(&mut self, input: &mut RefReadBuffer, output: &mut RefWriteBuffer, _: bool)
+            -> Result<BufferResult, SymmetricCipherError> {
+        symm_enc_or_dec(self, input, output)
+    }
+}
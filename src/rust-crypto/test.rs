@@ -8,10 +8,12 @@
 #![feature(macro_rules)]
 #![feature(simd)]
 
+extern crate num;
 extern crate serialize;
 extern crate test;
 
 pub mod aes;
+pub mod aeskw;
 pub mod aessafe;
 pub mod bcrypt;
 pub mod bcrypt_pbkdf;
@@ -20,17 +22,30 @@ pub mod blowfish;
 pub mod buffer;
 pub mod chacha20;
 mod cryptoutil;
+pub mod curve25519;
+pub mod dh;
 pub mod digest;
+pub mod domain_params;
+pub mod drbg;
+pub mod ec;
+pub mod ecdsa;
+pub mod ed25519;
+pub mod ghash;
 pub mod hmac;
 pub mod mac;
 pub mod md5;
+pub mod montgomery;
 pub mod pbkdf2;
 pub mod poly1305;
+pub mod prime_rng;
 pub mod rc4;
+pub mod rsa;
 pub mod salsa20;
 pub mod scrypt;
+pub mod secp256k1;
 pub mod sha1;
 pub mod sha2;
+mod simd;
 pub mod symmetriccipher;
 pub mod util;
 
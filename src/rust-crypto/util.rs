@@ -28,6 +28,73 @@ pub fn supports_aesni() -> bool {
     return (flags & 0x02000000) != 0;
 }
 
+/// Check whether the processor supports the PCLMULQDQ (carry-less multiplication) instruction.
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn supports_pclmulqdq() -> bool {
+    let mut flags: u32;
+    unsafe {
+        asm!(
+        "
+        mov $$1, %eax;
+        cpuid;
+        mov %ecx, $0;
+        "
+        : "=r" (flags) // output
+        : // input
+        : "eax", "ebx", "ecx", "edx" // clobbers
+        )
+        // See supports_aesni() - this avoids running out of registers on 32-bit targets.
+        asm!("")
+    }
+
+    return (flags & 0x00000002) != 0;
+}
+
+/// Check whether the processor supports the SHA extensions (SHA-NI).
+#[cfg(target_arch = "x86")]
+#[cfg(target_arch = "x86_64")]
+pub fn supports_sha_ni() -> bool {
+    let mut max_leaf: u32;
+    let mut flags: u32;
+    unsafe {
+        asm!(
+        "
+        xor %eax, %eax;
+        cpuid;
+        mov %eax, $0;
+        "
+        : "=r" (max_leaf) // output
+        : // input
+        : "eax", "ebx", "ecx", "edx" // clobbers
+        )
+        // See supports_aesni() - this avoids running out of registers on 32-bit targets.
+        asm!("")
+    }
+
+    // The SHA extensions are reported in leaf 7, which older processors don't have.
+    if max_leaf < 7 {
+        return false;
+    }
+
+    unsafe {
+        asm!(
+        "
+        mov $$7, %eax;
+        xor %ecx, %ecx;
+        cpuid;
+        mov %ebx, $0;
+        "
+        : "=r" (flags) // output
+        : // input
+        : "eax", "ebx", "ecx", "edx" // clobbers
+        )
+        asm!("")
+    }
+
+    return (flags & 0x20000000) != 0;
+}
+
 #[cfg(target_arch = "x86")]
 #[cfg(target_arch = "x86_64")]
 #[inline(never)]